@@ -0,0 +1,21 @@
+//! Similar to the simple example, except now we export the results to every supported format for review elsewhere
+
+use rustnao::{Export, ExportFormat, HandlerBuilder, Sauce};
+
+fn main() {
+	let data = std::fs::read_to_string("config.json").expect("Couldn't read file.");
+	let json: serde_json::Value = serde_json::from_str(data.as_str()).expect("JSON not well formatted.");
+	let api_key = json["api_key"].as_str();
+	let file = "https://i.imgur.com/W42kkKS.jpg";
+
+	if let Some(key) = api_key {
+		let handle = HandlerBuilder::default().api_key(key).build();
+		let result: Vec<Sauce> = handle.get_sauce(file, None, None).unwrap();
+		println!("{}", result.to_markdown().unwrap());
+		for format in [ExportFormat::Csv, ExportFormat::Ndjson, ExportFormat::Markdown, ExportFormat::Html].iter() {
+			let path = format!("results.{}", format.extension());
+			result.export_to_file(path.as_str(), *format).unwrap();
+			println!("Wrote {}", path);
+		}
+	}
+}
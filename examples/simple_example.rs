@@ -1,6 +1,6 @@
 //! A simple example, assuming you had a config.json file that had your api key.
 
-use rustnao::{HandlerBuilder, Sauce};
+use rustnao::{HandlerBuilder, Sauce, ToTable};
 
 fn main() {
 	let data = std::fs::read_to_string("config.json").expect("Couldn't read file.");
@@ -11,8 +11,6 @@ fn main() {
 	if let Some(key) = api_key {
 		let handle = HandlerBuilder::default().api_key(key).build();
 		let result: Vec<Sauce> = handle.get_sauce(file, None, None).unwrap();
-		for i in result {
-			println!("{:?}", i);
-		}
+		println!("{}", result.to_table());
 	}
 }
@@ -0,0 +1,22 @@
+//! Fuzzes the parsing of result headers, which should never panic no matter what SauceNAO sends.
+//! Run with ``cargo fuzz run result_header`` from the root of the repository.
+
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+	let input = String::from_utf8_lossy(data);
+	let mut parts = input.splitn(2, '\n');
+	let index_name = parts.next().unwrap_or("");
+	let similarity = parts.next().unwrap_or("");
+	let response = serde_json::json!({
+		"header": { "status": 0, "short_limit": "4", "long_limit": "100" },
+		"results": [{
+			"header": { "similarity": similarity, "thumbnail": "", "index_id": data.len(), "index_name": index_name },
+			"data": { "ext_urls": [] }
+		}]
+	});
+
+	rustnao::fuzzing::process_response(response.to_string().as_str());
+	rustnao::fuzzing::process_response(input.as_ref());
+});
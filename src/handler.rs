@@ -1,17 +1,81 @@
 //! Handler module of rustnao.  The handler for the SauceNAO API calls.
 
+#[macro_use]
+mod trace;
+
+#[macro_use]
+mod metrics;
+#[cfg(feature = "metrics")]
+pub use metrics::Metrics;
+
 mod error;
 pub use error::{ErrType, Error, Result};
+use trace::{FilterReason, FilterStats};
 
 mod constants;
 
 mod sauce;
-pub use sauce::Sauce;
+pub use sauce::{Sauce, SauceBuilder, SauceResponse};
+
+mod source_ref;
+pub use source_ref::SourceRef;
+
+mod author;
+pub use author::Author;
+
+mod cluster;
+pub use cluster::{normalize_url, SauceGroup, ToGroups};
+
+mod rank;
+pub use rank::{RankReason, RankSources, RankedSauce, SourcePolicy};
+
+mod export;
+pub use export::{Export, ExportFormat};
+
+mod table;
+pub use table::ToTable;
+
+mod header;
 
 mod deserialize;
-use deserialize::SauceResult;
+use deserialize::{Lenient, ResultEntry, SauceResult};
+
+mod client;
+use client::{Client, ClientConfig};
 
-use std::cell::Cell;
+mod cassette;
+pub use cassette::Cassette;
+
+mod keys;
+use keys::KeyPool;
+
+mod quota;
+pub use quota::{AccountType, FileQuotaStore, QuotaRecord, QuotaStatus, QuotaStore};
+
+mod listener;
+pub use listener::{ResultHeader, SearchListener};
+
+mod search;
+pub use search::{FakeSearch, ReverseImageSearch, SearchFuture};
+
+mod aggregate;
+pub use aggregate::MultiSearch;
+
+mod fallback;
+pub use fallback::{FallbackChain, FallbackResult, FallbackStep};
+
+#[cfg(any(feature = "ascii2d", feature = "iqdb", feature = "tracemoe"))]
+mod engines;
+#[cfg(feature = "ascii2d")]
+pub use engines::Ascii2d;
+#[cfg(feature = "iqdb")]
+pub use engines::Iqdb;
+#[cfg(feature = "tracemoe")]
+pub use engines::TraceMoe;
+
+use std::cell::{Cell, RefCell};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use url::Url;
 
 /// A builder to create a Handler for RustNAO usage.
@@ -23,6 +87,7 @@ use url::Url;
 #[derive(Default, Debug, Clone)]
 pub struct HandlerBuilder {
 	api_key: Option<String>,
+	api_keys: Vec<String>,
 	testmode: Option<bool>,
 	db_mask: Option<Vec<u32>>,
 	db_mask_i: Option<Vec<u32>>,
@@ -30,6 +95,18 @@ pub struct HandlerBuilder {
 	num_results: Option<u32>,
 	min_similarity: Option<f64>,
 	empty_filter_enabled: Option<bool>,
+	timeout: Option<Duration>,
+	connect_timeout: Option<Duration>,
+	proxy: Option<String>,
+	proxy_credentials: Option<(String, String)>,
+	user_agent: Option<String>,
+	headers: Vec<(String, String)>,
+	api_url: Option<String>,
+	cassette: Option<Cassette>,
+	quota_store: Option<Arc<dyn QuotaStore>>,
+	listeners: Vec<Arc<dyn SearchListener>>,
+	#[cfg(feature = "metrics")]
+	metrics: Option<Metrics>,
 }
 
 impl HandlerBuilder {
@@ -48,6 +125,23 @@ impl HandlerBuilder {
 		self
 	}
 
+	/// Sets a pool of API keys used for searches for the Handler, which is added to the key set by ``api_key`` if there is one.  Each search uses the key
+	/// with the most searches left, and the limits of every key are tracked separately.  Keys that SauceNAO rejects are no longer used, and the search is
+	/// retried with another key, but the last key that is left is always kept and tried again by the next search.
+	///
+	/// ### Arguments
+	/// * api_keys - A slice of string references representing your API keys.
+	///
+	/// ### Examples
+	/// ```
+	/// use rustnao::HandlerBuilder;
+	/// let handle = HandlerBuilder::default().api_keys(&["first_api_key", "second_api_key"]).build();
+	/// ```
+	pub fn api_keys(&mut self, api_keys: &[&str]) -> &mut HandlerBuilder {
+		self.api_keys = api_keys.iter().map(|api_key| api_key.to_string()).collect();
+		self
+	}
+
 	/// Sets whether testmode should be enabled on searches for the Handler.  If this is on, then each index will output at most one result.  If this is unset then it is set to off by default.
 	///
 	/// ### Arguments
@@ -153,6 +247,209 @@ impl HandlerBuilder {
 		self
 	}
 
+	/// Sets how long a search may take as a whole for the Handler, including uploading the image and reading the response.  You can change this per-search.
+	/// If this is not set, searches never time out.
+	///
+	/// ### Arguments
+	/// * timeout - A Duration representing how long a search may take before it fails with a timeout error.
+	///
+	/// ### Examples
+	/// ```
+	/// use rustnao::HandlerBuilder;
+	/// use std::time::Duration;
+	/// let handle = HandlerBuilder::default().timeout(Duration::from_secs(30)).build();
+	/// ```
+	pub fn timeout(&mut self, timeout: Duration) -> &mut HandlerBuilder {
+		self.timeout = Some(timeout);
+		self
+	}
+
+	/// Sets how long to wait for a connection to SauceNAO to be established for the Handler.  If this is not set, the default of curl is used.
+	///
+	/// ### Arguments
+	/// * connect_timeout - A Duration representing how long connecting may take before the search fails with a timeout error.
+	///
+	/// ### Examples
+	/// ```
+	/// use rustnao::HandlerBuilder;
+	/// use std::time::Duration;
+	/// let handle = HandlerBuilder::default().connect_timeout(Duration::from_secs(5)).build();
+	/// ```
+	pub fn connect_timeout(&mut self, connect_timeout: Duration) -> &mut HandlerBuilder {
+		self.connect_timeout = Some(connect_timeout);
+		self
+	}
+
+	/// Sets a proxy that every request of the Handler is sent through.  The protocol of the proxy is taken from the scheme of the URL, and can be
+	/// ``http``, ``https``, ``socks4``, ``socks4a``, ``socks5`` or ``socks5h``.  If this is not set, the ``http_proxy`` and ``https_proxy`` environment
+	/// variables are used if present.
+	///
+	/// An invalid proxy URL is reported as an error by the first search.
+	///
+	/// ### Arguments
+	/// * proxy - A string reference representing the URL of the proxy, such as ``http://proxy.example.com:3128``.  Credentials may be included in it.
+	///
+	/// ### Examples
+	/// ```
+	/// use rustnao::HandlerBuilder;
+	/// let handle = HandlerBuilder::default().proxy("socks5://127.0.0.1:1080").build();
+	/// ```
+	pub fn proxy(&mut self, proxy: &str) -> &mut HandlerBuilder {
+		self.proxy = Some(proxy.to_string());
+		self
+	}
+
+	/// Sets the username and password used to authenticate with the proxy of the Handler.  These replace any credentials included in the proxy URL.
+	///
+	/// ### Arguments
+	/// * username - A string reference representing the username for the proxy.
+	/// * password - A string reference representing the password for the proxy.
+	///
+	/// ### Examples
+	/// ```
+	/// use rustnao::HandlerBuilder;
+	/// let handle = HandlerBuilder::default()
+	///     .proxy("http://proxy.example.com:3128")
+	///     .proxy_credentials("username", "password")
+	///     .build();
+	/// ```
+	pub fn proxy_credentials(&mut self, username: &str, password: &str) -> &mut HandlerBuilder {
+		self.proxy_credentials = Some((username.to_string(), password.to_string()));
+		self
+	}
+
+	/// Sets the User-Agent sent with every request of the Handler.  If this is not set, the default User-Agent of curl is used.
+	///
+	/// ### Arguments
+	/// * user_agent - A string reference representing the User-Agent.
+	///
+	/// ### Examples
+	/// ```
+	/// use rustnao::HandlerBuilder;
+	/// let handle = HandlerBuilder::default().user_agent("my-build-farm/1.0").build();
+	/// ```
+	pub fn user_agent(&mut self, user_agent: &str) -> &mut HandlerBuilder {
+		self.user_agent = Some(user_agent.to_string());
+		self
+	}
+
+	/// Adds a header that is sent with every request of the Handler.  This can be called multiple times, and headers with the same name are all sent.
+	///
+	/// An invalid header name or value is reported as an error by the first search.
+	///
+	/// ### Arguments
+	/// * name - A string reference representing the name of the header.
+	/// * value - A string reference representing the value of the header.
+	///
+	/// ### Examples
+	/// ```
+	/// use rustnao::HandlerBuilder;
+	/// let handle = HandlerBuilder::default().header("X-Request-Source", "build-farm").build();
+	/// ```
+	pub fn header(&mut self, name: &str, value: &str) -> &mut HandlerBuilder {
+		self.headers.push((name.to_string(), value.to_string()));
+		self
+	}
+
+	/// Sets the URL that searches are sent to, instead of SauceNAO's search API.  This is useful for testing against a mock server, or for going through
+	/// a mirror or gateway that forwards requests to SauceNAO.
+	///
+	/// An invalid URL is reported as an error by the first search.
+	///
+	/// ### Arguments
+	/// * api_url - A string reference representing the URL of the search API.
+	///
+	/// ### Examples
+	/// ```
+	/// use rustnao::HandlerBuilder;
+	/// let handle = HandlerBuilder::default().api_url("http://127.0.0.1:8080/search.php").build();
+	/// ```
+	pub fn api_url(&mut self, api_url: &str) -> &mut HandlerBuilder {
+		self.api_url = Some(api_url.to_string());
+		self
+	}
+
+	/// Sets a cassette that the Handler records the responses of SauceNAO to, or replays responses from instead of sending requests, depending on how
+	/// the cassette was created.  This is meant for tests around real responses.
+	///
+	/// ### Arguments
+	/// * cassette - The cassette to record to or replay from.
+	///
+	/// ### Examples
+	/// ```
+	/// use rustnao::{Cassette, HandlerBuilder};
+	/// let handle = HandlerBuilder::default().api_key("your_api_key").cassette(Cassette::record("./target/pixiv.json")).build();
+	/// ```
+	pub fn cassette(&mut self, cassette: Cassette) -> &mut HandlerBuilder {
+		self.cassette = Some(cassette);
+		self
+	}
+
+	/// Sets a store that the search limits of every API key are saved to after each search and loaded from when the Handler is built, so that a restarted
+	/// process does not assume it has its full limits.  If this is not set, the limits are assumed to be 12 searches every 30 seconds and 200 every day
+	/// until the first search.
+	///
+	/// Limits saved more than a day ago are ignored.  Problems with loading or saving are reported as warnings of a ``SauceResponse`` instead of failing,
+	/// those with loading in the first one.
+	///
+	/// ### Arguments
+	/// * quota_store - The store to use, such as a ``FileQuotaStore``.
+	///
+	/// ### Examples
+	/// ```
+	/// use rustnao::{FileQuotaStore, HandlerBuilder};
+	/// let handle = HandlerBuilder::default().api_key("your_api_key").quota_store(FileQuotaStore::new("./quota.json")).build();
+	/// ```
+	pub fn quota_store<S: QuotaStore + 'static>(&mut self, quota_store: S) -> &mut HandlerBuilder {
+		self.quota_store = Some(Arc::new(quota_store));
+		self
+	}
+
+	/// Adds a listener that is notified of the requests, responses, rate limits, retries and errors of every search of the Handler.  This can be called
+	/// multiple times to add several listeners, which are called in the order they were added.
+	///
+	/// ### Arguments
+	/// * listener - The listener to add.
+	///
+	/// ### Examples
+	/// ```
+	/// use rustnao::{HandlerBuilder, ResultHeader, SearchListener};
+	///
+	/// #[derive(Debug)]
+	/// struct LimitLogger;
+	///
+	/// impl SearchListener for LimitLogger {
+	///     fn response_received(&self, header: &ResultHeader) {
+	///         println!("{:?} searches left today", header.long_remaining);
+	///     }
+	/// }
+	///
+	/// let handle = HandlerBuilder::default().listener(LimitLogger).build();
+	/// ```
+	pub fn listener<L: SearchListener + 'static>(&mut self, listener: L) -> &mut HandlerBuilder {
+		self.listeners.push(Arc::new(listener));
+		self
+	}
+
+	/// Sets the metrics the Handler records its searches to.  Clones of a Metrics share their values, so the same metrics can be given to several
+	/// Handlers and exported together.  Only available with the ``metrics`` feature.
+	///
+	/// ### Arguments
+	/// * metrics - The metrics to record to.
+	///
+	/// ### Examples
+	/// ```
+	/// use rustnao::{HandlerBuilder, Metrics};
+	/// let metrics = Metrics::new();
+	/// let handle = HandlerBuilder::default().api_key("your_api_key").metrics(metrics.clone()).build();
+	/// let exported = metrics.to_prometheus();
+	/// ```
+	#[cfg(feature = "metrics")]
+	pub fn metrics(&mut self, metrics: Metrics) -> &mut HandlerBuilder {
+		self.metrics = Some(metrics);
+		self
+	}
+
 	/// Builds the HandlerBuilder, returning a Handler that can be used to search.
 	///
 	/// ### Examples
@@ -161,11 +458,11 @@ impl HandlerBuilder {
 	/// let handle = HandlerBuilder::default().api_key("your_api_key").db(999).num_results(50).build();
 	/// ```
 	pub fn build(&self) -> Handler {
-		let mut api_key = "";
-		match &self.api_key {
-			Some(x) => api_key = x.as_str(),
-			None => (),
+		let mut api_keys = Vec::new();
+		if let Some(x) = &self.api_key {
+			api_keys.push(x.clone());
 		}
+		api_keys.extend(self.api_keys.iter().cloned());
 
 		let mut testmode = None;
 		if let Some(x) = self.testmode {
@@ -174,10 +471,28 @@ impl HandlerBuilder {
 
 		let mut num_results = None;
 		if let Some(x) = self.num_results {
-			num_results = Some(x as u32);
+			num_results = Some(x);
 		}
 
-		let result = Handler::new(api_key, testmode, self.db_mask.clone(), self.db_mask_i.clone(), self.db, num_results);
+		let mut result = Handler::new(api_keys, testmode, self.db_mask.clone(), self.db_mask_i.clone(), self.db, num_results);
+		result.timeout = self.timeout;
+		result.client_config = ClientConfig {
+			connect_timeout: self.connect_timeout,
+			proxy: self.proxy.clone(),
+			proxy_credentials: self.proxy_credentials.clone(),
+			user_agent: self.user_agent.clone(),
+			headers: self.headers.clone(),
+			follow_redirects: false,
+		};
+		result.api_url = self.api_url.clone();
+		result.cassette = self.cassette.clone();
+		result.quota_store = self.quota_store.clone();
+		result.listeners = self.listeners.clone();
+		#[cfg(feature = "metrics")]
+		{
+			result.metrics = self.metrics.clone();
+		}
+		result.load_quota();
 		if let Some(x) = self.min_similarity {
 			result.set_min_similarity(x);
 		}
@@ -200,19 +515,26 @@ impl HandlerBuilder {
 /// ```
 #[derive(Debug, Clone)]
 pub struct Handler {
-	api_key: String,
+	keys: RefCell<KeyPool>,
 	output_type: i32,
 	testmode: Option<u32>,
 	db_mask: Option<Vec<u32>>,
 	db_mask_i: Option<Vec<u32>>,
 	db: Option<u32>,
 	num_results: Option<u32>,
-	short_limit: Cell<u32>,
-	long_limit: Cell<u32>,
-	short_left: Cell<u32>,
-	long_left: Cell<u32>,
 	min_similarity: Cell<f64>,
 	empty_filter_enabled: Cell<bool>,
+	// Problems with loading the quota state when the Handler was built, which are reported with the first response
+	load_warnings: RefCell<Vec<String>>,
+	timeout: Option<Duration>,
+	client_config: ClientConfig,
+	client: RefCell<Option<Client>>,
+	api_url: Option<String>,
+	cassette: Option<Cassette>,
+	quota_store: Option<Arc<dyn QuotaStore>>,
+	listeners: Vec<Arc<dyn SearchListener>>,
+	#[cfg(feature = "metrics")]
+	metrics: Option<Metrics>,
 }
 
 impl Handler {
@@ -306,29 +628,33 @@ impl Handler {
 	}
 
 	/// Generates a url from the given image url
-	fn generate_url(&self, image_path: &str, num_results: Option<u32>) -> Result<String> {
-		let mut request_url = Url::parse(constants::API_URL)?;
-		request_url.query_pairs_mut().append_pair("api_key", self.api_key.as_str());
+	fn generate_url(&self, api_key: &str, image_path: &str, num_results: Option<u32>, all_databases: bool) -> Result<String> {
+		let mut request_url = Url::parse(self.api_url.as_deref().unwrap_or(constants::API_URL))?;
+		request_url.query_pairs_mut().append_pair("api_key", api_key);
 		request_url
 			.query_pairs_mut()
 			.append_pair("output_type", self.output_type.to_string().as_str());
 
-		if let Some(val) = self.db {
-			request_url.query_pairs_mut().append_pair("db", val.to_string().as_str());
-		}
-
-		if let Some(val) = &self.db_mask {
-			if !val.is_empty() {
-				request_url
-					.query_pairs_mut()
-					.append_pair("dbmask", self.generate_bitmask(val.clone()).to_string().as_str());
+		if all_databases {
+			// The masks are left out too, as they would narrow the search down again
+			request_url.query_pairs_mut().append_pair("db", "999");
+		} else {
+			if let Some(val) = self.db {
+				request_url.query_pairs_mut().append_pair("db", val.to_string().as_str());
 			}
-		}
-		if let Some(val) = &self.db_mask_i {
-			if !val.is_empty() {
-				request_url
-					.query_pairs_mut()
-					.append_pair("dbmaski", self.generate_bitmask(val.clone()).to_string().as_str());
+			if let Some(val) = &self.db_mask {
+				if !val.is_empty() {
+					request_url
+						.query_pairs_mut()
+						.append_pair("dbmask", self.generate_bitmask(val.clone()).to_string().as_str());
+				}
+			}
+			if let Some(val) = &self.db_mask_i {
+				if !val.is_empty() {
+					request_url
+						.query_pairs_mut()
+						.append_pair("dbmaski", self.generate_bitmask(val.clone()).to_string().as_str());
+				}
 			}
 		}
 
@@ -359,29 +685,78 @@ impl Handler {
 			request_url.query_pairs_mut().append_pair("url", image_path);
 		}
 
-		Ok(request_url.into_string())
+		debug!(parameters = %trace::redacted_query(&request_url), "generated request URL");
+		Ok(request_url.into())
 	}
 
 	fn new(
-		api_key: &str, testmode: Option<u32>, db_mask: Option<Vec<u32>>, db_mask_i: Option<Vec<u32>>, db: Option<u32>, num_results: Option<u32>,
+		api_keys: Vec<String>, testmode: Option<u32>, db_mask: Option<Vec<u32>>, db_mask_i: Option<Vec<u32>>, db: Option<u32>,
+		num_results: Option<u32>,
 	) -> Handler {
 		Handler {
-			api_key: api_key.to_string(),
+			keys: RefCell::new(KeyPool::new(api_keys)),
 			output_type: 2, // This is set to 2 by default, as we need a JSON reply
 			testmode,
 			db_mask,
 			db_mask_i,
 			db,
 			num_results,
-			short_limit: Cell::new(12),
-			long_limit: Cell::new(200),
-			short_left: Cell::new(12),
-			long_left: Cell::new(200),
 			min_similarity: Cell::new(0.0),
 			empty_filter_enabled: Cell::new(false),
+			load_warnings: RefCell::new(Vec::new()),
+			timeout: None,
+			client_config: ClientConfig::default(),
+			client: RefCell::new(None),
+			api_url: None,
+			cassette: None,
+			quota_store: None,
+			listeners: Vec::new(),
+			#[cfg(feature = "metrics")]
+			metrics: None,
+		}
+	}
+
+	/// Loads the limits of every API key from the quota store, if there is one.
+	fn load_quota(&self) {
+		let store = match &self.quota_store {
+			Some(store) => store,
+			None => return,
+		};
+		let mut keys = self.keys.borrow_mut();
+		for index in 0..keys.keys().len() {
+			let key = keys.get_mut(index);
+			match store.load(quota::key_id(key.api_key.as_str()).as_str()) {
+				Ok(Some(record)) => key.restore(&record),
+				Ok(None) => {}
+				Err(err) => self.load_warnings.borrow_mut().push(format!("could not load quota state: {}", err)),
+			}
+			record_metrics!(self, |metrics| metrics.record_quota(keys.get(index)));
 		}
 	}
 
+	/// Saves the limits of an API key to the quota store, if there is one, returning a warning if that failed.
+	fn save_quota(&self, key: usize) -> Option<String> {
+		let store = self.quota_store.as_ref()?;
+		let (key_id, record) = {
+			let keys = self.keys.borrow();
+			let state = keys.get(key);
+			(quota::key_id(state.api_key.as_str()), state.record()?)
+		};
+		store
+			.save(key_id.as_str(), &record)
+			.err()
+			.map(|err| format!("could not save quota state: {}", err))
+	}
+
+	/// Returns the HTTP client of the Handler, creating it on first use.
+	fn client(&self) -> Result<Client> {
+		let mut client = self.client.borrow_mut();
+		if client.is_none() {
+			*client = Some(Client::new(&self.client_config, self.cassette.clone())?);
+		}
+		Ok(client.clone().unwrap())
+	}
+
 	/// Sets the minimum similarity threshold for ``get_sauce``.  By default this is 0.0.
 	/// ## Arguments
 	/// * `min_similarity` - Represents the minimum similarity threshold (in percent) you wish to set.  It can be any value that can convert to a f64.  This includes f32s, i16s, i32s, and i8s.
@@ -410,7 +785,7 @@ impl Handler {
 		self.empty_filter_enabled.set(enabled);
 	}
 
-	/// Gets the current short limit as an i32.  By default this is 12.
+	/// Gets the current short limit as an i32.  By default this is 12.  If there are several API keys, this is the total of the keys that are still used.
 	///
 	/// ## Example
 	/// ```
@@ -419,10 +794,10 @@ impl Handler {
 	/// println!("{}", handle.get_short_limit());
 	/// ```
 	pub fn get_short_limit(&self) -> u32 {
-		self.short_limit.get()
+		self.total_of(|key| key.short_limit)
 	}
 
-	/// Gets the current long limit as an i32.  By default this is 200.
+	/// Gets the current long limit as an i32.  By default this is 200.  If there are several API keys, this is the total of the keys that are still used.
 	///
 	/// ## Example
 	/// ```
@@ -431,10 +806,10 @@ impl Handler {
 	/// println!("{}", handle.get_long_limit());
 	/// ```
 	pub fn get_long_limit(&self) -> u32 {
-		self.long_limit.get()
+		self.total_of(|key| key.long_limit)
 	}
 
-	/// Gets the current remaining short limit as an i32.
+	/// Gets the current remaining short limit as an i32.  If there are several API keys, this is the total of the keys that are still used.
 	///
 	/// ## Example
 	/// ```
@@ -443,10 +818,10 @@ impl Handler {
 	/// println!("{}", handle.get_current_short_limit());
 	/// ```
 	pub fn get_current_short_limit(&self) -> u32 {
-		self.short_left.get()
+		self.total_of(|key| key.short_left)
 	}
 
-	/// Gets the current remaining long limit as an i32.
+	/// Gets the current remaining long limit as an i32.  If there are several API keys, this is the total of the keys that are still used.
 	///
 	/// ## Example
 	/// ```
@@ -455,94 +830,236 @@ impl Handler {
 	/// println!("{}", handle.get_current_long_limit());
 	/// ```
 	pub fn get_current_long_limit(&self) -> u32 {
-		self.long_left.get()
+		self.total_of(|key| key.long_left)
 	}
 
-	fn is_valid_min_sim(&self, min_similarity: Option<f64>) -> bool {
-		if let Some(min_similarity) = min_similarity {
-			if min_similarity > 100.0 || min_similarity < 0.0 {
-				return false;
-			}
-		}
+	/// Gets the API keys that SauceNAO rejected, and which are no longer used for searches.  The last key that is left is never quarantined, so this
+	/// is always empty for a Handler with a single key.
+	///
+	/// ## Example
+	/// ```
+	/// use rustnao::HandlerBuilder;
+	/// let handle = HandlerBuilder::default().api_keys(&["first_api_key", "second_api_key"]).build();
+	/// assert!(handle.get_quarantined_api_keys().is_empty());
+	/// ```
+	pub fn get_quarantined_api_keys(&self) -> Vec<String> {
+		self.keys
+			.borrow()
+			.keys()
+			.iter()
+			.filter(|key| key.quarantined)
+			.map(|key| key.api_key.clone())
+			.collect()
+	}
 
-		true
+	/// Gets a snapshot of the search limits of the API key that will be used for the next search, including estimates of when they reset and the type of
+	/// the account.
+	///
+	/// ## Example
+	/// ```
+	/// use rustnao::HandlerBuilder;
+	/// let handle = HandlerBuilder::default().api_key("your_api_key").build();
+	/// let status = handle.get_quota_status();
+	/// assert_eq!(status.short_remaining, 12);
+	/// assert_eq!(status.short_reset_at, None);
+	/// ```
+	pub fn get_quota_status(&self) -> QuotaStatus {
+		let keys = self.keys.borrow();
+		keys.get(keys.best()).status()
 	}
 
-	fn is_valid_num_res(&self, num_results: Option<u32>) -> bool {
-		if let Some(num_results) = num_results {
-			if num_results > 999 {
-				return false;
+	/// Gets a snapshot of the search limits of every API key, in the order they were given to the HandlerBuilder.
+	///
+	/// ## Example
+	/// ```
+	/// use rustnao::HandlerBuilder;
+	/// let handle = HandlerBuilder::default().api_keys(&["first_api_key", "second_api_key"]).build();
+	/// assert_eq!(handle.get_quota_statuses().len(), 2);
+	/// ```
+	pub fn get_quota_statuses(&self) -> Vec<QuotaStatus> {
+		self.keys.borrow().keys().iter().map(|key| key.status()).collect()
+	}
+
+	/// Blocks until at least the given number of searches can be made, going by the estimated search limits of every API key that is still used.
+	/// ## Arguments
+	/// * ``searches`` - A u32 representing the number of searches that should be available.
+	///
+	/// ## Example
+	/// ```
+	/// use rustnao::HandlerBuilder;
+	/// let handle = HandlerBuilder::default().api_key("your_api_key").build();
+	/// handle.wait_for_searches(1).unwrap();
+	/// handle.get_sauce("./tests/test.jpg", None, None);
+	/// ```
+	///
+	/// ## Errors
+	/// If more searches are requested than the limits of the API keys ever allow, an error of the kind ``ErrType::InvalidParameters`` is returned.  If
+	/// every API key has been rejected, an error of the kind ``ErrType::InvalidApiKey`` is returned.
+	pub fn wait_for_searches(&self, searches: u32) -> Result<()> {
+		async_std::task::block_on(self.async_wait_for_searches(searches))
+	}
+
+	/// Asynchronously waits until at least the given number of searches can be made.  Otherwise identical to ``wait_for_searches(...)``.
+	/// ## Arguments
+	/// * ``searches`` - A u32 representing the number of searches that should be available.
+	///
+	/// ## Errors
+	/// If more searches are requested than the limits of the API keys ever allow, an error of the kind ``ErrType::InvalidParameters`` is returned.  If
+	/// every API key has been rejected, an error of the kind ``ErrType::InvalidApiKey`` is returned.
+	pub async fn async_wait_for_searches(&self, searches: u32) -> Result<()> {
+		loop {
+			let next_reset = {
+				let keys = self.keys.borrow();
+				if keys.keys().iter().all(|key| key.quarantined) {
+					return Err(Error::from(ErrType::InvalidApiKey(
+						"every API key has been rejected by SauceNAO".to_string(),
+					)));
+				}
+				let now = Instant::now();
+				let available = keys.keys().iter().map(|key| key.available(now)).fold(0, u32::saturating_add);
+				if available >= searches {
+					return Ok(());
+				}
+				let most = keys
+					.keys()
+					.iter()
+					.filter(|key| !key.quarantined)
+					.map(|key| key.short_limit.min(key.long_limit))
+					.fold(0, u32::saturating_add);
+				if searches > most {
+					return Err(Error::invalid_parameter(format!(
+						"{} searches were requested, but at most {} can be made at once",
+						searches, most
+					)));
+				}
+				keys.next_reset()
+			};
+			match next_reset {
+				Some(reset) => async_std::task::sleep(reset.saturating_duration_since(Instant::now())).await,
+				None => return Ok(()),
 			}
 		}
+	}
 
-		true
+	/// Sums a value over the API keys that are not quarantined.
+	fn total_of<F: Fn(&keys::KeyState) -> u32>(&self, value: F) -> u32 {
+		self.keys
+			.borrow()
+			.keys()
+			.iter()
+			.filter(|key| !key.quarantined)
+			.map(value)
+			.fold(0, u32::saturating_add)
 	}
 
-	fn process_results(&self, returned_sauce: SauceResult, min_similarity: Option<f64>) -> Result<Vec<Sauce>> {
+	fn process_results(&self, returned_sauce: SauceResult, min_similarity: Option<f64>, key: usize) -> Result<SauceResponse> {
 		let mut ret_sauce: Vec<Sauce> = Vec::new();
+		let mut warnings = returned_sauce.warnings();
 
-		if returned_sauce.header.status >= 0 {
+		// Without a status there is no telling whether the search succeeded, so it is not taken as a success
+		let status = match &returned_sauce.header.status {
+			Lenient::Value(status) => *status,
+			Lenient::Missing => return Err(Error::invalid_serde("the response has no header.status")),
+			Lenient::Unrecognised(value) => return Err(Error::invalid_serde(format!("unrecognised value for header.status: {}", value))),
+		};
+		// A positive status is a server side error, but any results that were still returned are used
+		let has_results = returned_sauce.results.as_ref().is_some_and(|res| !res.is_empty());
+		if status == 0 || (status > 0 && has_results) {
 			// Update non-sauce fields
-			self.short_left.set(returned_sauce.header.short_remaining);
-			self.long_left.set(returned_sauce.header.long_remaining);
-			self.short_limit.set(returned_sauce.header.short_limit.parse()?);
-			self.long_limit.set(returned_sauce.header.long_limit.parse()?);
+			self.keys.borrow_mut().update(key, &returned_sauce.header);
+			warnings.extend(self.save_quota(key));
+			record_metrics!(self, |metrics| metrics.record_quota(self.keys.borrow().get(key)));
 
 			// Actual "returned" value:
 			if let Some(res) = returned_sauce.results {
-				let actual_min_sim: f64;
-				match min_similarity {
-					Some(min_sim) => actual_min_sim = min_sim,
-					None => actual_min_sim = self.min_similarity.get(),
-				}
-				for sauce in res {
-					let sauce_min_sim: f64 = sauce.header.similarity.parse()?;
-					if (sauce_min_sim >= actual_min_sim)
-						&& ((self.empty_filter_enabled.get() && !sauce.data.ext_urls.is_empty()) || !self.empty_filter_enabled.get())
+				let mut stats = FilterStats {
+					received: res.len(),
+					..FilterStats::default()
+				};
+				let actual_min_sim: f64 = match min_similarity {
+					Some(min_sim) => min_sim,
+					None => self.min_similarity.get(),
+				};
+				for (position, entry) in res.into_iter().enumerate() {
+					let sauce = match entry {
+						ResultEntry::Valid(sauce) => sauce,
+						ResultEntry::Invalid(_) => {
+							stats.skip(FilterReason::Invalid);
+							continue;
+						}
+					};
+					// Results whose header cannot be parsed are skipped rather than failing the whole search
+					let parsed = match header::parse_header(&sauce.header) {
+						Ok(parsed) => parsed,
+						Err(err) => {
+							warnings.push(format!("result {}: skipped, {}", position, err));
+							stats.skip(FilterReason::UnparseableHeader);
+							continue;
+						}
+					};
+					let ext_urls = sauce.data.ext_urls.into_value().map(|list| list.urls).unwrap_or_default();
+					if (parsed.similarity >= actual_min_sim)
+						&& ((self.empty_filter_enabled.get() && !ext_urls.is_empty()) || !self.empty_filter_enabled.get())
 					{
-						let actual_index: u32 = sauce.header.index_name.split(':').collect::<Vec<&str>>()[0]
-							.to_string()
-							.split('#')
-							.collect::<Vec<&str>>()[1]
-							.to_string()
-							.parse::<u32>()?;
+						let actual_index = parsed.index;
 						let source: Option<constants::Source> = self.get_source(actual_index);
 
+						let mut builder = SauceBuilder::default();
+						builder
+							.ext_urls(ext_urls)
+							.index(actual_index)
+							.similarity(parsed.similarity as f32)
+							.thumbnail(sauce.header.thumbnail.value().map(String::as_str).unwrap_or(""));
+						if let Some(index_id) = sauce.header.index_id.value() {
+							builder.index_id(*index_id);
+						}
+						if let Some(title) = sauce.data.title.value() {
+							builder.title(title.as_str());
+						}
+
 						match source {
 							Some(src) => {
-								ret_sauce.push(sauce::new_sauce(
-									sauce.data.ext_urls,
-									sauce.data.title,
-									src.name.to_string(),
-									actual_index,
-									sauce.header.index_id,
-									sauce.header.similarity.parse().unwrap(),
-									sauce.header.thumbnail.to_string(),
-									match serde_json::to_value(&sauce.data.additional_fields) {
-										Ok(x) => Some(x),
-										Err(_x) => None,
-									},
-								));
+								builder.site(src.name);
+								if let Ok(x) = serde_json::to_value(&sauce.data.additional_fields) {
+									builder.additional_fields(x);
+								}
 							}
 							None => {
-								ret_sauce.push(sauce::new_sauce(
-									sauce.data.ext_urls,
-									sauce.data.title,
-									sauce.header.index_name,
-									actual_index,
-									sauce.header.index_id,
-									sauce.header.similarity.parse().unwrap(),
-									sauce.header.thumbnail.to_string(),
-									None,
-								));
+								builder.site(sauce.header.index_name.value().map(String::as_str).unwrap_or(""));
 							}
 						}
+						ret_sauce.push(builder.build());
+					} else if parsed.similarity < actual_min_sim {
+						stats.skip(FilterReason::BelowSimilarity);
+					} else {
+						stats.skip(FilterReason::EmptyUrls);
 					}
 				}
+				debug!(
+					received = stats.received,
+					returned = ret_sauce.len(),
+					invalid = stats.invalid,
+					unparseable_header = stats.unparseable_header,
+					below_similarity = stats.below_similarity,
+					empty_urls = stats.empty_urls,
+					min_similarity = actual_min_sim,
+					"filtered results"
+				);
+				record_metrics!(self, |metrics| metrics.record_results(&stats, ret_sauce.len()));
 			}
-			Ok(ret_sauce)
+			if !warnings.is_empty() {
+				warn!(warnings = ?warnings, "unexpected response content");
+			}
+			Ok(SauceResponse {
+				results: ret_sauce,
+				warnings: self.load_warnings.take().into_iter().chain(warnings).collect(),
+			})
 		} else {
-			Err(Error::invalid_code(returned_sauce.header.status, returned_sauce.header.message))
+			Err(Error::from_status(
+				status,
+				returned_sauce.header.message.into_value().unwrap_or_default(),
+				returned_sauce.header.long_remaining.value().cloned(),
+			))
 		}
 	}
 
@@ -569,6 +1086,53 @@ impl Handler {
 		async_std::task::block_on(async { self.async_get_sauce(image_path, num_results, min_similarity).await })
 	}
 
+	/// Returns a Result of either a SauceResponse, which contains the results along with the warnings raised while processing the response, or an
+	/// error.  Otherwise identical to ``get_sauce(...)``.
+	/// ## Arguments
+	/// * ``image_path`` - A string slice that contains the url of the image you wish to look up.
+	/// * ``num_results`` - An Option containing a u32 to specify the number of results you wish to get for this specific search.  If this is None, it will default to whatever was originally set in the Handler when it was initialized.  This can be at most 999.
+	/// * ``min_similarity`` - An Option containing a f64 to specify the minimum similarity you wish to meet for a result to show up for this specific search.  If this is None, it will default to whatever was originally set in the Handler when it was initialized.
+	///
+	/// ## Example
+	/// ```
+	/// use rustnao::HandlerBuilder;
+	/// let handle = HandlerBuilder::default().api_key("your_api_key").num_results(999).db(999).build();
+	/// if let Ok(response) = handle.get_sauce_with_warnings("./tests/test.jpg", None, None) {
+	///     println!("{} results, {} warnings", response.results.len(), response.warnings.len());
+	/// }
+	/// ```
+	///
+	/// ## Errors
+	/// The errors are the same as for ``get_sauce(...)``.
+	pub fn get_sauce_with_warnings(&self, image_path: &str, num_results: Option<u32>, min_similarity: Option<f64>) -> Result<SauceResponse> {
+		async_std::task::block_on(async { self.async_get_sauce_with_warnings(image_path, num_results, min_similarity).await })
+	}
+
+	/// Returns a Result of either a vector of Sauce objects, or an error, failing with a timeout error if the search takes longer than the given timeout.
+	/// Otherwise identical to ``get_sauce(...)``, and the timeout of the Handler is ignored for this search.
+	/// ## Arguments
+	/// * ``image_path`` - A string slice that contains the url of the image you wish to look up.
+	/// * ``num_results`` - An Option containing a u32 to specify the number of results you wish to get for this specific search.  If this is None, it will default to whatever was originally set in the Handler when it was initialized.  This can be at most 999.
+	/// * ``min_similarity`` - An Option containing a f64 to specify the minimum similarity you wish to meet for a result to show up for this specific search.  If this is None, it will default to whatever was originally set in the Handler when it was initialized.
+	/// * ``timeout`` - A Duration representing how long this search may take, including uploading the image and reading the response.
+	///
+	/// ## Example
+	/// ```
+	/// use rustnao::HandlerBuilder;
+	/// use std::time::Duration;
+	/// let handle = HandlerBuilder::default().api_key("your_api_key").num_results(999).db(999).build();
+	/// handle.get_sauce_with_timeout("./tests/test.jpg", None, None, Duration::from_secs(10));
+	/// ```
+	///
+	/// ## Errors
+	/// If the search took longer than ``timeout``, an error of the kind ``ErrType::Timeout`` will be returned.  Otherwise, the errors are the same as
+	/// for ``get_sauce(...)``.
+	pub fn get_sauce_with_timeout(
+		&self, image_path: &str, num_results: Option<u32>, min_similarity: Option<f64>, timeout: Duration,
+	) -> Result<Vec<Sauce>> {
+		async_std::task::block_on(async { self.async_get_sauce_with_timeout(image_path, num_results, min_similarity, timeout).await })
+	}
+
 	/// Returns a string representing a vector of Sauce objects as a serialized JSON, or an error.  Otherwise identical to ``get_sauce(...)``
 	/// ## Arguments
 	/// * ``image_path`` - A string slice that contains the url of the image you wish to look up.
@@ -611,6 +1175,14 @@ impl Handler {
 		Ok(serde_json::to_string(&ret_sauce)?)
 	}
 
+	/// Processes a raw response of the SauceNAO API as if a search had returned it, for the fuzz targets.
+	#[cfg(fuzzing)]
+	pub(crate) fn process_json(&self, response: &str) -> Result<SauceResponse> {
+		let returned_sauce: SauceResult = serde_json::from_str(response)?;
+		let key = self.keys.borrow().current();
+		self.process_results(returned_sauce, None, key)
+	}
+
 	/// Asynchronously returns a Result of either a vector of Sauce objects, which contain potential sources for the input path, or a SauceError.
 	/// ## Arguments
 	/// * ``image_path`` - A string slice that contains the url of the image you wish to look up.
@@ -621,25 +1193,227 @@ impl Handler {
 	/// If there was a problem forming a URL, reading a file, making a request, or parsing the returned JSON, an error will be returned.
 	/// Furthermore, if you pass a link in which SauceNAO returns an error code, an error containing the code and message will be returned.
 	pub async fn async_get_sauce(&self, image_path: &str, num_results: Option<u32>, min_similarity: Option<f64>) -> Result<Vec<Sauce>> {
-		// Check passed in values first to see if they're valid!
-
-		if !self.is_valid_min_sim(min_similarity) {
-			return Err(Error::invalid_parameter(
-				"min_similarity must be less 100.0 and greater than 0.0.".to_string(),
-			));
-		} else if !self.is_valid_num_res(num_results) {
-			return Err(Error::invalid_parameter("num_results must be less than 999.".to_string()));
-		}
+		self.async_get_sauce_with_warnings(image_path, num_results, min_similarity)
+			.await
+			.map(|response| response.results)
+	}
+
+	/// Asynchronously returns a Result of either a SauceResponse, which contains the results along with the warnings raised while processing the
+	/// response, or an error.  Otherwise identical to ``async_get_sauce(...)``.
+	/// ## Arguments
+	/// * ``image_path`` - A string slice that contains the url of the image you wish to look up.
+	/// * ``num_results`` - An Option containing a u32 to specify the number of results you wish to get for this specific search.  If this is None, it will default to whatever was originally set in the Handler when it was initialized.  This can be at most 999.
+	/// * ``min_similarity`` - An Option containing a f64 to specify the minimum similarity you wish to meet for a result to show up for this specific search.  If this is None, it will default to whatever was originally set in the Handler when it was initialized.
+	///
+	/// ## Errors
+	/// The errors are the same as for ``async_get_sauce(...)``.
+	pub async fn async_get_sauce_with_warnings(
+		&self, image_path: &str, num_results: Option<u32>, min_similarity: Option<f64>,
+	) -> Result<SauceResponse> {
+		self.timed_search(image_path, num_results, min_similarity, self.timeout, false).await
+	}
 
-		let url_string = self.generate_url(image_path, num_results)?;
+	/// Asynchronously returns a Result of either a vector of Sauce objects, or an error, failing with a timeout error if the search takes longer than the
+	/// given timeout.  Otherwise identical to ``async_get_sauce(...)``, and the timeout of the Handler is ignored for this search.
+	/// ## Arguments
+	/// * ``image_path`` - A string slice that contains the url of the image you wish to look up.
+	/// * ``num_results`` - An Option containing a u32 to specify the number of results you wish to get for this specific search.  If this is None, it will default to whatever was originally set in the Handler when it was initialized.  This can be at most 999.
+	/// * ``min_similarity`` - An Option containing a f64 to specify the minimum similarity you wish to meet for a result to show up for this specific search.  If this is None, it will default to whatever was originally set in the Handler when it was initialized.
+	/// * ``timeout`` - A Duration representing how long this search may take, including uploading the image and reading the response.
+	///
+	/// ## Errors
+	/// If the search took longer than ``timeout``, an error of the kind ``ErrType::Timeout`` will be returned.  Otherwise, the errors are the same as
+	/// for ``async_get_sauce(...)``.
+	pub async fn async_get_sauce_with_timeout(
+		&self, image_path: &str, num_results: Option<u32>, min_similarity: Option<f64>, timeout: Duration,
+	) -> Result<Vec<Sauce>> {
+		self.timed_search(image_path, num_results, min_similarity, Some(timeout), false)
+			.await
+			.map(|response| response.results)
+	}
 
-		let returned_sauce: SauceResult = if !(image_path.starts_with("https://") || image_path.starts_with("http://")) {
-			surf::post(&url_string).body_file(image_path)?.await?.body_json().await?
-		} else {
-			surf::post(&url_string).await?.body_json().await?
+	/// Searches every database for the image, whatever the Handler is set to search, for a ``FallbackChain``.  Otherwise identical to
+	/// ``async_get_sauce(...)``.
+	pub(crate) async fn async_get_sauce_from_all_databases(
+		&self, image_path: &str, num_results: Option<u32>, min_similarity: Option<f64>,
+	) -> Result<Vec<Sauce>> {
+		self.timed_search(image_path, num_results, min_similarity, self.timeout, true)
+			.await
+			.map(|response| response.results)
+	}
+
+	/// Returns whether the Handler searches every database, so that searching them all would find nothing more.
+	pub(crate) fn searches_all_databases(&self) -> bool {
+		matches!(self.db, None | Some(999))
+			&& matches!(self.db_mask.as_deref(), None | Some([]))
+			&& matches!(self.db_mask_i.as_deref(), None | Some([]))
+	}
+
+	/// Returns the minimum similarity used when a search does not give one.
+	pub(crate) fn get_min_similarity(&self) -> f64 {
+		self.min_similarity.get()
+	}
+
+	/// Searches for the image, with an optional timeout for the whole search, and optionally every database whatever the Handler is set to search.
+	///
+	/// The future returned is safe to drop at any point: dropping it aborts the upload, and the state of the Handler is only updated once a complete
+	/// response has been read.
+	async fn timed_search(
+		&self, image_path: &str, num_results: Option<u32>, min_similarity: Option<f64>, timeout: Option<Duration>, all_databases: bool,
+	) -> Result<SauceResponse> {
+		#[cfg(feature = "metrics")]
+		let started = Instant::now();
+		let search = async {
+			match (search::check_parameters(num_results, min_similarity), timeout) {
+				(Err(err), _) => Err(err),
+				(Ok(()), Some(timeout)) => {
+					match async_std::future::timeout(timeout, self.search_with_keys(image_path, num_results, min_similarity, all_databases)).await {
+						Ok(result) => result,
+						Err(_) => Err(Error::timeout(format!("the search did not complete within {:?}", timeout))),
+					}
+				}
+				(Ok(()), None) => self.search_with_keys(image_path, num_results, min_similarity, all_databases).await,
+			}
 		};
+		let result = instrument!(
+			search,
+			"search",
+			image_path,
+			num_results = ?num_results,
+			min_similarity = ?min_similarity,
+			timeout = ?timeout
+		)
+		.await;
+		record_metrics!(self, |metrics| metrics.record_search(started.elapsed(), &result));
+		if let Err(err) = &result {
+			debug!(error = %err, "search failed");
+			self.notify(|listener| listener.error(err));
+		}
+		result
+	}
 
-		self.process_results(returned_sauce, min_similarity)
+	/// Calls a method on every listener.
+	fn notify<F: Fn(&dyn SearchListener)>(&self, event: F) {
+		for listener in &self.listeners {
+			event(listener.as_ref());
+		}
+	}
+
+	/// Searches with the best API key, retrying with another key if SauceNAO rejects the key or its limits are exceeded and another key has searches left.
+	async fn search_with_keys(
+		&self, image_path: &str, num_results: Option<u32>, min_similarity: Option<f64>, all_databases: bool,
+	) -> Result<SauceResponse> {
+		let mut attempt = 1;
+		// Problems with saving the limits of keys that were retried, which are reported with the response
+		let mut warnings = Vec::new();
+		loop {
+			let key = match self.keys.borrow_mut().select() {
+				Some(key) => key,
+				None => {
+					return Err(Error::from(ErrType::InvalidApiKey(
+						"every API key has been rejected by SauceNAO".to_string(),
+					)))
+				}
+			};
+			let api_key = self.keys.borrow().get(key).api_key.clone();
+			self.notify(|listener| listener.request_started(image_path, attempt));
+			let result = instrument!(
+				self.search_with_key(api_key.as_str(), key, image_path, num_results, min_similarity, all_databases),
+				"attempt",
+				attempt,
+				key_id = %quota::key_id(api_key.as_str())
+			)
+			.await;
+
+			match &result {
+				Ok(_) => {
+					if !self.listeners.is_empty() {
+						let status = self.keys.borrow().get(key).status();
+						if listener::is_limit_approached(&status) {
+							self.notify(|listener| listener.rate_limit_approached(&status));
+						}
+					}
+				}
+				Err(err) => {
+					match err.kind() {
+						ErrType::InvalidApiKey(_) => {
+							// Without another key to retry with, the rejection is returned and the key is tried again by the next search
+							if !self.keys.borrow_mut().quarantine(key) {
+								return result;
+							}
+						}
+						ErrType::ShortLimitExceeded { .. } => self.keys.borrow_mut().exhaust_short(key),
+						ErrType::DailyLimitExceeded { .. } => self.keys.borrow_mut().exhaust_long(key),
+						_ => return result,
+					}
+					if let ErrType::ShortLimitExceeded { .. } | ErrType::DailyLimitExceeded { .. } = err.kind() {
+						self.notify(|listener| listener.rate_limit_exceeded(err));
+					}
+					warnings.extend(self.save_quota(key));
+					record_metrics!(self, |metrics| metrics.record_quota(self.keys.borrow().get(key)));
+					if self.keys.borrow().has_quota() {
+						attempt += 1;
+						self.notify(|listener| listener.retry_scheduled(err, attempt));
+						continue;
+					}
+				}
+			}
+			return result.map(|mut response| {
+				warnings.append(&mut response.warnings);
+				response.warnings = warnings;
+				response
+			});
+		}
+	}
+
+	/// Sends a single search request with the given API key.
+	async fn search_with_key(
+		&self, api_key: &str, key: usize, image_path: &str, num_results: Option<u32>, min_similarity: Option<f64>, all_databases: bool,
+	) -> Result<SauceResponse> {
+		let url_string = self.generate_url(api_key, image_path, num_results, all_databases)?;
+		let mut request = surf::Request::with_client(surf::http::Method::POST, Url::parse(&url_string)?, self.client()?);
+		let is_link = image_path.starts_with("https://") || image_path.starts_with("http://");
+		if !is_link {
+			request = request.body_file(image_path)?;
+		}
+		record_metrics!(self, |metrics| {
+			let upload_bytes = if is_link {
+				0
+			} else {
+				async_std::fs::metadata(image_path).await.map_or(0, |metadata| metadata.len())
+			};
+			metrics.record_request(upload_bytes)
+		});
+
+		let mut response = request.await?;
+		let http_status = response.status();
+		let body = response.body_string().await?;
+		debug!(http_status = http_status.as_u16(), bytes = body.len(), "received response");
+
+		// SauceNAO sends its errors as JSON, even with an unsuccessful HTTP status, so the HTTP status is only used if the body cannot be read
+		match serde_json::from_str::<SauceResult>(body.as_str()) {
+			Ok(returned_sauce) => {
+				debug!(
+					status = ?returned_sauce.header.status.value(),
+					results = returned_sauce.results.as_ref().map_or(0, Vec::len),
+					short_remaining = ?returned_sauce.header.short_remaining.value(),
+					long_remaining = ?returned_sauce.header.long_remaining.value(),
+					"parsed response"
+				);
+				if !self.listeners.is_empty() {
+					let header = ResultHeader::from(&returned_sauce.header);
+					self.notify(|listener| listener.response_received(&header));
+				}
+				self.process_results(returned_sauce, min_similarity, key)
+			}
+			Err(err) => {
+				if http_status.is_success() {
+					Err(Error::from(err))
+				} else {
+					Err(Error::from_http_status(http_status.as_u16(), body))
+				}
+			}
+		}
 	}
 
 	/// Asynchronously returns a string representing a vector of Sauce objects as a serialized JSON, or an error.  Otherwise identical to ``async_get_sauce(...)``
@@ -679,7 +1453,7 @@ impl Handler {
 /// let handle = HandlerBuilder::default().api_key("your_api_key").num_results(999).db(999).build();
 /// let result = handle.get_sauce("./tests/test.jpg", None, None);
 /// if result.is_ok() {
-/// 	result.unwrap().to_json_pretty();
+///     result.unwrap().to_json_pretty();
 /// }
 /// ```
 pub trait ToJSON {
@@ -690,7 +1464,7 @@ pub trait ToJSON {
 	/// let handle = HandlerBuilder::default().api_key("your_api_key").num_results(999).db(999).build();
 	/// let result = handle.get_sauce("./tests/test.jpg", None, None);
 	/// if result.is_ok() {
-	/// 	result.unwrap().to_json();
+	///     result.unwrap().to_json();
 	/// }
 	/// ```
 	/// ### Errors
@@ -704,7 +1478,7 @@ pub trait ToJSON {
 	/// let handle = HandlerBuilder::default().api_key("your_api_key").num_results(999).db(999).build();
 	/// let result = handle.get_sauce("./tests/test.jpg", None, None);
 	/// if result.is_ok() {
-	/// 	result.unwrap().to_json_pretty();
+	///     result.unwrap().to_json_pretty();
 	/// }
 	/// ```
 	/// ### Errors
@@ -0,0 +1,164 @@
+//! An aggregator that searches several engines for the same image and merges their results.
+
+use super::cluster::normalize_url;
+use super::search::{check_parameters, limit, ReverseImageSearch, SearchFuture};
+use super::{Result, Sauce};
+use std::fmt;
+use std::future::poll_fn;
+use std::task::Poll;
+
+/// Searches several ``ReverseImageSearch`` engines for the sources of an image and merges their results, and is a ``ReverseImageSearch`` itself.
+///
+/// The engines are queried one after another by default, or all at once with ``parallel(true)``.  Either way the results are merged in the order the
+/// engines were added: a result sharing an external URL with an earlier one, once normalised with ``normalize_url``, is folded into it, adding its
+/// other URLs and keeping the higher similarity.  Engines that fail are skipped, and an error is only returned if every engine failed, in which case it is the error of the first engine.
+/// ``num_results`` and ``min_similarity`` apply to the merged results.
+/// ### Example
+/// ```
+/// use rustnao::{FakeSearch, MultiSearch, ReverseImageSearch, SauceBuilder};
+/// let url = "https://danbooru.donmai.us/post/show/3441187".to_string();
+/// let mut first = FakeSearch::new();
+/// first.results("cat.png", vec![SauceBuilder::default().site("Danbooru").ext_urls(vec![url.clone()]).similarity(90.0).build()]);
+/// let mut second = FakeSearch::new();
+/// second.results("cat.png", vec![SauceBuilder::default().site("Danbooru").ext_urls(vec![url]).similarity(95.0).build()]);
+///
+/// let mut search = MultiSearch::new();
+/// search.engine(first).engine(second).parallel(true);
+/// let results = search.search_blocking("cat.png", None, None).unwrap();
+/// assert_eq!(results.len(), 1);
+/// assert_eq!(results[0].similarity, 95.0);
+/// ```
+#[derive(Default)]
+pub struct MultiSearch {
+	engines: Vec<Box<dyn ReverseImageSearch>>,
+	parallel: bool,
+}
+
+impl fmt::Debug for MultiSearch {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("MultiSearch")
+			.field("engines", &self.engines.len())
+			.field("parallel", &self.parallel)
+			.finish()
+	}
+}
+
+impl MultiSearch {
+	/// Creates an aggregator without any engines, which finds nothing until engines are added.
+	pub fn new() -> MultiSearch {
+		MultiSearch::default()
+	}
+
+	/// Adds an engine to search.  Results from engines added earlier come first.
+	///
+	/// ### Arguments
+	/// * engine - Anything that implements ``ReverseImageSearch``, such as a ``Handler``.
+	///
+	/// ### Examples
+	/// ```
+	/// use rustnao::{HandlerBuilder, MultiSearch};
+	/// let mut search = MultiSearch::new();
+	/// search.engine(HandlerBuilder::default().api_key("your_api_key").build());
+	/// ```
+	pub fn engine<S: ReverseImageSearch + 'static>(&mut self, engine: S) -> &mut MultiSearch {
+		self.engines.push(Box::new(engine));
+		self
+	}
+
+	/// Sets whether the engines are searched at the same time rather than one after another.  By default they are searched one after another.
+	///
+	/// ### Arguments
+	/// * parallel - A boolean representing whether to search the engines at the same time.
+	///
+	/// ### Examples
+	/// ```
+	/// use rustnao::MultiSearch;
+	/// let mut search = MultiSearch::new();
+	/// search.parallel(true);
+	/// ```
+	pub fn parallel(&mut self, parallel: bool) -> &mut MultiSearch {
+		self.parallel = parallel;
+		self
+	}
+
+	async fn find(&self, image_path: &str, num_results: Option<u32>, min_similarity: Option<f64>) -> Result<Vec<Sauce>> {
+		check_parameters(num_results, min_similarity)?;
+		let searches = self.engines.iter().map(|engine| engine.search(image_path, num_results, min_similarity));
+		let outcomes = if self.parallel {
+			join(searches.collect()).await
+		} else {
+			let mut outcomes = Vec::with_capacity(self.engines.len());
+			for search in searches {
+				outcomes.push(search.await);
+			}
+			outcomes
+		};
+
+		let mut first_error = None;
+		let mut succeeded = outcomes.is_empty();
+		let mut merged: Vec<Sauce> = Vec::new();
+		for outcome in outcomes {
+			match outcome {
+				Ok(results) => {
+					succeeded = true;
+					results.into_iter().for_each(|sauce| merge(&mut merged, sauce));
+				}
+				Err(err) => {
+					first_error.get_or_insert(err);
+				}
+			}
+		}
+		match first_error {
+			Some(err) if !succeeded => Err(err),
+			_ => Ok(limit(merged, num_results, min_similarity)),
+		}
+	}
+}
+
+impl ReverseImageSearch for MultiSearch {
+	fn search<'a>(&'a self, image_path: &'a str, num_results: Option<u32>, min_similarity: Option<f64>) -> SearchFuture<'a, Vec<Sauce>> {
+		Box::pin(self.find(image_path, num_results, min_similarity))
+	}
+}
+
+/// Waits for every search, polling them all on the current task, and returns their outcomes in order.
+async fn join(mut searches: Vec<SearchFuture<'_, Vec<Sauce>>>) -> Vec<Result<Vec<Sauce>>> {
+	let mut outcomes: Vec<Option<Result<Vec<Sauce>>>> = searches.iter().map(|_| None).collect();
+	poll_fn(|cx| {
+		for (search, outcome) in searches.iter_mut().zip(outcomes.iter_mut()) {
+			if outcome.is_none() {
+				if let Poll::Ready(result) = search.as_mut().poll(cx) {
+					*outcome = Some(result);
+				}
+			}
+		}
+		if outcomes.iter().all(Option::is_some) {
+			Poll::Ready(())
+		} else {
+			Poll::Pending
+		}
+	})
+	.await;
+	outcomes.into_iter().flatten().collect()
+}
+
+/// Adds a result to the merged results, folding it into an earlier result that shares an external URL with it once normalised.
+fn merge(merged: &mut Vec<Sauce>, sauce: Sauce) {
+	let urls: Vec<String> = sauce.ext_urls.iter().map(|url| normalize_url(url)).collect();
+	match merged
+		.iter_mut()
+		.find(|earlier| earlier.ext_urls.iter().any(|url| urls.contains(&normalize_url(url))))
+	{
+		Some(earlier) => {
+			let mut earlier_urls: Vec<String> = earlier.ext_urls.iter().map(|url| normalize_url(url)).collect();
+			for (url, normalized) in sauce.ext_urls.into_iter().zip(urls) {
+				if !earlier_urls.contains(&normalized) {
+					earlier.ext_urls.push(url);
+					earlier_urls.push(normalized);
+				}
+			}
+			earlier.similarity = earlier.similarity.max(sauce.similarity);
+		}
+		None => merged.push(sauce),
+	}
+}
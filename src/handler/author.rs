@@ -0,0 +1,127 @@
+//! Extraction of the artists and creators of results from the fields that each index gives them in.
+
+use super::{constants, Sauce};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// The fields in ``additional_fields`` that hold only the name of an author, depending on the index.  Boorus give the artist tag as ``creator``,
+/// which some indexes, such as H-Misc, give as a list.
+const NAME_FIELDS: [&str; 4] = ["creator", "user_name", "artist", "author"];
+
+/// An artist or creator of a result, as given by the index it was found in.
+/// ### Example
+/// ```
+/// use rustnao::{Author, Handler, SauceBuilder};
+/// let sauce = SauceBuilder::default()
+///     .site("Pixiv")
+///     .index(Handler::PIXIV)
+///     .additional_fields(serde_json::json!({ "member_name": "リン☆ユウ", "member_id": 4754550 }))
+///     .build();
+/// assert_eq!(
+///     sauce.authors(),
+///     vec![Author {
+///         name: "リン☆ユウ".to_string(),
+///         id: Some("4754550".to_string()),
+///         profile_url: Some("https://www.pixiv.net/users/4754550".to_string()),
+///         site: "Pixiv".to_string(),
+///     }]
+/// );
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Author {
+	/// The name of the author, such as a Pixiv member name, a booru artist tag or a Twitter handle.
+	pub name: String,
+	/// The ID of the author on the site, if the index gives one.
+	pub id: Option<String>,
+	/// The link to the profile of the author, if the index gives one or it can be made from the ID.
+	pub profile_url: Option<String>,
+	/// The site the name and ID belong to, which is the site of the result except for Twitter authors.
+	pub site: String,
+}
+
+/// Displays an Author as the name and site, such as ``lin_yuu (Danbooru)``.
+impl fmt::Display for Author {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{} ({})", self.name, self.site)
+	}
+}
+
+/// Returns the authors of a result, in order and without duplicates.
+pub(crate) fn authors(sauce: &Sauce) -> Vec<Author> {
+	let fields = match &sauce.additional_fields {
+		Some(fields) => fields,
+		None => return Vec::new(),
+	};
+	let mut authors: Vec<Author> = Vec::new();
+	let mut add = |name: Option<String>, id: Option<String>, profile_url: Option<String>, site: &str| {
+		if let Some(name) = name {
+			let author = Author {
+				name,
+				id,
+				profile_url,
+				site: site.to_string(),
+			};
+			if !authors.contains(&author) {
+				authors.push(author);
+			}
+		}
+	};
+
+	// Pixiv, Nijie, Nico Nico Seiga, MediBang, drawr, bcy.net and PortalGraphics.net
+	let member_id = id(fields.get("member_id"));
+	let profile_url = member_id.as_ref().and_then(|id| member_profile(sauce.index, id));
+	add(name(fields.get("member_name")), member_id, profile_url, &sauce.site);
+
+	// deviantArt, FurAffinity, ArtStation and ascii2d
+	let author_url = name(fields.get("author_url"));
+	add(name(fields.get("author_name")), None, author_url, &sauce.site);
+
+	let handle = name(fields.get("twitter_user_handle")).map(|handle| handle.trim_start_matches('@').to_string());
+	let profile_url = handle.as_ref().map(|handle| format!("https://twitter.com/{}", handle));
+	add(handle, id(fields.get("twitter_user_id")), profile_url, "Twitter");
+
+	let account = name(fields.get("pawoo_user_acct")).map(|account| account.trim_start_matches('@').to_string());
+	let profile_url = account.as_ref().map(|account| format!("https://pawoo.net/@{}", account));
+	let display_name = name(fields.get("pawoo_user_display_name")).or_else(|| account.clone());
+	add(display_name, account, profile_url, &sauce.site);
+
+	for field in NAME_FIELDS.iter() {
+		match fields.get(field) {
+			Some(serde_json::Value::Array(names)) => {
+				for value in names {
+					add(name(Some(value)), None, None, &sauce.site);
+				}
+			}
+			value => add(name(value), None, None, &sauce.site),
+		}
+	}
+	authors
+}
+
+/// Returns a trimmed name, or None if it is missing or empty.
+fn name(value: Option<&serde_json::Value>) -> Option<String> {
+	value
+		.and_then(serde_json::Value::as_str)
+		.map(str::trim)
+		.filter(|name| !name.is_empty())
+		.map(str::to_string)
+}
+
+/// Returns an ID, which indexes give as either a number or a string.
+fn id(value: Option<&serde_json::Value>) -> Option<String> {
+	match value {
+		Some(serde_json::Value::Number(id)) => Some(id.to_string()),
+		value => name(value),
+	}
+}
+
+/// Returns the link to the profile of a member of a site, for the indexes whose profile links can be made from the member ID.
+fn member_profile(index: u32, id: &str) -> Option<String> {
+	match index {
+		index if index == constants::PIXIV.index => Some(format!("https://www.pixiv.net/users/{}", id)),
+		index if index == constants::NIJIE.index => Some(format!("https://nijie.info/members.php?id={}", id)),
+		index if index == constants::NICO_NICO_SEIGA.index => Some(format!("https://seiga.nicovideo.jp/user/illust/{}", id)),
+		index if index == constants::MEDIBANG.index => Some(format!("https://medibang.com/author/{}", id)),
+		_ => None,
+	}
+}
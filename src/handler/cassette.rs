@@ -0,0 +1,196 @@
+//! Cassettes, which record the responses of SauceNAO to a file and replay them in place of SauceNAO, so that tests can be written around real responses.
+
+use super::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use url::Url;
+
+/// The value the API key is replaced with in recorded URLs, and the signatures of thumbnails in recorded responses.
+const SCRUBBED_API_KEY: &str = "REDACTED";
+/// The value the user ID is replaced with in recorded responses.
+const SCRUBBED_USER_ID: &str = "0";
+
+/// A request to SauceNAO and the response it got, as stored in a cassette file.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub(crate) struct Interaction {
+	/// The method of the request.
+	pub method: String,
+	/// The URL of the request, with the API key scrubbed.
+	pub url: String,
+	/// The HTTP status of the response.
+	pub status: u16,
+	/// The body of the response.
+	pub body: String,
+}
+
+/// The contents of a cassette file.
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct Tape {
+	interactions: Vec<Interaction>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Mode {
+	Record,
+	Replay,
+}
+
+#[derive(Debug, Default)]
+struct State {
+	tape: Tape,
+	/// Whether each interaction has been replayed.
+	replayed: Vec<bool>,
+}
+
+/// A file of recorded SauceNAO responses.  A Handler given a cassette with ``HandlerBuilder::cassette(...)`` either records every response it gets from
+/// SauceNAO to the file, or replays the responses from the file without sending any requests.
+///
+/// The API key is scrubbed from the URLs that are recorded, and the user ID and thumbnail signatures from the responses, so cassettes can be
+/// committed alongside tests.  The bodies of requests, such as uploaded files, are not recorded.
+/// ### Example
+/// Recording the responses of a few searches once, with a real API key:
+/// ```no_run
+/// use rustnao::{Cassette, HandlerBuilder};
+/// let handle = HandlerBuilder::default().api_key("your_api_key").cassette(Cassette::record("./tests/cassettes/pixiv.json")).build();
+/// handle.get_sauce("https://i.imgur.com/W42kkKS.jpg", None, None).unwrap();
+/// ```
+///
+/// And replaying them in a test, which needs neither the API key nor network access:
+/// ```no_run
+/// use rustnao::{Cassette, HandlerBuilder};
+/// let cassette = Cassette::replay("./tests/cassettes/pixiv.json").unwrap();
+/// let handle = HandlerBuilder::default().cassette(cassette).build();
+/// let result = handle.get_sauce("https://i.imgur.com/W42kkKS.jpg", None, None).unwrap();
+/// assert_eq!(result[0].site, "Pixiv");
+/// ```
+#[derive(Debug, Clone)]
+pub struct Cassette {
+	path: PathBuf,
+	mode: Mode,
+	state: Arc<Mutex<State>>,
+}
+
+impl Cassette {
+	/// Creates a cassette that records to the file at the given path.  The file is replaced with the responses recorded so far after every response,
+	/// so responses recorded to it earlier are lost.
+	///
+	/// ### Arguments
+	/// * path - The path of the JSON file to record to.
+	pub fn record<P: AsRef<Path>>(path: P) -> Cassette {
+		Cassette {
+			path: path.as_ref().to_path_buf(),
+			mode: Mode::Record,
+			state: Arc::new(Mutex::new(State::default())),
+		}
+	}
+
+	/// Creates a cassette that replays the responses recorded to the file at the given path.
+	///
+	/// A request is answered with the first response recorded for the same method and URL, ignoring the API key, that has not been replayed yet.  Once
+	/// every matching response has been replayed, the last one is replayed again.
+	///
+	/// ### Arguments
+	/// * path - The path of the JSON file to replay.
+	///
+	/// ### Errors
+	/// If the file could not be read or is not a cassette, an error is returned.
+	pub fn replay<P: AsRef<Path>>(path: P) -> Result<Cassette> {
+		let tape: Tape = serde_json::from_str(fs::read_to_string(path.as_ref())?.as_str())?;
+		let replayed = vec![false; tape.interactions.len()];
+		Ok(Cassette {
+			path: path.as_ref().to_path_buf(),
+			mode: Mode::Replay,
+			state: Arc::new(Mutex::new(State { tape, replayed })),
+		})
+	}
+
+	/// Returns whether the cassette replays responses rather than recording them.
+	pub(crate) fn is_replaying(&self) -> bool {
+		self.mode == Mode::Replay
+	}
+
+	fn state(&self) -> MutexGuard<'_, State> {
+		self.state.lock().unwrap_or_else(|err| err.into_inner())
+	}
+
+	/// Returns the recorded response to a request.
+	pub(crate) fn find(&self, method: &str, url: &Url) -> Result<Interaction> {
+		let url = scrub(url);
+		let mut state = self.state();
+		let matching: Vec<usize> = (0..state.tape.interactions.len())
+			.filter(|&index| {
+				let interaction = &state.tape.interactions[index];
+				interaction.method == method && interaction.url == url
+			})
+			.collect();
+		let index = match matching.iter().find(|&&index| !state.replayed[index]).or_else(|| matching.last()) {
+			Some(&index) => index,
+			None => {
+				return Err(Error::invalid_request(format!(
+					"the cassette {} has no response recorded for {} {}",
+					self.path.display(),
+					method,
+					url
+				)))
+			}
+		};
+		state.replayed[index] = true;
+		Ok(state.tape.interactions[index].clone())
+	}
+
+	/// Records the response to a request, and writes every response recorded so far to the file.
+	pub(crate) fn record_interaction(&self, method: &str, url: &Url, status: u16, body: &str) -> Result<()> {
+		let mut state = self.state();
+		state.tape.interactions.push(Interaction {
+			method: method.to_string(),
+			url: scrub(url),
+			status,
+			body: scrub_body(body),
+		});
+		if let Some(parent) = self.path.parent() {
+			fs::create_dir_all(parent)?;
+		}
+		fs::write(&self.path, serde_json::to_string_pretty(&state.tape)?)?;
+		Ok(())
+	}
+}
+
+/// Returns a URL with the value of its API key replaced.
+fn scrub(url: &Url) -> String {
+	scrub_pair(url, "api_key")
+}
+
+/// Returns a response with the user ID and the signatures of thumbnail URLs replaced.  Responses that are not JSON are returned as they are.
+fn scrub_body(body: &str) -> String {
+	let mut json: serde_json::Value = match serde_json::from_str(body) {
+		Ok(json) => json,
+		Err(_) => return body.to_string(),
+	};
+	if let Some(user_id) = json.pointer_mut("/header/user_id") {
+		*user_id = SCRUBBED_USER_ID.into();
+	}
+	if let Some(results) = json.get_mut("results").and_then(serde_json::Value::as_array_mut) {
+		for thumbnail in results.iter_mut().filter_map(|result| result.pointer_mut("/header/thumbnail")) {
+			let url = thumbnail.as_str().and_then(|url| Url::parse(url).ok());
+			if let Some(url) = url.filter(|url| url.query_pairs().any(|(name, _)| name == "auth")) {
+				*thumbnail = scrub_pair(&url, "auth").into();
+			}
+		}
+	}
+	json.to_string()
+}
+
+/// Returns a URL with the value of the given query pair replaced.
+fn scrub_pair(url: &Url, scrubbed_name: &str) -> String {
+	let mut scrubbed = url.clone();
+	scrubbed.query_pairs_mut().clear().extend_pairs(url.query_pairs().map(|(name, value)| {
+		if name == scrubbed_name {
+			(name, SCRUBBED_API_KEY.into())
+		} else {
+			(name, value)
+		}
+	}));
+	scrubbed.into()
+}
@@ -0,0 +1,180 @@
+//! The HTTP client used to send requests to the SauceNAO API.
+//!
+//! This is a curl based client on the same isahc as the one bundled with surf, except that it can be configured, and can record responses to or replay them from a
+//! cassette.
+
+use super::{Cassette, Error, Result};
+use std::{fmt, future::Future, pin::Pin, sync::Arc, time::Duration};
+use surf::http::header::{HeaderMap, HeaderName, HeaderValue, USER_AGENT};
+use surf::middleware::{Body, HttpClient, Request, Response};
+use url::Url;
+
+/// The settings used to build a ``Client``.
+#[derive(Default, Clone, PartialEq)]
+pub(crate) struct ClientConfig {
+	/// How long to wait for a connection to SauceNAO to be established.
+	pub connect_timeout: Option<Duration>,
+	/// The URL of the proxy to send requests through.  The protocol is taken from the scheme, such as ``http``, ``https`` or ``socks5``.
+	pub proxy: Option<String>,
+	/// The username and password for the proxy.
+	pub proxy_credentials: Option<(String, String)>,
+	/// The User-Agent sent with every request.
+	pub user_agent: Option<String>,
+	/// Additional headers sent with every request.
+	pub headers: Vec<(String, String)>,
+	/// Whether redirects are followed.  SauceNAO does not redirect, but other search engines do.
+	pub follow_redirects: bool,
+}
+
+impl fmt::Debug for ClientConfig {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		// The proxy passwords are left out so that they do not end up in logs
+		f.debug_struct("ClientConfig")
+			.field("connect_timeout", &self.connect_timeout)
+			.field("proxy", &self.proxy.as_deref().map(without_password))
+			.field("proxy_username", &self.proxy_credentials.as_ref().map(|(username, _)| username))
+			.field("user_agent", &self.user_agent)
+			.field("headers", &self.headers)
+			.field("follow_redirects", &self.follow_redirects)
+			.finish()
+	}
+}
+
+/// Returns a URL with its password removed, or a placeholder if it cannot be parsed, as it may still contain one.
+fn without_password(url: &str) -> String {
+	match Url::parse(url) {
+		Ok(mut url) => {
+			let _ = url.set_password(None);
+			url.into()
+		}
+		Err(_) => "(invalid URL)".to_string(),
+	}
+}
+
+impl ClientConfig {
+	/// Returns the proxy URI with the credentials, if any, included in it as curl expects.
+	fn proxy_uri(&self) -> Result<Option<surf::http::Uri>> {
+		let proxy = match &self.proxy {
+			Some(proxy) => proxy,
+			None => return Ok(None),
+		};
+		let mut url = Url::parse(proxy.as_str()).map_err(|err| Error::invalid_parameter(format!("invalid proxy URL \"{}\": {}", proxy, err)))?;
+		if let Some((username, password)) = &self.proxy_credentials {
+			if url.set_username(username.as_str()).is_err() || url.set_password(Some(password.as_str())).is_err() {
+				return Err(Error::invalid_parameter(format!("the proxy URL \"{}\" cannot have credentials", proxy)));
+			}
+		}
+		let uri = url
+			.as_str()
+			.parse()
+			.map_err(|err| Error::invalid_parameter(format!("invalid proxy URL \"{}\": {}", proxy, err)))?;
+		Ok(Some(uri))
+	}
+
+	/// Returns the User-Agent and additional headers as a header map.
+	fn header_map(&self) -> Result<HeaderMap> {
+		let mut headers = HeaderMap::new();
+		for (name, value) in &self.headers {
+			let name = HeaderName::from_bytes(name.as_bytes())
+				.map_err(|err| Error::invalid_parameter(format!("invalid header name \"{}\": {}", name, err)))?;
+			let value = HeaderValue::from_str(value.as_str())
+				.map_err(|err| Error::invalid_parameter(format!("invalid value for header {}: {}", name, err)))?;
+			headers.append(name, value);
+		}
+		if let Some(user_agent) = &self.user_agent {
+			let value = HeaderValue::from_str(user_agent.as_str()).map_err(|err| Error::invalid_parameter(format!("invalid User-Agent: {}", err)))?;
+			headers.insert(USER_AGENT, value);
+		}
+		Ok(headers)
+	}
+}
+
+/// A configurable curl based client for surf.
+#[derive(Clone)]
+pub(crate) struct Client {
+	client: Arc<isahc::HttpClient>,
+	headers: Arc<HeaderMap>,
+	cassette: Option<Cassette>,
+}
+
+impl Client {
+	/// Creates a new client with the given settings, which records to or replays from the cassette if one is given.
+	///
+	/// ## Errors
+	/// If the proxy URL or any of the headers are invalid, an error of the kind ``ErrType::InvalidParameters`` is returned.
+	pub(crate) fn new(config: &ClientConfig, cassette: Option<Cassette>) -> Result<Client> {
+		let mut builder = isahc::HttpClient::builder();
+		if let Some(connect_timeout) = config.connect_timeout {
+			builder = builder.connect_timeout(connect_timeout);
+		}
+		if let Some(proxy) = config.proxy_uri()? {
+			builder = builder.proxy(proxy);
+		}
+		if config.follow_redirects {
+			builder = builder.redirect_policy(isahc::config::RedirectPolicy::Limit(10));
+		}
+		Ok(Client {
+			client: Arc::new(builder.build()?),
+			headers: Arc::new(config.header_map()?),
+			cassette,
+		})
+	}
+}
+
+impl fmt::Debug for Client {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("Client")
+			.field("headers", &self.headers)
+			.field("cassette", &self.cassette)
+			.finish()
+	}
+}
+
+impl HttpClient for Client {
+	type Error = Error;
+
+	fn send(&self, req: Request) -> Pin<Box<dyn Future<Output = std::result::Result<Response, Self::Error>> + Send + 'static>> {
+		let client = self.client.clone();
+		let headers = self.headers.clone();
+		let cassette = self.cassette.clone();
+		// Dropping this future drops the request future of isahc, which aborts the transfer, so it is safe to cancel at any point.
+		Box::pin(async move {
+			let (mut parts, body) = req.into_parts();
+			let method = parts.method.to_string();
+			let url = Url::parse(parts.uri.to_string().as_str())?;
+			if let Some(cassette) = cassette.as_ref().filter(|cassette| cassette.is_replaying()) {
+				let interaction = cassette.find(method.as_str(), &url)?;
+				return surf::http::Response::builder()
+					.status(interaction.status)
+					.body(Body::from(interaction.body.into_bytes()))
+					.map_err(|err| Error::invalid_request(format!("invalid recorded response: {}", err)).with_source(err));
+			}
+
+			for name in headers.keys() {
+				parts.headers.remove(name);
+				for value in headers.get_all(name) {
+					parts.headers.append(name, value.clone());
+				}
+			}
+			// A body of unknown length is streamed, and curl cannot follow a redirect after streaming one, so requests without a body send none
+			let body = if parts.method == surf::http::Method::GET || parts.method == surf::http::Method::HEAD {
+				isahc::Body::empty()
+			} else {
+				isahc::Body::reader(body)
+			};
+			let req = surf::http::Request::from_parts(parts, body);
+			let res = client.send_async(req).await?;
+
+			let (parts, mut body) = res.into_parts();
+			match cassette {
+				Some(cassette) => {
+					// The whole body is read to record it, and handed to surf from memory
+					let text = body.text_async().await?;
+					cassette.record_interaction(method.as_str(), &url, parts.status.as_u16(), text.as_str())?;
+					Ok(surf::http::Response::from_parts(parts, Body::from(text.into_bytes())))
+				}
+				None => Ok(surf::http::Response::from_parts(parts, Body::from_reader(body))),
+			}
+		})
+	}
+}
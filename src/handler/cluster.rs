@@ -0,0 +1,200 @@
+//! Normalisation of external URLs, and clustering of results that refer to the same work on different sites.
+
+use super::{Sauce, SourceRef};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use url::Url;
+
+/// Query parameters that only track where a link was shared from.
+const TRACKING_PARAMETERS: [&str; 5] = ["ref", "ref_src", "fbclid", "gclid", "igshid"];
+
+/// Returns the canonical form of an external URL, so that different links to the same page compare equal.
+///
+/// Links to works on sites known to ``SourceRef`` become the canonical link to the work, so that old and new shapes of URL, and links to the image
+/// files of a Pixiv illustration, all give the same URL.  Other links are switched to HTTPS, lose ``www.``, ``m.`` and ``mobile.`` from their host,
+/// their fragment, tracking query parameters and any trailing slash.  Anything that is not a URL is returned trimmed.
+/// ### Example
+/// ```
+/// use rustnao::normalize_url;
+/// assert_eq!(normalize_url("http://www.pixiv.net/member_illust.php?mode=medium&illust_id=61477678"), "https://www.pixiv.net/artworks/61477678");
+/// assert_eq!(normalize_url("https://i.pximg.net/img-original/img/2017/02/19/00/00/39/61477678_p0.jpg"), "https://www.pixiv.net/artworks/61477678");
+/// assert_eq!(normalize_url("http://m.example.com/gallery/12/?utm_source=feed#top"), "https://example.com/gallery/12");
+/// ```
+pub fn normalize_url(url: &str) -> String {
+	if let Some(source_ref) = SourceRef::parse(url) {
+		return source_ref.url();
+	}
+	let mut parsed = match Url::parse(url.trim()) {
+		Ok(parsed) if parsed.scheme() == "http" || parsed.scheme() == "https" => parsed,
+		_ => return url.trim().to_string(),
+	};
+	if parsed.scheme() == "http" {
+		let _ = parsed.set_scheme("https");
+		if parsed.port() == Some(80) {
+			let _ = parsed.set_port(None);
+		}
+	}
+	if let Some(host) = parsed.host_str().map(str::to_string) {
+		let stripped = ["www.", "m.", "mobile."].iter().find_map(|prefix| host.strip_prefix(prefix));
+		if let Some(stripped) = stripped.filter(|stripped| stripped.contains('.')) {
+			let _ = parsed.set_host(Some(stripped));
+		}
+	}
+	parsed.set_fragment(None);
+
+	let query: Vec<(String, String)> = parsed
+		.query_pairs()
+		.filter(|(name, _)| !name.starts_with("utm_") && !TRACKING_PARAMETERS.contains(&name.as_ref()))
+		.map(|(name, value)| (name.into_owned(), value.into_owned()))
+		.collect();
+	if query.is_empty() {
+		parsed.set_query(None);
+	} else {
+		parsed.query_pairs_mut().clear().extend_pairs(query);
+	}
+
+	let path = parsed.path().trim_end_matches('/').to_string();
+	parsed.set_path(path.as_str());
+	let normalized: String = parsed.into();
+	// A URL always has a path, so one without a path or query would still end with a slash
+	match normalized.strip_suffix('/') {
+		Some(normalized) => normalized.to_string(),
+		None => normalized,
+	}
+}
+
+/// Results that refer to the same work, such as the Pixiv illustration and the Danbooru and Gelbooru posts of it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SauceGroup {
+	/// The results that refer to the work, in the order they were found in.
+	pub results: Vec<Sauce>,
+	/// The normalised links to the work from every result, including the sources boorus give for their posts, in order and without duplicates.
+	pub urls: Vec<String>,
+}
+
+impl SauceGroup {
+	/// Returns the result with the highest similarity, or the first of them if several are as similar.  This is None if the group has no results.
+	pub fn best(&self) -> Option<&Sauce> {
+		let mut results = self.results.iter();
+		let first = results.next()?;
+		Some(results.fold(first, |best, sauce| if sauce.similarity > best.similarity { sauce } else { best }))
+	}
+
+	/// Returns the highest similarity of the results, or 0 if the group has no results.
+	pub fn similarity(&self) -> f32 {
+		self.best().map_or(0.0, |best| best.similarity)
+	}
+
+	/// Returns the sites and IDs the links to the work point to, in order and without duplicates.
+	pub fn source_refs(&self) -> Vec<SourceRef> {
+		let mut source_refs: Vec<SourceRef> = Vec::new();
+		for source_ref in self.urls.iter().filter_map(|url| SourceRef::parse(url)) {
+			if !source_refs.contains(&source_ref) {
+				source_refs.push(source_ref);
+			}
+		}
+		source_refs
+	}
+}
+
+/// A trait to cluster results that refer to the same work into groups.
+///
+/// Results are grouped together if any of their normalised links are the same, whether from their ``ext_urls`` or from the ``source`` that boorus
+/// give for their posts.  Since links to a Pixiv illustration or its image files normalise to the same URL, a Pixiv result and a Danbooru post
+/// sourced from it end up in the same group.  Groups are in the order of their first result.
+/// ### Example
+/// ```
+/// use rustnao::{HandlerBuilder, ToGroups};
+/// let handle = HandlerBuilder::default().api_key("your_api_key").build();
+/// if let Ok(result) = handle.get_sauce("./tests/test.jpg", None, None) {
+///     for group in result.to_groups() {
+///         println!("{:.2}% {}", group.similarity(), group.urls.join(" "));
+///     }
+/// }
+/// ```
+pub trait ToGroups {
+	/// Clusters the results into groups that each refer to one work.
+	/// ### Example
+	/// ```
+	/// use rustnao::{SauceBuilder, ToGroups};
+	/// let results = vec![
+	///     SauceBuilder::default().site("Pixiv").ext_urls(vec!["https://www.pixiv.net/artworks/61477678".to_string()]).build(),
+	///     SauceBuilder::default().site("Danbooru").ext_urls(vec!["https://danbooru.donmai.us/post/show/3441187".to_string()])
+	///         .additional_fields(serde_json::json!({"source": "https://i.pximg.net/img-original/img/2017/02/19/00/00/39/61477678_p0.jpg"})).build(),
+	/// ];
+	/// let groups = results.to_groups();
+	/// assert_eq!(groups.len(), 1);
+	/// assert_eq!(groups[0].urls, vec!["https://www.pixiv.net/artworks/61477678", "https://danbooru.donmai.us/posts/3441187"]);
+	/// ```
+	fn to_groups(&self) -> Vec<SauceGroup>;
+}
+
+impl ToGroups for Vec<Sauce> {
+	fn to_groups(&self) -> Vec<SauceGroup> {
+		let links: Vec<Vec<String>> = self.iter().map(links).collect();
+
+		// Each result starts in a group of its own, and results sharing a link are joined, keeping the group of the earliest result
+		let mut group_of: Vec<usize> = (0..self.len()).collect();
+		let mut first_with_link: HashMap<&str, usize> = HashMap::new();
+		for (position, result_links) in links.iter().enumerate() {
+			for link in result_links {
+				let earlier = *first_with_link.entry(link.as_str()).or_insert(position);
+				let (from, to) = (root(&mut group_of, position), root(&mut group_of, earlier));
+				if from != to {
+					group_of[from.max(to)] = from.min(to);
+				}
+			}
+		}
+
+		let mut groups: Vec<SauceGroup> = Vec::new();
+		let mut group_positions: HashMap<usize, usize> = HashMap::new();
+		for (position, sauce) in self.iter().enumerate() {
+			let root = root(&mut group_of, position);
+			let group_position = *group_positions.entry(root).or_insert_with(|| {
+				groups.push(SauceGroup {
+					results: Vec::new(),
+					urls: Vec::new(),
+				});
+				groups.len() - 1
+			});
+			let group = &mut groups[group_position];
+			group.results.push(sauce.clone());
+			for link in &links[position] {
+				if !group.urls.contains(link) {
+					group.urls.push(link.clone());
+				}
+			}
+		}
+		groups
+	}
+}
+
+/// Returns the normalised links of a result, from its external URLs followed by the source of a booru post.
+pub(crate) fn links(sauce: &Sauce) -> Vec<String> {
+	let mut links: Vec<String> = Vec::new();
+	for link in sauce.ext_urls.iter().map(String::as_str).chain(source_url(sauce)).map(normalize_url) {
+		if !links.contains(&link) {
+			links.push(link);
+		}
+	}
+	links
+}
+
+/// Returns the source that a booru gives for a post, if it is a link.
+pub(crate) fn source_url(sauce: &Sauce) -> Option<&str> {
+	sauce
+		.additional_fields
+		.as_ref()
+		.and_then(|fields| fields.get("source"))
+		.and_then(serde_json::Value::as_str)
+		.filter(|source| source.starts_with("https://") || source.starts_with("http://"))
+}
+
+/// Returns the earliest result of the group a result is in.
+fn root(group_of: &mut [usize], mut position: usize) -> usize {
+	while group_of[position] != position {
+		group_of[position] = group_of[group_of[position]];
+		position = group_of[position];
+	}
+	position
+}
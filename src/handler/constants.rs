@@ -7,6 +7,7 @@ pub const API_URL: &str = "https://saucenao.com/search.php";
 pub struct Source<'a> {
 	pub index: u32,
 	pub name: &'a str,
+	#[allow(dead_code)]
 	pub rating_regex: Option<&'a str>,
 }
 
@@ -1,21 +1,151 @@
 //! Collection of structs used to deserialize the API JSON results, upon which it is further processed.
+//!
+//! SauceNAO is not always consistent about the types it sends (numbers may arrive as strings and vice versa, single URLs may not be wrapped in a list), so
+//! most fields are wrapped in a ``Lenient`` value.  Values of a known alternative shape are converted, and anything else is kept so it can be reported
+//! as a warning instead of failing the whole search.
 
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer};
 use std::collections::HashMap;
 
+/// A value that may be missing, of an expected shape, or of an unrecognised shape.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub enum Lenient<T> {
+	#[default]
+	Missing,
+	Value(T),
+	Unrecognised(serde_json::Value),
+}
+
+impl<T> Lenient<T> {
+	pub fn value(&self) -> Option<&T> {
+		match self {
+			Lenient::Value(value) => Some(value),
+			_ => None,
+		}
+	}
+
+	pub fn into_value(self) -> Option<T> {
+		match self {
+			Lenient::Value(value) => Some(value),
+			_ => None,
+		}
+	}
+
+	/// Returns a warning describing the value if its shape was not recognised.
+	pub fn warning(&self, field: &str) -> Option<String> {
+		match self {
+			Lenient::Unrecognised(value) => Some(format!("unrecognised value for {}: {}", field, value)),
+			_ => None,
+		}
+	}
+}
+
+/// Conversion from the shapes SauceNAO is known to send for a type.
+pub trait FromLenient: Sized {
+	fn from_lenient(value: &serde_json::Value) -> Option<Self>;
+}
+
+impl FromLenient for String {
+	fn from_lenient(value: &serde_json::Value) -> Option<Self> {
+		match value {
+			serde_json::Value::String(string) => Some(string.clone()),
+			serde_json::Value::Number(number) => Some(number.to_string()),
+			_ => None,
+		}
+	}
+}
+
+impl FromLenient for u32 {
+	fn from_lenient(value: &serde_json::Value) -> Option<Self> {
+		match value {
+			serde_json::Value::Number(number) => match number.as_u64() {
+				Some(integer) => Some(integer),
+				None => number
+					.as_f64()
+					.filter(|float| float.fract() == 0.0 && *float >= 0.0)
+					.map(|float| float as u64),
+			}
+			.filter(|integer| *integer <= u64::from(u32::MAX))
+			.map(|integer| integer as u32),
+			serde_json::Value::String(string) => string.trim().parse().ok(),
+			_ => None,
+		}
+	}
+}
+
+impl FromLenient for i32 {
+	fn from_lenient(value: &serde_json::Value) -> Option<Self> {
+		match value {
+			serde_json::Value::Number(number) => number
+				.as_i64()
+				.filter(|integer| *integer >= i64::from(i32::MIN) && *integer <= i64::from(i32::MAX))
+				.map(|integer| integer as i32),
+			serde_json::Value::String(string) => string.trim().parse().ok(),
+			_ => None,
+		}
+	}
+}
+
+/// The URLs of a result, along with the elements of the list that were not URLs so that each can be reported without losing the others.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct UrlList {
+	pub urls: Vec<String>,
+	pub rejected: Vec<serde_json::Value>,
+}
+
+impl FromLenient for UrlList {
+	fn from_lenient(value: &serde_json::Value) -> Option<Self> {
+		match value {
+			serde_json::Value::Array(values) => {
+				let mut list = UrlList::default();
+				for value in values {
+					match String::from_lenient(value) {
+						Some(url) => list.urls.push(url),
+						None => list.rejected.push(value.clone()),
+					}
+				}
+				Some(list)
+			}
+			serde_json::Value::String(string) => Some(UrlList {
+				urls: vec![string.clone()],
+				rejected: Vec::new(),
+			}),
+			_ => None,
+		}
+	}
+}
+
+impl<'de, T: FromLenient> Deserialize<'de> for Lenient<T> {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		let value = serde_json::Value::deserialize(deserializer)?;
+		if value.is_null() {
+			return Ok(Lenient::Missing);
+		}
+		Ok(match T::from_lenient(&value) {
+			Some(converted) => Lenient::Value(converted),
+			None => Lenient::Unrecognised(value),
+		})
+	}
+}
+
 #[derive(Deserialize, Debug, Default, Clone)]
 pub struct Header {
-	pub similarity: String,
-	pub thumbnail: String,
-	pub index_id: u32,
-	pub index_name: String,
+	#[serde(default)]
+	pub similarity: Lenient<String>,
+	#[serde(default)]
+	pub thumbnail: Lenient<String>,
+	#[serde(default)]
+	pub index_id: Lenient<u32>,
+	#[serde(default)]
+	pub index_name: Lenient<String>,
 }
 
 #[derive(Deserialize, Debug, Default, Clone)]
 pub struct Data {
 	#[serde(default)]
-	pub ext_urls: Vec<String>,
-	pub title: Option<String>,
+	pub ext_urls: Lenient<UrlList>,
+	#[serde(default)]
+	pub title: Lenient<String>,
 
 	#[serde(flatten, skip_serializing_if = "HashMap::is_empty")]
 	pub additional_fields: HashMap<String, serde_json::Value>,
@@ -29,25 +159,84 @@ pub struct SauceJSON {
 	pub data: Data,
 }
 
-#[derive(Deserialize, Debug)]
+impl SauceJSON {
+	fn warnings(&self, position: usize) -> Vec<String> {
+		let rejected = self.data.ext_urls.value().map(|list| list.rejected.as_slice()).unwrap_or_default();
+		[
+			self.header.similarity.warning("header.similarity"),
+			self.header.thumbnail.warning("header.thumbnail"),
+			self.header.index_id.warning("header.index_id"),
+			self.header.index_name.warning("header.index_name"),
+			self.data.ext_urls.warning("data.ext_urls"),
+			self.data.title.warning("data.title"),
+		]
+		.iter()
+		.flatten()
+		.cloned()
+		.chain(rejected.iter().map(|value| format!("unrecognised element of data.ext_urls: {}", value)))
+		.map(|warning| format!("result {}: {}", position, warning))
+		.collect()
+	}
+}
+
+/// A single entry of the results, which is kept even if it is not an object so that it can be reported.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum ResultEntry {
+	Valid(Box<SauceJSON>),
+	Invalid(serde_json::Value),
+}
+
+#[derive(Deserialize, Debug, Default)]
 pub struct ResultHeader {
 	#[serde(default)]
-	pub long_limit: String,
+	pub long_limit: Lenient<u32>,
 	#[serde(default)]
-	pub short_limit: String,
+	pub short_limit: Lenient<u32>,
 	#[serde(default)]
-	pub long_remaining: u32,
+	pub long_remaining: Lenient<u32>,
 	#[serde(default)]
-	pub short_remaining: u32,
+	pub short_remaining: Lenient<u32>,
 	#[serde(default)]
-	pub message: String,
+	pub message: Lenient<String>,
 	#[serde(default)]
-	pub status: i32,
+	pub status: Lenient<i32>,
+	#[serde(default)]
+	pub account_type: Lenient<u32>,
 }
 
 #[derive(Deserialize, Debug)]
 pub struct SauceResult {
 	pub header: ResultHeader,
 	#[serde(default)]
-	pub results: Option<Vec<SauceJSON>>,
+	pub results: Option<Vec<ResultEntry>>,
+}
+
+impl SauceResult {
+	/// Returns warnings for every value in the response whose shape was not recognised.
+	pub fn warnings(&self) -> Vec<String> {
+		let mut warnings: Vec<String> = [
+			self.header.long_limit.warning("header.long_limit"),
+			self.header.short_limit.warning("header.short_limit"),
+			self.header.long_remaining.warning("header.long_remaining"),
+			self.header.short_remaining.warning("header.short_remaining"),
+			self.header.message.warning("header.message"),
+			self.header.status.warning("header.status"),
+			self.header.account_type.warning("header.account_type"),
+		]
+		.iter()
+		.flatten()
+		.cloned()
+		.collect();
+
+		if let Some(results) = &self.results {
+			for (position, entry) in results.iter().enumerate() {
+				match entry {
+					ResultEntry::Valid(sauce) => warnings.append(&mut sauce.warnings(position)),
+					ResultEntry::Invalid(value) => warnings.push(format!("result {}: skipped, unrecognised result: {}", position, value)),
+				}
+			}
+		}
+		warnings
+	}
 }
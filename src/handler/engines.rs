@@ -0,0 +1,164 @@
+//! Reverse image search engines other than SauceNAO, each behind a feature of the same name.  They produce ``Sauce`` results through the
+//! ``ReverseImageSearch`` trait, so they can be used interchangeably with a ``Handler`` or combined with it in a ``MultiSearch``.
+//!
+//! Results from sites that SauceNAO indexes use the index of that site, and others use 999.  The engine a result came from is
+//! stored in its ``additional_fields`` under ``engine``.
+//!
+//! iqdb and ascii2d have no API, so their results are scraped from the pages they show in a browser.  This is best-effort: if a site changes the
+//! layout of its page, results or fields it can no longer find are left out rather than failing the search.
+
+#[cfg(feature = "ascii2d")]
+mod ascii2d;
+#[cfg(feature = "ascii2d")]
+pub use ascii2d::Ascii2d;
+
+#[cfg(feature = "iqdb")]
+mod iqdb;
+#[cfg(feature = "iqdb")]
+pub use iqdb::Iqdb;
+
+#[cfg(feature = "tracemoe")]
+mod tracemoe;
+#[cfg(feature = "tracemoe")]
+pub use tracemoe::TraceMoe;
+
+#[cfg(any(feature = "ascii2d", feature = "iqdb"))]
+mod html;
+
+use super::client::{Client, ClientConfig};
+use super::{Error, Result};
+use std::path::Path;
+use std::sync::Mutex;
+#[cfg(any(feature = "ascii2d", feature = "iqdb"))]
+use url::Url;
+
+/// The index of results from sites that SauceNAO does not index.
+#[cfg(any(feature = "ascii2d", feature = "iqdb"))]
+pub(crate) const UNKNOWN_INDEX: u32 = 999;
+
+/// An HTTP client for an engine, created on first use.
+#[derive(Debug, Default)]
+pub(crate) struct LazyClient(Mutex<Option<Client>>);
+
+impl Clone for LazyClient {
+	fn clone(&self) -> LazyClient {
+		LazyClient(Mutex::new(self.0.lock().unwrap_or_else(|err| err.into_inner()).clone()))
+	}
+}
+
+impl LazyClient {
+	/// Returns the client, creating it if this is the first use.
+	pub(crate) fn get(&self) -> Result<Client> {
+		let mut client = self.0.lock().unwrap_or_else(|err| err.into_inner());
+		if client.is_none() {
+			let config = ClientConfig {
+				follow_redirects: true,
+				..ClientConfig::default()
+			};
+			*client = Some(Client::new(&config, None)?);
+		}
+		Ok(client.clone().unwrap())
+	}
+}
+
+/// Returns whether an image path is a link rather than a local file.
+pub(crate) fn is_link(image_path: &str) -> bool {
+	image_path.starts_with("https://") || image_path.starts_with("http://")
+}
+
+/// Returns the MIME type of an image going by the extension of its path.
+pub(crate) fn content_type(image_path: &str) -> &'static str {
+	let extension = Path::new(image_path)
+		.extension()
+		.and_then(|extension| extension.to_str())
+		.map(str::to_lowercase);
+	match extension.as_deref() {
+		Some("jpg") | Some("jpeg") => "image/jpeg",
+		Some("png") => "image/png",
+		Some("gif") => "image/gif",
+		Some("webp") => "image/webp",
+		Some("bmp") => "image/bmp",
+		_ => "application/octet-stream",
+	}
+}
+
+/// Sends a request, returning the body of the response.  Unsuccessful statuses are returned as errors.
+pub(crate) async fn send(request: surf::Request<Client>) -> Result<String> {
+	let mut response = request.await?;
+	let status = response.status();
+	let body = response.body_string().await?;
+	if status.is_success() {
+		Ok(body)
+	} else {
+		Err(Error::from_http_status(status.as_u16(), body))
+	}
+}
+
+/// Returns a ``multipart/form-data`` body with the given text fields and optionally a file, and the Content-Type header for it.
+#[cfg(any(feature = "ascii2d", feature = "iqdb"))]
+pub(crate) async fn multipart(fields: &[(&str, &str)], file: Option<(&str, &str)>) -> Result<(Vec<u8>, String)> {
+	const BOUNDARY: &str = "----rustnao-multipart-boundary";
+	let mut body = Vec::new();
+	for (name, value) in fields {
+		body.extend_from_slice(format!("--{}\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\n{}\r\n", BOUNDARY, name, value).as_bytes());
+	}
+	if let Some((name, image_path)) = file {
+		let file_name = Path::new(image_path)
+			.file_name()
+			.and_then(|file_name| file_name.to_str())
+			.unwrap_or("image");
+		body.extend_from_slice(
+			format!(
+				"--{}\r\nContent-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\nContent-Type: {}\r\n\r\n",
+				BOUNDARY,
+				name,
+				file_name,
+				content_type(image_path)
+			)
+			.as_bytes(),
+		);
+		body.extend_from_slice(&async_std::fs::read(image_path).await?);
+		body.extend_from_slice(b"\r\n");
+	}
+	body.extend_from_slice(format!("--{}--\r\n", BOUNDARY).as_bytes());
+	Ok((body, format!("multipart/form-data; boundary={}", BOUNDARY)))
+}
+
+/// Returns the SauceNAO index and the name of the site of a URL, using the name SauceNAO uses if it indexes the site and the host otherwise.
+#[cfg(any(feature = "ascii2d", feature = "iqdb"))]
+pub(crate) fn site_of(url: &str) -> (u32, String) {
+	use super::constants;
+	let host = match Url::parse(url) {
+		Ok(url) => url.host_str().unwrap_or_default().trim_start_matches("www.").to_string(),
+		Err(_) => return (UNKNOWN_INDEX, String::new()),
+	};
+	let source = match host.as_str() {
+		"pixiv.net" => Some(constants::PIXIV),
+		"seiga.nicovideo.jp" => Some(constants::NICO_NICO_SEIGA),
+		"danbooru.donmai.us" => Some(constants::DANBOORU),
+		"drawr.net" => Some(constants::DRAWR),
+		"nijie.info" => Some(constants::NIJIE),
+		"yande.re" => Some(constants::YANDE_RE),
+		"medibang.com" => Some(constants::MEDIBANG),
+		"gelbooru.com" => Some(constants::GELBOORU),
+		"konachan.com" => Some(constants::KONACHAN),
+		"chan.sankakucomplex.com" => Some(constants::SANKAKU_CHANNEL),
+		"anime-pictures.net" => Some(constants::ANIME_PICTURES_NET),
+		"e621.net" => Some(constants::E621_NET),
+		"idol.sankakucomplex.com" => Some(constants::IDOL_COMPLEX),
+		"deviantart.com" => Some(constants::DEVIANTART),
+		"pawoo.net" => Some(constants::PAWOO_NET),
+		"mangadex.org" => Some(constants::MANGADEX),
+		_ => None,
+	};
+	match source {
+		Some(source) => (source.index, source.name.to_string()),
+		None => (UNKNOWN_INDEX, host),
+	}
+}
+
+/// Resolves a link found in a page against the URL of the page, such as a relative thumbnail path or a protocol relative link.
+#[cfg(any(feature = "ascii2d", feature = "iqdb"))]
+pub(crate) fn resolve(base: &Url, link: &str) -> String {
+	base.join(link).map(String::from).unwrap_or_else(|_| link.to_string())
+}
@@ -0,0 +1,124 @@
+//! A backend for [ascii2d](https://ascii2d.net/), which searches illustration sites such as Pixiv and Twitter by colour.
+
+use super::html;
+use super::{is_link, multipart, resolve, send, site_of, LazyClient};
+use crate::handler::search::{check_parameters, limit, ReverseImageSearch, SearchFuture};
+use crate::handler::{Result, Sauce, SauceBuilder};
+use url::Url;
+
+/// The URL of ascii2d.
+const ASCII2D_URL: &str = "https://ascii2d.net/";
+
+/// Searches [ascii2d](https://ascii2d.net/) for the sources of images.  Requires the ``ascii2d`` feature.
+///
+/// ascii2d does not report how similar its results are, so their ``similarity`` is always 0 and ``min_similarity`` only rejects invalid values.  Each
+/// result has the URL of the work, and the ``additional_fields`` hold the ``engine`` (``"ascii2d"``) and, if ascii2d knows the author, their
+/// ``author_name`` and ``author_url``.
+///
+/// ascii2d has no API either, so the results are read from its result page on a best-effort basis, and items without a linked work are skipped.
+/// ### Example
+/// ```no_run
+/// use rustnao::{Ascii2d, ReverseImageSearch};
+/// let ascii2d = Ascii2d::new();
+/// let result = ascii2d.search_blocking("https://i.imgur.com/W42kkKS.jpg", Some(5), None);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Ascii2d {
+	api_url: Option<String>,
+	client: LazyClient,
+}
+
+impl Ascii2d {
+	/// Creates a new ascii2d backend.
+	pub fn new() -> Ascii2d {
+		Ascii2d::default()
+	}
+
+	/// Sets the base URL of ascii2d, such as a local stand-in for tests.  By default this is ``https://ascii2d.net/``.
+	///
+	/// ### Arguments
+	/// * api_url - A string slice that contains the base URL, ending with a slash.
+	///
+	/// ### Examples
+	/// ```
+	/// use rustnao::Ascii2d;
+	/// let mut ascii2d = Ascii2d::new();
+	/// ascii2d.api_url("http://localhost:8080/");
+	/// ```
+	pub fn api_url(&mut self, api_url: &str) -> &mut Ascii2d {
+		self.api_url = Some(api_url.to_string());
+		self
+	}
+
+	async fn find(&self, image_path: &str, num_results: Option<u32>, min_similarity: Option<f64>) -> Result<Vec<Sauce>> {
+		check_parameters(num_results, min_similarity)?;
+		let base = Url::parse(self.api_url.as_deref().unwrap_or(ASCII2D_URL))?;
+		// ascii2d redirects either search to the page of its results
+		let request = if is_link(image_path) {
+			let url = base.join(
+				format!(
+					"search/url/{}",
+					url::form_urlencoded::byte_serialize(image_path.as_bytes()).collect::<String>()
+				)
+				.as_str(),
+			)?;
+			surf::Request::with_client(surf::http::Method::GET, url, self.client.get()?)
+		} else {
+			let (body, content_type) = multipart(&[], Some(("file", image_path))).await?;
+			surf::Request::with_client(surf::http::Method::POST, base.join("search/file")?, self.client.get()?)
+				.body_bytes(body)
+				.set_header("Content-Type", content_type)
+		};
+		let page = send(request).await?;
+		// Every similarity is 0, so min_similarity would reject every result
+		Ok(limit(parse(&base, page.as_str()), num_results, None))
+	}
+}
+
+impl ReverseImageSearch for Ascii2d {
+	fn search<'a>(&'a self, image_path: &'a str, num_results: Option<u32>, min_similarity: Option<f64>) -> SearchFuture<'a, Vec<Sauce>> {
+		Box::pin(self.find(image_path, num_results, min_similarity))
+	}
+}
+
+/// Parses the results of an ascii2d page.  Every result is an item box, and those without a linked work, such as the searched image, are skipped.
+fn parse(base: &Url, page: &str) -> Vec<Sauce> {
+	html::sections(page, "item-box")
+		.into_iter()
+		.filter_map(|item| {
+			let detail = &item[item.find("detail-box")?..];
+			let anchors = html::sections(detail, "<a ");
+			let work = anchors.first()?;
+			let work_url = html::attribute(work, "href")?;
+			let title = html::text(&work[..work.find("</a>")?]);
+			let author = anchors.get(1).and_then(|author| {
+				let name = html::text(&author[..author.find("</a>")?]);
+				Some((name, html::attribute(author, "href")?))
+			});
+			let (index, site) = site_of(work_url.as_str());
+
+			let mut additional_fields = serde_json::json!({ "engine": "ascii2d" });
+			if let Some((name, url)) = author {
+				additional_fields["author_name"] = serde_json::Value::from(name);
+				additional_fields["author_url"] = serde_json::Value::from(url);
+			}
+			let mut builder = SauceBuilder::default();
+			builder
+				.ext_urls(vec![work_url])
+				.site(site.as_str())
+				.index(index)
+				.additional_fields(additional_fields);
+			if !title.is_empty() {
+				builder.title(title.as_str());
+			}
+			let thumbnail = html::tags(item, "img")
+				.into_iter()
+				.filter_map(|tag| html::attribute(tag, "src"))
+				.find(|src| src.contains("/thumbnail/"));
+			if let Some(thumbnail) = thumbnail {
+				builder.thumbnail(resolve(base, thumbnail.as_str()).as_str());
+			}
+			Some(builder.build())
+		})
+		.collect()
+}
@@ -0,0 +1,79 @@
+//! A minimal scanner for the result pages of iqdb and ascii2d.  It only understands as much HTML as those pages need, and is not a general parser,
+//! so the layouts it is known to handle are kept as fixtures in ``tests/fixtures``.
+
+/// Returns the parts of a page that start with each occurrence of the given marker, up to the next occurrence.
+pub(crate) fn sections<'a>(html: &'a str, marker: &str) -> Vec<&'a str> {
+	let starts: Vec<usize> = html.match_indices(marker).map(|(start, _)| start).collect();
+	starts
+		.iter()
+		.enumerate()
+		.map(|(position, &start)| &html[start..starts.get(position + 1).copied().unwrap_or(html.len())])
+		.collect()
+}
+
+/// Returns the opening tags with the given name in a fragment, such as ``<a href="...">`` for ``a``.
+pub(crate) fn tags<'a>(html: &'a str, name: &str) -> Vec<&'a str> {
+	let open = format!("<{}", name);
+	html.match_indices(open.as_str())
+		.filter_map(|(start, _)| {
+			let rest = &html[start..];
+			// Excludes longer tag names starting with the same letters, such as <abbr> for <a>
+			match rest[open.len()..].chars().next() {
+				Some(c) if c.is_whitespace() || c == '>' || c == '/' => rest.find('>').map(|end| &rest[..=end]),
+				_ => None,
+			}
+		})
+		.collect()
+}
+
+/// Returns the value of an attribute of an opening tag, which may be quoted with either kind of quote.
+pub(crate) fn attribute(tag: &str, name: &str) -> Option<String> {
+	let mut rest = tag;
+	while let Some(position) = rest.find(name) {
+		let before = rest[..position].chars().last();
+		let after = rest[position + name.len()..].trim_start();
+		rest = &rest[position + name.len()..];
+		if !before.is_some_and(char::is_whitespace) || !after.starts_with('=') {
+			continue;
+		}
+		let value = after[1..].trim_start();
+		let quote = value.chars().next()?;
+		let value = if quote == '"' || quote == '\'' {
+			let value = &value[1..];
+			&value[..value.find(quote)?]
+		} else {
+			&value[..value.find(|c: char| c.is_whitespace() || c == '>').unwrap_or(value.len())]
+		};
+		return Some(decode(value));
+	}
+	None
+}
+
+/// Returns the text of a fragment with the tags removed, the entities decoded and the whitespace collapsed.
+pub(crate) fn text(html: &str) -> String {
+	let mut text = String::new();
+	let mut in_tag = false;
+	for c in html.chars() {
+		match c {
+			'<' => {
+				in_tag = true;
+				text.push(' ');
+			}
+			'>' if in_tag => in_tag = false,
+			_ if !in_tag => text.push(c),
+			_ => {}
+		}
+	}
+	decode(text.split_whitespace().collect::<Vec<&str>>().join(" ").as_str())
+}
+
+/// Decodes the entities that appear in the pages.
+fn decode(text: &str) -> String {
+	text.replace("&lt;", "<")
+		.replace("&gt;", ">")
+		.replace("&quot;", "\"")
+		.replace("&#39;", "'")
+		.replace("&#x27;", "'")
+		.replace("&nbsp;", " ")
+		.replace("&amp;", "&")
+}
@@ -0,0 +1,121 @@
+//! A backend for [iqdb](https://iqdb.org/), which searches image boards such as Danbooru, Gelbooru and yande.re.
+
+use super::html;
+use super::{is_link, multipart, resolve, send, site_of, LazyClient};
+use crate::handler::search::{check_parameters, limit, ReverseImageSearch, SearchFuture};
+use crate::handler::{Result, Sauce, SauceBuilder};
+use url::Url;
+
+/// The URL iqdb is searched through.
+const IQDB_URL: &str = "https://iqdb.org/";
+
+/// Searches [iqdb](https://iqdb.org/) for the sources of images.  Requires the ``iqdb`` feature.
+///
+/// Each result has a URL of every board the match was found on, the first of which decides the ``site`` and ``index``.  The ``additional_fields``
+/// hold the ``engine`` (``"iqdb"``), the kind of ``match`` iqdb reports (such as ``"Best match"``), and the ``tags`` and ``rating`` of the post.
+///
+/// The results are read from the result page of iqdb, as it has no API, so they are best-effort: a match whose layout is not recognised is skipped.
+/// ### Example
+/// ```no_run
+/// use rustnao::{Iqdb, ReverseImageSearch};
+/// let iqdb = Iqdb::new();
+/// let result = iqdb.search_blocking("https://i.imgur.com/W42kkKS.jpg", None, Some(80.0));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Iqdb {
+	api_url: Option<String>,
+	client: LazyClient,
+}
+
+impl Iqdb {
+	/// Creates a new iqdb backend.
+	pub fn new() -> Iqdb {
+		Iqdb::default()
+	}
+
+	/// Sets the URL iqdb is searched through, such as a local stand-in for tests.  By default this is ``https://iqdb.org/``.
+	///
+	/// ### Arguments
+	/// * api_url - A string slice that contains the URL to send searches to.
+	///
+	/// ### Examples
+	/// ```
+	/// use rustnao::Iqdb;
+	/// let mut iqdb = Iqdb::new();
+	/// iqdb.api_url("http://localhost:8080/");
+	/// ```
+	pub fn api_url(&mut self, api_url: &str) -> &mut Iqdb {
+		self.api_url = Some(api_url.to_string());
+		self
+	}
+
+	async fn find(&self, image_path: &str, num_results: Option<u32>, min_similarity: Option<f64>) -> Result<Vec<Sauce>> {
+		check_parameters(num_results, min_similarity)?;
+		let url = Url::parse(self.api_url.as_deref().unwrap_or(IQDB_URL))?;
+		let (body, content_type) = if is_link(image_path) {
+			multipart(&[("url", image_path)], None).await?
+		} else {
+			multipart(&[], Some(("file", image_path))).await?
+		};
+		let request = surf::Request::with_client(surf::http::Method::POST, url.clone(), self.client.get()?)
+			.body_bytes(body)
+			.set_header("Content-Type", content_type);
+		let page = send(request).await?;
+		Ok(limit(parse(&url, page.as_str()), num_results, min_similarity))
+	}
+}
+
+impl ReverseImageSearch for Iqdb {
+	fn search<'a>(&'a self, image_path: &'a str, num_results: Option<u32>, min_similarity: Option<f64>) -> SearchFuture<'a, Vec<Sauce>> {
+		Box::pin(self.find(image_path, num_results, min_similarity))
+	}
+}
+
+/// Parses the results of an iqdb page, one per table.
+fn parse(base: &Url, page: &str) -> Vec<Sauce> {
+	html::sections(page, "<table")
+		.into_iter()
+		.filter_map(|table| {
+			let start = table.find("<th")?;
+			let heading = html::text(&table[start..start + table[start..].find("</th>")?]);
+			// The first table shows the searched image, and iqdb still lists its closest guesses after saying there are no relevant matches
+			if heading == "Your image" || heading == "No relevant matches" {
+				return None;
+			}
+			let ext_urls: Vec<String> = html::tags(table, "a")
+				.into_iter()
+				.filter_map(|tag| html::attribute(tag, "href"))
+				.map(|href| match href.strip_prefix("//") {
+					// iqdb links to the boards without a scheme, and every board it searches uses HTTPS
+					Some(href) => format!("https://{}", href),
+					None => resolve(base, href.as_str()),
+				})
+				.collect();
+			let (index, site) = site_of(ext_urls.first()?);
+			let image = html::tags(table, "img").into_iter().next()?;
+			let description = html::attribute(image, "alt").unwrap_or_default();
+			let text = html::text(table);
+			let similarity = text.split(" similarity").next()?.rsplit(' ').next()?.trim_end_matches('%').parse().ok()?;
+			let rating = text
+				.rfind('[')
+				.and_then(|start| text[start + 1..].find(']').map(|end| &text[start + 1..start + 1 + end]));
+
+			let mut builder = SauceBuilder::default();
+			builder
+				.ext_urls(ext_urls)
+				.site(site.as_str())
+				.index(index)
+				.similarity(similarity)
+				.additional_fields(serde_json::json!({
+					"engine": "iqdb",
+					"match": heading,
+					"tags": description.split("Tags: ").nth(1).unwrap_or_default(),
+					"rating": rating,
+				}));
+			if let Some(src) = html::attribute(image, "src") {
+				builder.thumbnail(resolve(base, src.as_str()).as_str());
+			}
+			Some(builder.build())
+		})
+		.collect()
+}
@@ -0,0 +1,144 @@
+//! A backend for [trace.moe](https://trace.moe/), which finds the scene of an anime a screenshot is from.
+
+use super::{content_type, is_link, send, LazyClient};
+use crate::handler::constants;
+use crate::handler::search::{check_parameters, limit, ReverseImageSearch, SearchFuture};
+use crate::handler::{Error, Result, Sauce, SauceBuilder};
+use serde::Deserialize;
+use url::Url;
+
+/// The URL of the trace.moe API.
+const TRACE_MOE_URL: &str = "https://api.trace.moe/";
+
+#[derive(Deserialize, Debug)]
+struct Response {
+	#[serde(default)]
+	error: String,
+	#[serde(default)]
+	result: Vec<Scene>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Scene {
+	anilist: Anilist,
+	filename: Option<String>,
+	episode: Option<serde_json::Value>,
+	from: Option<f64>,
+	to: Option<f64>,
+	similarity: f64,
+	video: Option<String>,
+	image: Option<String>,
+}
+
+/// The AniList entry of a scene, which is only the ID unless the information was asked for.
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+enum Anilist {
+	Id(u64),
+	Info { id: u64, title: Option<Titles> },
+}
+
+#[derive(Deserialize, Debug)]
+struct Titles {
+	english: Option<String>,
+	romaji: Option<String>,
+	native: Option<String>,
+}
+
+/// Searches [trace.moe](https://trace.moe/) for the anime scenes images are from.  Requires the ``tracemoe`` feature.
+///
+/// The results are from the ``Anime`` index, with the AniList page of the anime as their URL and its title, falling back to the name of the video
+/// file.  The ``additional_fields`` hold the ``engine`` (``"trace.moe"``), the ``anilist_id``, the ``episode``, the time in seconds the scene is
+/// ``from`` and ``to``, and the URL of a ``video`` of the scene.
+/// ### Example
+/// ```no_run
+/// use rustnao::{ReverseImageSearch, TraceMoe};
+/// let trace_moe = TraceMoe::new();
+/// let result = trace_moe.search_blocking("https://i.imgur.com/W42kkKS.jpg", Some(1), Some(90.0));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct TraceMoe {
+	api_url: Option<String>,
+	client: LazyClient,
+}
+
+impl TraceMoe {
+	/// Creates a new trace.moe backend.
+	pub fn new() -> TraceMoe {
+		TraceMoe::default()
+	}
+
+	/// Sets the base URL of the trace.moe API, such as a local stand-in for tests.  By default this is ``https://api.trace.moe/``.
+	///
+	/// ### Arguments
+	/// * api_url - A string slice that contains the base URL, ending with a slash.
+	///
+	/// ### Examples
+	/// ```
+	/// use rustnao::TraceMoe;
+	/// let mut trace_moe = TraceMoe::new();
+	/// trace_moe.api_url("http://localhost:8080/");
+	/// ```
+	pub fn api_url(&mut self, api_url: &str) -> &mut TraceMoe {
+		self.api_url = Some(api_url.to_string());
+		self
+	}
+
+	async fn find(&self, image_path: &str, num_results: Option<u32>, min_similarity: Option<f64>) -> Result<Vec<Sauce>> {
+		check_parameters(num_results, min_similarity)?;
+		let mut url = Url::parse(self.api_url.as_deref().unwrap_or(TRACE_MOE_URL))?.join("search")?;
+		url.query_pairs_mut().append_key_only("anilistInfo");
+		let request = if is_link(image_path) {
+			url.query_pairs_mut().append_pair("url", image_path);
+			surf::Request::with_client(surf::http::Method::GET, url, self.client.get()?)
+		} else {
+			surf::Request::with_client(surf::http::Method::POST, url, self.client.get()?)
+				.body_bytes(std::fs::read(image_path)?)
+				.set_header("Content-Type", content_type(image_path))
+		};
+		let response: Response = serde_json::from_str(send(request).await?.as_str())?;
+		if !response.error.is_empty() {
+			return Err(Error::invalid_request(response.error));
+		}
+		let results = response.result.into_iter().map(to_sauce).collect();
+		Ok(limit(results, num_results, min_similarity))
+	}
+}
+
+impl ReverseImageSearch for TraceMoe {
+	fn search<'a>(&'a self, image_path: &'a str, num_results: Option<u32>, min_similarity: Option<f64>) -> SearchFuture<'a, Vec<Sauce>> {
+		Box::pin(self.find(image_path, num_results, min_similarity))
+	}
+}
+
+fn to_sauce(scene: Scene) -> Sauce {
+	let (anilist_id, titles) = match scene.anilist {
+		Anilist::Id(id) => (id, None),
+		Anilist::Info { id, title } => (id, title),
+	};
+	let title = titles
+		.and_then(|titles| titles.english.or(titles.romaji).or(titles.native))
+		.or(scene.filename);
+
+	let mut builder = SauceBuilder::default();
+	builder
+		.ext_urls(vec![format!("https://anilist.co/anime/{}", anilist_id)])
+		.site(constants::ANIME.name)
+		.index(constants::ANIME.index)
+		.similarity((scene.similarity * 100.0) as f32)
+		.additional_fields(serde_json::json!({
+			"engine": "trace.moe",
+			"anilist_id": anilist_id,
+			"episode": scene.episode,
+			"from": scene.from,
+			"to": scene.to,
+			"video": scene.video,
+		}));
+	if let Some(title) = title {
+		builder.title(title.as_str());
+	}
+	if let Some(image) = scene.image {
+		builder.thumbnail(image.as_str());
+	}
+	builder.build()
+}
@@ -2,22 +2,33 @@
 // Much thanks to Andrew Gallant for the basis of this part of the library... followed the following
 // code for this: https://github.com/BurntSushi/imdb-rename/blob/master/imdb-index/src/error.rs.
 
-use failure::{Backtrace, Context, Fail};
-use std::{fmt, result};
+use std::{
+	error, fmt, result,
+	time::{Duration, SystemTime},
+};
 
 /// A type alias for handling errors related to rustnao.
 pub type Result<T> = result::Result<T, Error>;
 
 /// An error that can occur while interacting to the SauceNAO API.
+///
+/// The underlying error, such as the IO, JSON or HTTP error that caused it, is available through ``source()``.
 #[derive(Debug)]
 pub struct Error {
-	context: Context<ErrType>,
+	kind: ErrType,
+	source: Option<Box<dyn error::Error + Send + Sync + 'static>>,
 }
 
 impl Error {
 	/// Return the kind of error
 	pub fn kind(&self) -> &ErrType {
-		self.context.get_context()
+		&self.kind
+	}
+
+	/// Attaches the error that caused this error, to be returned by ``source()``.
+	pub(crate) fn with_source<E: Into<Box<dyn error::Error + Send + Sync + 'static>>>(mut self, source: E) -> Error {
+		self.source = Some(source.into());
+		self
 	}
 
 	pub(crate) fn invalid_parse<T: AsRef<str>>(unk: T) -> Error {
@@ -32,10 +43,6 @@ impl Error {
 		Error::from(ErrType::InvalidSerde(unk.as_ref().to_string()))
 	}
 
-	pub(crate) fn invalid_code(code: i32, message: String) -> Error {
-		Error::from(ErrType::InvalidCode { code, message })
-	}
-
 	pub(crate) fn invalid_request<T: AsRef<str>>(unk: T) -> Error {
 		Error::from(ErrType::InvalidRequest(unk.as_ref().to_string()))
 	}
@@ -43,26 +50,134 @@ impl Error {
 	pub(crate) fn invalid_parameter(message: String) -> Error {
 		Error::from(ErrType::InvalidParameters(message))
 	}
-}
 
-impl Fail for Error {
-	fn cause(&self) -> Option<&dyn Fail> {
-		self.context.cause()
+	pub(crate) fn timeout<T: AsRef<str>>(unk: T) -> Error {
+		Error::from(ErrType::Timeout(unk.as_ref().to_string()))
+	}
+
+	/// Classifies an unsuccessful status and message returned by SauceNAO into the matching kind of error.
+	///
+	/// SauceNAO uses positive statuses for server side errors and ``-2`` for its search limits, which ``long_remaining`` tells apart if the message
+	/// does not.  Other client side errors share negative statuses, so they are told apart by the known messages SauceNAO starts them with, and any
+	/// other message is kept as it is.
+	pub(crate) fn from_status(status: i32, message: String, long_remaining: Option<u32>) -> Error {
+		let err_type = if status > 0 {
+			ErrType::ServerError { code: status, message }
+		} else if status == -2 {
+			if starts_with_any(&message, &DAILY_LIMIT_MESSAGES) || long_remaining == Some(0) {
+				ErrType::DailyLimitExceeded {
+					reset_at: SystemTime::now() + LONG_LIMIT_WINDOW,
+					message,
+				}
+			} else {
+				ErrType::ShortLimitExceeded {
+					retry_after: SHORT_LIMIT_WINDOW,
+					message,
+				}
+			}
+		} else if starts_with_any(&message, &INVALID_API_KEY_MESSAGES) {
+			ErrType::InvalidApiKey(message)
+		} else if starts_with_any(&message, &FILE_TOO_LARGE_MESSAGES) {
+			ErrType::FileTooLarge(message)
+		} else if starts_with_any(&message, &UNSUPPORTED_IMAGE_MESSAGES) {
+			ErrType::UnsupportedImage(message)
+		} else {
+			ErrType::InvalidCode { code: status, message }
+		};
+		Error::from(err_type)
+	}
+
+	/// Classifies an unsuccessful HTTP status whose body could not be read as a SauceNAO response.
+	pub(crate) fn from_http_status(status: u16, body: String) -> Error {
+		let err_type = match status {
+			401 | 403 => ErrType::InvalidApiKey(body),
+			408 | 504 => ErrType::Timeout(body),
+			413 => ErrType::FileTooLarge(body),
+			415 => ErrType::UnsupportedImage(body),
+			429 => ErrType::ShortLimitExceeded {
+				retry_after: SHORT_LIMIT_WINDOW,
+				message: body,
+			},
+			500..=599 => ErrType::ServerError {
+				code: i32::from(status),
+				message: body,
+			},
+			_ => ErrType::InvalidRequest(format!("received HTTP status {}: {}", status, body)),
+		};
+		Error::from(err_type)
 	}
 
-	fn backtrace(&self) -> Option<&Backtrace> {
-		self.context.backtrace()
+	/// Returns whether the operation that caused this error could succeed if it is tried again later.
+	/// ## Example
+	/// ```
+	/// use rustnao::HandlerBuilder;
+	/// let handle = HandlerBuilder::default().api_key("your_api_key").build();
+	/// if let Err(err) = handle.get_sauce("https://i.imgur.com/W42kkKS.jpg", None, None) {
+	///     if err.is_retryable() {
+	///         println!("Try again later: {}", err);
+	///     }
+	/// }
+	/// ```
+	pub fn is_retryable(&self) -> bool {
+		self.kind().is_retryable()
+	}
+}
+
+/// The start of the messages SauceNAO sends when the daily search limit has been exceeded.
+const DAILY_LIMIT_MESSAGES: [&str; 1] = ["Daily Search Limit Exceeded"];
+
+/// The start of the messages SauceNAO sends when it rejects the API key or the account type.
+const INVALID_API_KEY_MESSAGES: [&str; 3] = [
+	"Invalid or wrong API key",
+	"Invalid API key",
+	"This account type does not permit API usage",
+];
+
+/// The start of the messages SauceNAO sends when the file is too large.
+const FILE_TOO_LARGE_MESSAGES: [&str; 1] = ["Specified file too large"];
+
+/// The start of the messages SauceNAO sends when it cannot use the image.
+const UNSUPPORTED_IMAGE_MESSAGES: [&str; 6] = [
+	"Image dimensions too small",
+	"Supplied URL is not usable",
+	"Problem with remote server or url is not usable",
+	"The file does not appear to be a valid image",
+	"Specified file does not seem to be an image",
+	"Specified file no longer exists on the remote server",
+];
+
+/// Returns whether the message starts with any of the known messages, ignoring case.
+fn starts_with_any(message: &str, known: &[&str]) -> bool {
+	let message = message.trim_start().to_lowercase();
+	known.iter().any(|known| message.starts_with(known.to_lowercase().as_str()))
+}
+
+/// The length of the short (per 30 seconds) search limit window of SauceNAO.
+pub(crate) const SHORT_LIMIT_WINDOW: Duration = Duration::from_secs(30);
+
+/// The length of the long (daily) search limit window of SauceNAO.
+pub(crate) const LONG_LIMIT_WINDOW: Duration = Duration::from_secs(24 * 60 * 60);
+
+impl error::Error for Error {
+	fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+		match &self.source {
+			Some(source) => Some(source.as_ref()),
+			None => None,
+		}
 	}
 }
 
 impl fmt::Display for Error {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-		self.context.fmt(f)
+		self.kind.fmt(f)
 	}
 }
 
 /// The specific type of error that can occur.
+///
+/// More kinds may be added in minor versions, so matches on it need a wildcard arm.
 #[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
 pub enum ErrType {
 	/// An error when forming the URL for the API.  
 	///
@@ -93,6 +208,60 @@ pub enum ErrType {
 	///
 	/// The data provided is an error message.
 	InvalidParameters(String),
+	/// An error when SauceNAO rejects the API key, or the account type is not allowed to use the API.
+	///
+	/// The data provided is the message from SauceNAO.
+	InvalidApiKey(String),
+	/// An error when the short (30 second) search limit has been exceeded.
+	ShortLimitExceeded {
+		/// How long to wait before searching again
+		retry_after: Duration,
+		/// The message from SauceNAO
+		message: String,
+	},
+	/// An error when the daily search limit has been exceeded.
+	DailyLimitExceeded {
+		/// An estimate of when searching is possible again, as SauceNAO does not report it
+		reset_at: SystemTime,
+		/// The message from SauceNAO
+		message: String,
+	},
+	/// An error when the file is larger than SauceNAO accepts.
+	///
+	/// The data provided is the message from SauceNAO.
+	FileTooLarge(String),
+	/// An error when SauceNAO cannot use the image, such as when it is not an image, is too small, or the URL cannot be fetched.
+	///
+	/// The data provided is the message from SauceNAO.
+	UnsupportedImage(String),
+	/// An error on the side of SauceNAO.  These are usually temporary.
+	ServerError {
+		/// The status code from SauceNAO, or the HTTP status code if the response could not be read
+		code: i32,
+		/// The message from SauceNAO
+		message: String,
+	},
+	/// An error when the request took too long.
+	///
+	/// The data provided is the error found.
+	Timeout(String),
+}
+
+impl ErrType {
+	/// Returns whether the operation that caused this kind of error could succeed if it is tried again later.  This is the case for timeouts, failed
+	/// requests, server errors and the short search limit, but not for the daily search limit, which takes too long to reset.
+	/// ## Example
+	/// ```
+	/// use rustnao::ErrType;
+	/// assert!(ErrType::Timeout("timed out".to_string()).is_retryable());
+	/// assert!(!ErrType::FileTooLarge("Specified file too large".to_string()).is_retryable());
+	/// ```
+	pub fn is_retryable(&self) -> bool {
+		matches!(
+			self,
+			ErrType::InvalidRequest(_) | ErrType::ShortLimitExceeded { .. } | ErrType::ServerError { .. } | ErrType::Timeout(_)
+		)
+	}
 }
 
 impl fmt::Display for ErrType {
@@ -104,54 +273,83 @@ impl fmt::Display for ErrType {
 			ErrType::InvalidCode { code, message } => write!(f, "ERROR: Recieved an invalid status code {} after API call with message: \"{}\"", code, message),
 			ErrType::InvalidRequest(ref unk) => write!(f, "ERROR: Failed to make the request, error was due to: {}", unk),
 			ErrType::InvalidParameters(message) => write!(f, "ERROR: An invalid parameter was passed, error was due to: {}", message),
+			ErrType::InvalidApiKey(message) => write!(f, "ERROR: The API key was rejected with message: \"{}\"", message),
+			ErrType::ShortLimitExceeded { retry_after, message } => write!(
+				f,
+				"ERROR: The short search limit was exceeded, retry in {} seconds. Message: \"{}\"",
+				retry_after.as_secs(),
+				message
+			),
+			ErrType::DailyLimitExceeded { message, .. } => write!(f, "ERROR: The daily search limit was exceeded with message: \"{}\"", message),
+			ErrType::FileTooLarge(message) => write!(f, "ERROR: The file was too large with message: \"{}\"", message),
+			ErrType::UnsupportedImage(message) => write!(f, "ERROR: The image could not be used with message: \"{}\"", message),
+			ErrType::ServerError { code, message } => write!(f, "ERROR: SauceNAO had a server error {} with message: \"{}\"", code, message),
+			ErrType::Timeout(ref unk) => write!(f, "ERROR: The request timed out, error was due to: {}", unk),
 		}
 	}
 }
 
 impl From<ErrType> for Error {
-	fn from(err_type: ErrType) -> Error {
-		Error::from(Context::new(err_type))
-	}
-}
-
-impl From<Context<ErrType>> for Error {
-	fn from(context: Context<ErrType>) -> Error {
-		Error { context }
+	fn from(kind: ErrType) -> Error {
+		Error { kind, source: None }
 	}
 }
 
 impl From<serde_json::Error> for Error {
 	fn from(err: serde_json::Error) -> Self {
-		Error::invalid_serde(err.to_string())
+		Error::invalid_serde(err.to_string()).with_source(err)
 	}
 }
 
 impl From<url::ParseError> for Error {
 	fn from(err: url::ParseError) -> Self {
-		Error::invalid_parse(err.to_string())
+		Error::invalid_parse(err.to_string()).with_source(err)
 	}
 }
 
 impl From<std::num::ParseIntError> for Error {
 	fn from(err: std::num::ParseIntError) -> Self {
-		Error::invalid_parse(err.to_string())
+		Error::invalid_parse(err.to_string()).with_source(err)
 	}
 }
 
 impl From<std::num::ParseFloatError> for Error {
 	fn from(err: std::num::ParseFloatError) -> Self {
-		Error::invalid_parse(err.to_string())
+		Error::invalid_parse(err.to_string()).with_source(err)
 	}
 }
 
 impl From<std::io::Error> for Error {
 	fn from(err: std::io::Error) -> Self {
-		Error::invalid_path(err.to_string())
+		Error::invalid_path(err.to_string()).with_source(err)
+	}
+}
+
+impl From<isahc::Error> for Error {
+	fn from(err: isahc::Error) -> Self {
+		match err {
+			isahc::Error::Timeout => Error::timeout(err.to_string()).with_source(err),
+			_ => Error::invalid_request(err.to_string()).with_source(err),
+		}
 	}
 }
 
 impl From<surf::Exception> for Error {
 	fn from(err: surf::Exception) -> Self {
-		Error::invalid_request(err.to_string())
+		// Errors of the client of the Handler are passed through surf as they are
+		let err = match err.downcast::<Error>() {
+			Ok(err) => return *err,
+			Err(err) => err,
+		};
+		let timed_out = if let Some(io_err) = err.downcast_ref::<std::io::Error>() {
+			io_err.kind() == std::io::ErrorKind::TimedOut
+		} else {
+			matches!(err.downcast_ref::<isahc::Error>(), Some(isahc::Error::Timeout))
+		};
+		if timed_out {
+			Error::timeout(err.to_string()).with_source(err)
+		} else {
+			Error::invalid_request(err.to_string()).with_source(err)
+		}
 	}
 }
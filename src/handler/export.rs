@@ -0,0 +1,338 @@
+//! Exporting of search results into formats other than JSON.
+
+use super::{Result, Sauce};
+use std::path::Path;
+
+/// The formats that results can be exported to with [`Export`](trait.Export.html).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ExportFormat {
+	/// Comma-separated values with a header row, following RFC 4180 quoting rules.  Fields that a spreadsheet would run as a formula are prefixed
+	/// with a ``'``.
+	Csv,
+	/// Newline-delimited JSON, with one serialized Sauce per line.
+	Ndjson,
+	/// A Markdown table, whose text is escaped so that it cannot add formatting, links or HTML.
+	Markdown,
+	/// A self-contained HTML report with thumbnails and links.
+	Html,
+}
+
+impl ExportFormat {
+	/// Returns the usual file extension for the format, without the leading dot.
+	/// ## Example
+	/// ```
+	/// use rustnao::ExportFormat;
+	/// assert_eq!(ExportFormat::Markdown.extension(), "md");
+	/// ```
+	pub fn extension(self) -> &'static str {
+		match self {
+			ExportFormat::Csv => "csv",
+			ExportFormat::Ndjson => "ndjson",
+			ExportFormat::Markdown => "md",
+			ExportFormat::Html => "html",
+		}
+	}
+}
+
+/// A trait to export results to CSV, NDJSON, Markdown and HTML strings or files.
+/// ### Example
+/// Exporting a Sauce vector into a CSV string:
+/// ```
+/// use rustnao::{Export, HandlerBuilder};
+/// let handle = HandlerBuilder::default().api_key("your_api_key").num_results(999).db(999).build();
+/// let result = handle.get_sauce("./tests/test.jpg", None, None);
+/// if result.is_ok() {
+///     result.unwrap().to_csv();
+/// }
+/// ```
+pub trait Export {
+	/// Converts to a Result containing a string in the given format.
+	/// ### Example
+	/// ```
+	/// use rustnao::{Export, ExportFormat, SauceBuilder};
+	/// let results = vec![SauceBuilder::default().site("Pixiv").similarity(92.5).build()];
+	/// let markdown = results.export(ExportFormat::Markdown).unwrap();
+	/// ```
+	/// ### Errors
+	/// There may be a problem serializing a Sauce, so this will throw an Error if that is encountered.
+	fn export(&self, format: ExportFormat) -> Result<String>;
+
+	/// Writes the results in the given format to a file, creating or truncating it.
+	/// ### Example
+	/// ```no_run
+	/// use rustnao::{Export, ExportFormat, SauceBuilder};
+	/// let results = vec![SauceBuilder::default().site("Pixiv").similarity(92.5).build()];
+	/// results.export_to_file("results.html", ExportFormat::Html).unwrap();
+	/// ```
+	/// ### Errors
+	/// If the results could not be converted, or the file could not be written to, an Error will be returned.
+	fn export_to_file<P: AsRef<Path>>(&self, path: P, format: ExportFormat) -> Result<()> {
+		std::fs::write(path, self.export(format)?)?;
+		Ok(())
+	}
+
+	/// Converts to a Result containing a CSV string.
+	/// ### Example
+	/// ```
+	/// use rustnao::{Export, SauceBuilder};
+	/// let results = vec![SauceBuilder::default().site("Pixiv").similarity(92.5).build()];
+	/// let csv = results.to_csv().unwrap();
+	/// ```
+	/// ### Errors
+	/// There may be a problem serializing the additional fields of a Sauce, so this will throw an Error if that is encountered.
+	fn to_csv(&self) -> Result<String> {
+		self.export(ExportFormat::Csv)
+	}
+
+	/// Converts to a Result containing a NDJSON string.
+	/// ### Example
+	/// ```
+	/// use rustnao::{Export, SauceBuilder};
+	/// let results = vec![SauceBuilder::default().site("Pixiv").similarity(92.5).build()];
+	/// let ndjson = results.to_ndjson().unwrap();
+	/// ```
+	/// ### Errors
+	/// There may be a problem converting a Sauce to a JSON string, so this will throw an Error if that is encountered.
+	fn to_ndjson(&self) -> Result<String> {
+		self.export(ExportFormat::Ndjson)
+	}
+
+	/// Converts to a Result containing a Markdown table.
+	/// ### Example
+	/// ```
+	/// use rustnao::{Export, SauceBuilder};
+	/// let results = vec![SauceBuilder::default().site("Pixiv").similarity(92.5).build()];
+	/// let markdown = results.to_markdown().unwrap();
+	/// ```
+	/// ### Errors
+	/// This currently never fails, but returns a Result to be consistent with the other formats.
+	fn to_markdown(&self) -> Result<String> {
+		self.export(ExportFormat::Markdown)
+	}
+
+	/// Converts to a Result containing a self-contained HTML report.
+	/// ### Example
+	/// ```
+	/// use rustnao::{Export, SauceBuilder};
+	/// let results = vec![SauceBuilder::default().site("Pixiv").similarity(92.5).build()];
+	/// let html = results.to_html().unwrap();
+	/// ```
+	/// ### Errors
+	/// This currently never fails, but returns a Result to be consistent with the other formats.
+	fn to_html(&self) -> Result<String> {
+		self.export(ExportFormat::Html)
+	}
+}
+
+impl Export for Vec<Sauce> {
+	fn export(&self, format: ExportFormat) -> Result<String> {
+		match format {
+			ExportFormat::Csv => to_csv(self),
+			ExportFormat::Ndjson => to_ndjson(self),
+			ExportFormat::Markdown => Ok(to_markdown(self)),
+			ExportFormat::Html => Ok(to_html(self)),
+		}
+	}
+}
+
+const CSV_HEADER: [&str; 8] = [
+	"similarity",
+	"site",
+	"index",
+	"index_id",
+	"title",
+	"ext_urls",
+	"thumbnail",
+	"additional_fields",
+];
+
+fn to_csv(sauces: &[Sauce]) -> Result<String> {
+	let mut result = CSV_HEADER.join(",");
+	result.push_str("\r\n");
+	for sauce in sauces {
+		let additional_fields = match &sauce.additional_fields {
+			Some(fields) => serde_json::to_string(fields)?,
+			None => String::new(),
+		};
+		let row = [
+			sauce.similarity.to_string(),
+			sauce.site.clone(),
+			sauce.index.to_string(),
+			sauce.index_id.to_string(),
+			sauce.title.clone().unwrap_or_default(),
+			sauce.ext_urls.join(" "),
+			sauce.thumbnail.clone(),
+			additional_fields,
+		];
+		result.push_str(row.iter().map(|field| escape_csv(field)).collect::<Vec<String>>().join(",").as_str());
+		result.push_str("\r\n");
+	}
+	Ok(result)
+}
+
+fn escape_csv(field: &str) -> String {
+	// Titles and sites come from third parties, so a field that would start a formula is prefixed with a quote against CSV injection
+	let field = if field.starts_with(&['=', '+', '-', '@', '\t', '\r'][..]) {
+		format!("'{}", field)
+	} else {
+		field.to_string()
+	};
+	if field.contains(&[',', '"', '\n', '\r'][..]) {
+		format!("\"{}\"", field.replace('"', "\"\""))
+	} else {
+		field.to_string()
+	}
+}
+
+fn to_ndjson(sauces: &[Sauce]) -> Result<String> {
+	let mut result = String::new();
+	for sauce in sauces {
+		result.push_str(serde_json::to_string(sauce)?.as_str());
+		result.push('\n');
+	}
+	Ok(result)
+}
+
+fn to_markdown(sauces: &[Sauce]) -> String {
+	let mut result = String::from("| Similarity | Site | Title | Links | Thumbnail |\n| ---: | --- | --- | --- | --- |\n");
+	for sauce in sauces {
+		let links = sauce
+			.ext_urls
+			.iter()
+			.enumerate()
+			.map(|(i, url)| {
+				if is_web_link(url) {
+					format!("[{}]({})", i + 1, escape_markdown_link(url))
+				} else {
+					escape_markdown(url)
+				}
+			})
+			.collect::<Vec<String>>()
+			.join(" ");
+		let thumbnail = if sauce.thumbnail.is_empty() {
+			String::new()
+		} else if is_web_link(&sauce.thumbnail) {
+			format!("![thumbnail]({})", escape_markdown_link(&sauce.thumbnail))
+		} else {
+			escape_markdown(&sauce.thumbnail)
+		};
+		result.push_str(
+			format!(
+				"| {:.2}% | {} | {} | {} | {} |\n",
+				sauce.similarity,
+				escape_markdown(&sauce.site),
+				escape_markdown(sauce.title.as_deref().unwrap_or("")),
+				links,
+				thumbnail
+			)
+			.as_str(),
+		);
+	}
+	result
+}
+
+/// Escapes the text of a table cell, so that it cannot add formatting, links or raw HTML to the Markdown.
+fn escape_markdown(text: &str) -> String {
+	let mut result = String::with_capacity(text.len());
+	for c in text.chars() {
+		match c {
+			'&' => result.push_str("&amp;"),
+			'<' => result.push_str("&lt;"),
+			'>' => result.push_str("&gt;"),
+			'\\' | '`' | '*' | '_' | '[' | ']' | '~' | '|' => {
+				result.push('\\');
+				result.push(c);
+			}
+			'\r' => {}
+			'\n' => result.push(' '),
+			_ => result.push(c),
+		}
+	}
+	result
+}
+
+/// Escapes the target of a Markdown link, where a parenthesis would end the link early, and a space or angle bracket would end it altogether.
+fn escape_markdown_link(url: &str) -> String {
+	let mut result = String::with_capacity(url.len());
+	for c in url.chars() {
+		match c {
+			'(' => result.push_str("%28"),
+			')' => result.push_str("%29"),
+			' ' => result.push_str("%20"),
+			'<' => result.push_str("%3C"),
+			'>' => result.push_str("%3E"),
+			'|' => result.push_str("%7C"),
+			'\r' | '\n' => {}
+			_ => result.push(c),
+		}
+	}
+	result
+}
+
+/// Returns whether a URL is an HTTP or HTTPS link, which are the only URLs exported as links, so that a ``javascript:`` URL from a result is
+/// never made clickable.
+fn is_web_link(url: &str) -> bool {
+	let url = url.trim_start().to_ascii_lowercase();
+	url.starts_with("http://") || url.starts_with("https://")
+}
+
+const HTML_STYLE: &str = "body{font-family:sans-serif;margin:2em;}table{border-collapse:collapse;}\
+th,td{border:1px solid #ccc;padding:0.5em;text-align:left;vertical-align:top;}\
+th{background:#eee;}img{max-width:150px;max-height:150px;}";
+
+fn to_html(sauces: &[Sauce]) -> String {
+	let mut result = String::from("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>RustNAO results</title>\n<style>");
+	result.push_str(HTML_STYLE);
+	result.push_str("</style>\n</head>\n<body>\n<h1>RustNAO results</h1>\n");
+	result.push_str(format!("<p>{} result(s)</p>\n", sauces.len()).as_str());
+	result.push_str("<table>\n<tr><th>Thumbnail</th><th>Similarity</th><th>Site</th><th>Title</th><th>Links</th></tr>\n");
+	for sauce in sauces {
+		let thumbnail = if sauce.thumbnail.is_empty() {
+			String::new()
+		} else if is_web_link(&sauce.thumbnail) {
+			format!("<img src=\"{}\" alt=\"thumbnail\">", escape_html(&sauce.thumbnail))
+		} else {
+			escape_html(&sauce.thumbnail)
+		};
+		let links = sauce
+			.ext_urls
+			.iter()
+			.map(|url| {
+				if is_web_link(url) {
+					format!("<a href=\"{0}\">{0}</a>", escape_html(url))
+				} else {
+					escape_html(url)
+				}
+			})
+			.collect::<Vec<String>>()
+			.join("<br>");
+		result.push_str(
+			format!(
+				"<tr><td>{}</td><td>{:.2}%</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+				thumbnail,
+				sauce.similarity,
+				escape_html(&sauce.site),
+				escape_html(sauce.title.as_deref().unwrap_or("")),
+				links
+			)
+			.as_str(),
+		);
+	}
+	result.push_str("</table>\n</body>\n</html>\n");
+	result
+}
+
+fn escape_html(text: &str) -> String {
+	let mut result = String::with_capacity(text.len());
+	for c in text.chars() {
+		match c {
+			'&' => result.push_str("&amp;"),
+			'<' => result.push_str("&lt;"),
+			'>' => result.push_str("&gt;"),
+			'"' => result.push_str("&quot;"),
+			'\'' => result.push_str("&#39;"),
+			_ => result.push(c),
+		}
+	}
+	result
+}
@@ -0,0 +1,268 @@
+//! A fallback chain, which broadens a search step by step until something is found.
+
+use super::search::{check_parameters, limit, ReverseImageSearch, SearchFuture};
+use super::{Error, Handler, Result, Sauce};
+use std::fmt;
+
+/// A step of a ``FallbackChain``, as reported in a ``FallbackResult``.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FallbackStep {
+	/// The search of the Handler, as it is configured.
+	Initial,
+	/// A search of every SauceNAO database, whatever the Handler is set to search.
+	AllDatabases,
+	/// The results of the last SauceNAO search with a lower minimum similarity.
+	MinSimilarity(f64),
+	/// A search with another engine, by the name it was added with.
+	Engine(String),
+}
+
+impl fmt::Display for FallbackStep {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			FallbackStep::Initial => write!(f, "initial search"),
+			FallbackStep::AllDatabases => write!(f, "all databases"),
+			FallbackStep::MinSimilarity(min_similarity) => write!(f, "minimum similarity {}", min_similarity),
+			FallbackStep::Engine(name) => write!(f, "engine {}", name),
+		}
+	}
+}
+
+/// The outcome of a search with a ``FallbackChain``.
+#[derive(Debug)]
+pub struct FallbackResult {
+	/// The results of the step that found something, or no results if no step did.
+	pub results: Vec<Sauce>,
+	/// The step that found the results, or None if no step found anything.
+	pub step: Option<FallbackStep>,
+	/// The steps that failed, in order, with their errors.
+	pub errors: Vec<(FallbackStep, Error)>,
+}
+
+enum Step {
+	AllDatabases,
+	MinSimilarity(f64),
+	Engine(String, Box<dyn ReverseImageSearch>),
+}
+
+/// Searches with a ``Handler``, and if nothing is found, goes through a chain of broader searches until one finds something.
+///
+/// The steps are tried in the order they were added, and each broadens the ones before it: once every database has been searched, later SauceNAO
+/// steps keep searching every database, and once the minimum similarity has been lowered, later steps, including other engines, use the lower one.
+/// Steps that lower the minimum similarity filter the results of the last SauceNAO search again instead of searching again, so they cost no searches,
+/// and are skipped if no SauceNAO search has succeeded.  Searching every database is skipped if the Handler already does.
+///
+/// A step that fails is recorded in ``FallbackResult::errors`` and the chain moves on to the next one, so another engine can take over when SauceNAO
+/// limits are exceeded.  An error is only returned if every step that was tried failed.
+/// ### Example
+/// ```
+/// use rustnao::{FakeSearch, FallbackChain, HandlerBuilder, SauceBuilder};
+/// let mut fake = FakeSearch::new();
+/// fake.results("https://i.imgur.com/W42kkKS.jpg", vec![SauceBuilder::default().site("Pixiv").similarity(92.5).build()]);
+///
+/// let handle = HandlerBuilder::default().api_key("your_api_key").db(5).build();
+/// let mut chain = FallbackChain::new(handle);
+/// chain.all_databases().min_similarity(40.0).engine("fake", fake);
+/// if let Ok(result) = chain.get_sauce("https://i.imgur.com/W42kkKS.jpg", None, Some(80.0)) {
+///     if let Some(step) = result.step {
+///         println!("Found {} results with the {}", result.results.len(), step);
+///     }
+/// }
+/// ```
+pub struct FallbackChain {
+	handler: Handler,
+	steps: Vec<Step>,
+}
+
+impl fmt::Debug for FallbackChain {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let steps: Vec<FallbackStep> = self.steps.iter().map(Step::report).collect();
+		f.debug_struct("FallbackChain")
+			.field("handler", &self.handler)
+			.field("steps", &steps)
+			.finish()
+	}
+}
+
+impl Step {
+	fn report(&self) -> FallbackStep {
+		match self {
+			Step::AllDatabases => FallbackStep::AllDatabases,
+			Step::MinSimilarity(min_similarity) => FallbackStep::MinSimilarity(*min_similarity),
+			Step::Engine(name, _) => FallbackStep::Engine(name.clone()),
+		}
+	}
+}
+
+impl FallbackChain {
+	/// Creates a chain that only searches with the given Handler, until steps are added.
+	///
+	/// ### Arguments
+	/// * handler - The Handler to search with first.
+	pub fn new(handler: Handler) -> FallbackChain {
+		FallbackChain { handler, steps: Vec::new() }
+	}
+
+	/// Returns the Handler of the chain, such as to check its limits.
+	pub fn handler(&self) -> &Handler {
+		&self.handler
+	}
+
+	/// Adds a step that searches every SauceNAO database, whatever the Handler is set to search.
+	///
+	/// ### Examples
+	/// ```
+	/// use rustnao::{FallbackChain, Handler, HandlerBuilder};
+	/// let mut chain = FallbackChain::new(HandlerBuilder::default().db(Handler::PIXIV).build());
+	/// chain.all_databases();
+	/// ```
+	pub fn all_databases(&mut self) -> &mut FallbackChain {
+		self.steps.push(Step::AllDatabases);
+		self
+	}
+
+	/// Adds a step that lowers the minimum similarity.
+	///
+	/// ### Arguments
+	/// * min_similarity - The lower minimum similarity, which can be any value that converts to a f64, from 0.0 to 100.0.  It must not be above the
+	///   minimum similarity before this step, or searching with the chain returns an error.
+	///
+	/// ### Examples
+	/// ```
+	/// use rustnao::{FallbackChain, HandlerBuilder};
+	/// let mut chain = FallbackChain::new(HandlerBuilder::default().build());
+	/// chain.min_similarity(60).min_similarity(30);
+	/// ```
+	pub fn min_similarity<T: Into<f64>>(&mut self, min_similarity: T) -> &mut FallbackChain {
+		self.steps.push(Step::MinSimilarity(min_similarity.into()));
+		self
+	}
+
+	/// Adds a step that hands the search off to another engine.
+	///
+	/// ### Arguments
+	/// * name - A string slice that names the engine in a ``FallbackResult``.
+	/// * engine - Anything that implements ``ReverseImageSearch``, such as another Handler.
+	///
+	/// ### Examples
+	/// ```
+	/// use rustnao::{FallbackChain, HandlerBuilder};
+	/// let mut chain = FallbackChain::new(HandlerBuilder::default().api_key("your_api_key").build());
+	/// chain.engine("second key", HandlerBuilder::default().api_key("another_api_key").build());
+	/// ```
+	pub fn engine<S: ReverseImageSearch + 'static>(&mut self, name: &str, engine: S) -> &mut FallbackChain {
+		self.steps.push(Step::Engine(name.to_string(), Box::new(engine)));
+		self
+	}
+
+	/// Returns the results of the first step of the chain that finds something, along with which step that was.
+	/// ## Arguments
+	/// * ``image_path`` - A string slice that contains the url or local path of the image you wish to look up.
+	/// * ``num_results`` - An Option containing a u32 to specify the maximum number of results.  If this is None, the number set in the Handler is used.
+	/// * ``min_similarity`` - An Option containing a f64 to specify the minimum similarity to start with.  If this is None, the minimum similarity of
+	///   the Handler is used.
+	///
+	/// ## Example
+	/// ```
+	/// use rustnao::{FallbackChain, HandlerBuilder};
+	/// let mut chain = FallbackChain::new(HandlerBuilder::default().api_key("your_api_key").db(5).build());
+	/// chain.all_databases();
+	/// let result = chain.get_sauce("./tests/test.jpg", None, Some(60.0));
+	/// ```
+	///
+	/// ## Errors
+	/// If the parameters or a minimum similarity of the chain are invalid, or a minimum similarity of the chain is above the one before it, an error of
+	/// the kind ``ErrType::InvalidParameters`` is returned.  Otherwise, an error is only returned if every step that was tried failed, and it is the
+	/// error of the first step.
+	pub fn get_sauce(&self, image_path: &str, num_results: Option<u32>, min_similarity: Option<f64>) -> Result<FallbackResult> {
+		async_std::task::block_on(self.async_get_sauce(image_path, num_results, min_similarity))
+	}
+
+	/// Asynchronously returns the results of the first step of the chain that finds something.  Otherwise identical to ``get_sauce(...)``.
+	pub async fn async_get_sauce(&self, image_path: &str, num_results: Option<u32>, min_similarity: Option<f64>) -> Result<FallbackResult> {
+		check_parameters(num_results, min_similarity)?;
+		let mut min_similarity = min_similarity.unwrap_or_else(|| self.handler.get_min_similarity());
+		let mut threshold = min_similarity;
+		for step in &self.steps {
+			if let Step::MinSimilarity(lower) = step {
+				check_parameters(None, Some(*lower))?;
+				if *lower > threshold {
+					return Err(Error::invalid_parameter(format!(
+						"a min_similarity step of {} would raise the minimum similarity of {}",
+						lower, threshold
+					)));
+				}
+				threshold = *lower;
+			}
+		}
+
+		let mut all_databases = false;
+		// The results of the last SauceNAO search, before filtering by similarity
+		let mut unfiltered: Option<Vec<Sauce>> = None;
+		let mut succeeded = false;
+		let mut errors = Vec::new();
+
+		let initial = std::iter::once((FallbackStep::Initial, None));
+		for (report, step) in initial.chain(self.steps.iter().map(|step| (step.report(), Some(step)))) {
+			let outcome = match step {
+				None => self.search_handler(image_path, num_results, all_databases).await,
+				Some(Step::AllDatabases) if all_databases || self.handler.searches_all_databases() => continue,
+				Some(Step::AllDatabases) => {
+					all_databases = true;
+					self.search_handler(image_path, num_results, all_databases).await
+				}
+				Some(Step::MinSimilarity(lower)) => {
+					min_similarity = *lower;
+					match &unfiltered {
+						Some(results) => Ok(results.clone()),
+						None => continue,
+					}
+				}
+				Some(Step::Engine(_, engine)) => engine.search(image_path, num_results, Some(min_similarity)).await,
+			};
+			let results = match outcome {
+				Ok(results) => results,
+				Err(err) => {
+					errors.push((report, err));
+					continue;
+				}
+			};
+			succeeded = true;
+			if !matches!(step, Some(Step::Engine(..))) {
+				unfiltered = Some(results.clone());
+			}
+			let results = limit(results, num_results, Some(min_similarity));
+			if !results.is_empty() {
+				return Ok(FallbackResult {
+					results,
+					step: Some(report),
+					errors,
+				});
+			}
+		}
+
+		if !succeeded && !errors.is_empty() {
+			return Err(errors.remove(0).1);
+		}
+		Ok(FallbackResult {
+			results: Vec::new(),
+			step: None,
+			errors,
+		})
+	}
+
+	/// Searches with the Handler without filtering by similarity, so that the results can be filtered again by later steps.
+	async fn search_handler(&self, image_path: &str, num_results: Option<u32>, all_databases: bool) -> Result<Vec<Sauce>> {
+		if all_databases {
+			self.handler.async_get_sauce_from_all_databases(image_path, num_results, Some(0.0)).await
+		} else {
+			self.handler.async_get_sauce(image_path, num_results, Some(0.0)).await
+		}
+	}
+}
+
+impl ReverseImageSearch for FallbackChain {
+	fn search<'a>(&'a self, image_path: &'a str, num_results: Option<u32>, min_similarity: Option<f64>) -> SearchFuture<'a, Vec<Sauce>> {
+		Box::pin(async move { Ok(self.async_get_sauce(image_path, num_results, min_similarity).await?.results) })
+	}
+}
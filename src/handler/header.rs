@@ -0,0 +1,109 @@
+//! Parsing of the per-result headers returned by the SauceNAO API.
+
+use super::{deserialize::Header, Error, Result};
+
+/// The values extracted from the header of a single result.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ParsedHeader {
+	/// The official index of the result, taken from ``index_name`` or falling back to ``index_id``.
+	pub index: u32,
+	/// The similarity of the result, in percent.
+	pub similarity: f64,
+}
+
+/// Parses the header of a single result.
+///
+/// The index is read from ``index_name``, which normally looks like ``Index #5: Pixiv Images - 61477678_p0.jpg``.  If ``index_name`` is not in that
+/// format, the ``index_id`` of the header is used instead.  The similarity must be a finite number, otherwise an error is returned.
+pub(crate) fn parse_header(header: &Header) -> Result<ParsedHeader> {
+	let index = match header.index_name.value().and_then(|index_name| parse_index_name(index_name.as_str())) {
+		Some(index) => index,
+		None => *header
+			.index_id
+			.value()
+			.ok_or_else(|| Error::invalid_parse("neither index_name nor index_id contain an index"))?,
+	};
+	let similarity = match header.similarity.value() {
+		Some(similarity) => parse_similarity(similarity.as_str())?,
+		None => return Err(Error::invalid_parse("similarity is missing")),
+	};
+	Ok(ParsedHeader { index, similarity })
+}
+
+/// Extracts the index number following the ``#`` in the part of ``index_name`` before the first ``:``.
+fn parse_index_name(index_name: &str) -> Option<u32> {
+	let prefix = index_name.split(':').next()?;
+	let (_, after_hash) = prefix.split_at(prefix.find('#')? + 1);
+	let digits: &str = &after_hash[..after_hash.find(|c: char| !c.is_ascii_digit()).unwrap_or(after_hash.len())];
+	digits.parse().ok()
+}
+
+fn parse_similarity(similarity: &str) -> Result<f64> {
+	match similarity.trim().parse::<f64>() {
+		Ok(value) if value.is_finite() => Ok(value),
+		_ => Err(Error::invalid_parse(format!("similarity \"{}\" is not a valid number", similarity))),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use proptest::prelude::*;
+
+	/// Creates a result header with the given values, as SauceNAO sends them
+	fn header(index_name: &str, index_id: u32, similarity: &str) -> Header {
+		serde_json::from_value(serde_json::json!({
+			"similarity": similarity, "thumbnail": "", "index_id": index_id, "index_name": index_name
+		}))
+		.unwrap()
+	}
+
+	/// Tests a well formed header
+	#[test]
+	fn test_valid_header() {
+		let parsed = parse_header(&header("Index #9: Danbooru - abc.jpg", 5, "61.31")).unwrap();
+		assert_eq!(parsed, ParsedHeader { index: 9, similarity: 61.31 });
+	}
+
+	/// Tests that the index_id is used when the index_name has no index
+	#[test]
+	fn test_index_name_fallback() {
+		for index_name in ["", "Danbooru", "Index: Danbooru", "Index #: Danbooru", "#", ":#9", "Index #abc: Danbooru"].iter() {
+			assert_eq!(parse_header(&header(index_name, 9, "61.31")).unwrap().index, 9, "{}", index_name);
+		}
+		let missing: Header = serde_json::from_value(serde_json::json!({ "similarity": "61.31" })).unwrap();
+		assert!(parse_header(&missing).is_err());
+	}
+
+	/// Tests that a similarity which is not a finite number is an error
+	#[test]
+	fn test_invalid_similarity() {
+		for similarity in ["", "abc", "NaN", "inf", "61,31"].iter() {
+			assert!(
+				parse_header(&header("Index #9: Danbooru - abc.jpg", 9, similarity)).is_err(),
+				"{}",
+				similarity
+			);
+		}
+		assert_eq!(parse_header(&header("Index #9: Danbooru", 9, " 61.31 ")).unwrap().similarity, 61.31);
+	}
+
+	proptest! {
+		/// Tests that arbitrary header strings never panic, and that a parsed similarity is always finite
+		#[test]
+		fn test_arbitrary_headers(index_name in any::<String>(), index_id in any::<u32>(), similarity in any::<String>()) {
+			if let Ok(parsed) = parse_header(&header(index_name.as_str(), index_id, similarity.as_str())) {
+				prop_assert!(parsed.similarity.is_finite());
+			}
+		}
+
+		/// Tests that the index is always taken from an index_name in the usual format
+		#[test]
+		fn test_index_name_format(index in any::<u32>(), index_id in any::<u32>(), suffix in "[^:]*", similarity in 0.0..100.0f64) {
+			let index_name = format!("Index #{}: {}", index, suffix);
+			let parsed = parse_header(&header(index_name.as_str(), index_id, similarity.to_string().as_str())).unwrap();
+			prop_assert_eq!(parsed.index, index);
+			prop_assert_eq!(parsed.similarity, similarity);
+		}
+	}
+}
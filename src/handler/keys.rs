@@ -0,0 +1,265 @@
+//! A pool of API keys, each with its own search limits, used to spread searches over several accounts.
+
+use super::deserialize::ResultHeader;
+use super::error::{LONG_LIMIT_WINDOW, SHORT_LIMIT_WINDOW};
+use super::quota::{AccountType, QuotaRecord, QuotaStatus};
+use std::time::{Duration, Instant, SystemTime};
+
+/// The search limits of a single API key, as last reported by SauceNAO.
+#[derive(Debug, Clone)]
+pub(crate) struct KeyState {
+	pub api_key: String,
+	pub short_limit: u32,
+	pub long_limit: u32,
+	pub short_left: u32,
+	pub long_left: u32,
+	/// When the remaining counts were last updated, or None if they have never been.
+	pub updated: Option<Instant>,
+	/// Whether SauceNAO rejected the key, in which case it is no longer used.
+	pub quarantined: bool,
+	pub account_type: Option<AccountType>,
+}
+
+impl KeyState {
+	fn new(api_key: String) -> KeyState {
+		KeyState {
+			api_key,
+			short_limit: 12,
+			long_limit: 200,
+			short_left: 12,
+			long_left: 200,
+			updated: None,
+			quarantined: false,
+			account_type: None,
+		}
+	}
+
+	/// Estimates the searches left in the short window, assuming it has been reset if it passed since the last update.
+	pub fn available_short(&self, now: Instant) -> u32 {
+		match self.updated {
+			Some(updated) if now.duration_since(updated) < SHORT_LIMIT_WINDOW => self.short_left,
+			_ => self.short_limit,
+		}
+	}
+
+	/// Estimates the searches left in the daily window, assuming it has been reset if it passed since the last update.
+	pub fn available_long(&self, now: Instant) -> u32 {
+		match self.updated {
+			Some(updated) if now.duration_since(updated) < LONG_LIMIT_WINDOW => self.long_left,
+			_ => self.long_limit,
+		}
+	}
+
+	/// Estimates when the short window resets, or None if its full limit is available.
+	pub fn short_reset(&self, now: Instant) -> Option<Instant> {
+		self.reset(now, SHORT_LIMIT_WINDOW, self.short_left, self.short_limit)
+	}
+
+	/// Estimates when the daily window resets, or None if its full limit is available.
+	pub fn long_reset(&self, now: Instant) -> Option<Instant> {
+		self.reset(now, LONG_LIMIT_WINDOW, self.long_left, self.long_limit)
+	}
+
+	fn reset(&self, now: Instant, window: Duration, left: u32, limit: u32) -> Option<Instant> {
+		let reset = self.updated? + window;
+		if reset > now && left < limit {
+			Some(reset)
+		} else {
+			None
+		}
+	}
+
+	/// Estimates the number of searches that can be made right now, which is limited by both windows.
+	pub fn available(&self, now: Instant) -> u32 {
+		if self.quarantined {
+			0
+		} else {
+			self.available_short(now).min(self.available_long(now))
+		}
+	}
+
+	/// Returns a snapshot of the limits of the key.
+	pub fn status(&self) -> QuotaStatus {
+		let now = Instant::now();
+		let system_now = SystemTime::now();
+		let to_system_time = |instant: Instant| system_now + (instant - now);
+		QuotaStatus {
+			short_limit: self.short_limit,
+			long_limit: self.long_limit,
+			short_remaining: self.available_short(now),
+			long_remaining: self.available_long(now),
+			short_reset_at: self.short_reset(now).map(to_system_time),
+			long_reset_at: self.long_reset(now).map(to_system_time),
+			account_type: self.account_type,
+			quarantined: self.quarantined,
+		}
+	}
+
+	/// Returns the limits of the key as a record for a quota store, or None if they have never been updated.
+	pub fn record(&self) -> Option<QuotaRecord> {
+		let updated = self.updated?;
+		Some(QuotaRecord {
+			short_limit: self.short_limit,
+			long_limit: self.long_limit,
+			short_remaining: self.short_left,
+			long_remaining: self.long_left,
+			updated_at: SystemTime::now() - updated.elapsed(),
+			account_type: self.account_type,
+		})
+	}
+
+	/// Restores the limits of the key from a record of a quota store.  Records older than a day are ignored, as the daily window has been reset since.
+	pub fn restore(&mut self, record: &QuotaRecord) {
+		// A record from the future (such as after the clock was changed) is treated as having just been made
+		let age = SystemTime::now().duration_since(record.updated_at).unwrap_or_default();
+		if age >= LONG_LIMIT_WINDOW {
+			return;
+		}
+		self.short_limit = record.short_limit;
+		self.long_limit = record.long_limit;
+		self.short_left = if age >= SHORT_LIMIT_WINDOW {
+			record.short_limit
+		} else {
+			record.short_remaining
+		};
+		self.long_left = record.long_remaining;
+		self.account_type = self.account_type.or(record.account_type);
+		self.updated = Some(Instant::now().checked_sub(age).unwrap_or_else(Instant::now));
+	}
+
+	fn has_quota(&self, now: Instant) -> bool {
+		!self.quarantined && self.available_short(now) > 0 && self.available_long(now) > 0
+	}
+}
+
+/// A pool of API keys.  There is always at least one key, which is blank if none were given.
+#[derive(Debug, Clone)]
+pub(crate) struct KeyPool {
+	keys: Vec<KeyState>,
+	/// The index of the key that was selected last.
+	current: usize,
+}
+
+impl KeyPool {
+	pub(crate) fn new(api_keys: Vec<String>) -> KeyPool {
+		let mut keys: Vec<KeyState> = api_keys.into_iter().map(KeyState::new).collect();
+		if keys.is_empty() {
+			keys.push(KeyState::new(String::new()));
+		}
+		KeyPool { keys, current: 0 }
+	}
+
+	pub(crate) fn keys(&self) -> &[KeyState] {
+		&self.keys
+	}
+
+	pub(crate) fn get(&self, index: usize) -> &KeyState {
+		&self.keys[index]
+	}
+
+	pub(crate) fn get_mut(&mut self, index: usize) -> &mut KeyState {
+		&mut self.keys[index]
+	}
+
+	/// The index of the key that was selected last.
+	#[cfg(fuzzing)]
+	pub(crate) fn current(&self) -> usize {
+		self.current
+	}
+
+	/// Selects the key to use for the next search, or returns None if every key has been quarantined.
+	///
+	/// Keys with searches left in both windows are preferred, and of those the one with the most searches left for the day.  If no key has any searches
+	/// left, the one with the most left for the day is still returned so that SauceNAO can report the limit.
+	pub(crate) fn select(&mut self) -> Option<usize> {
+		let now = Instant::now();
+		let mut selected: Option<usize> = None;
+		for (index, key) in self.keys.iter().enumerate() {
+			if key.quarantined {
+				continue;
+			}
+			let better = match selected {
+				None => true,
+				Some(best) => {
+					let best = &self.keys[best];
+					(key.has_quota(now), key.available_long(now)) > (best.has_quota(now), best.available_long(now))
+				}
+			};
+			if better {
+				selected = Some(index);
+			}
+		}
+		if let Some(index) = selected {
+			self.current = index;
+		}
+		selected
+	}
+
+	/// Returns the key that would be selected for the next search, or the first key if every key has been quarantined.
+	pub(crate) fn best(&self) -> usize {
+		self.clone().select().unwrap_or(0)
+	}
+
+	/// Returns the next moment at which the number of searches available from any key is expected to increase.
+	pub(crate) fn next_reset(&self) -> Option<Instant> {
+		let now = Instant::now();
+		self.keys
+			.iter()
+			.filter(|key| !key.quarantined)
+			.flat_map(|key| key.short_reset(now).into_iter().chain(key.long_reset(now)))
+			.min()
+	}
+
+	/// Returns whether any key that is not quarantined has searches left.
+	pub(crate) fn has_quota(&self) -> bool {
+		let now = Instant::now();
+		self.keys.iter().any(|key| key.has_quota(now))
+	}
+
+	/// Updates the limits of a key from the header of a successful response.
+	pub(crate) fn update(&mut self, index: usize, header: &ResultHeader) {
+		let key = &mut self.keys[index];
+		if let Some(x) = header.short_remaining.value() {
+			key.short_left = *x;
+		}
+		if let Some(x) = header.long_remaining.value() {
+			key.long_left = *x;
+		}
+		if let Some(x) = header.short_limit.value() {
+			key.short_limit = *x;
+		}
+		if let Some(x) = header.long_limit.value() {
+			key.long_limit = *x;
+		}
+		if let Some(x) = header.account_type.value() {
+			key.account_type = Some(AccountType::from_code(*x));
+		}
+		key.updated = Some(Instant::now());
+	}
+
+	/// Stops using a key that SauceNAO rejected, returning whether it was quarantined.  The last key that is not quarantined never is, so that a
+	/// Handler always has a key to search with, and can recover if SauceNAO rejected the key by mistake or it was fixed on the side of the account.
+	pub(crate) fn quarantine(&mut self, index: usize) -> bool {
+		let others = self.keys.iter().enumerate().any(|(other, key)| other != index && !key.quarantined);
+		if others {
+			self.keys[index].quarantined = true;
+		}
+		others
+	}
+
+	/// Marks a key as having no searches left in the short window.
+	pub(crate) fn exhaust_short(&mut self, index: usize) {
+		let key = &mut self.keys[index];
+		key.long_left = key.available_long(Instant::now());
+		key.short_left = 0;
+		key.updated = Some(Instant::now());
+	}
+
+	/// Marks a key as having no searches left for the day.
+	pub(crate) fn exhaust_long(&mut self, index: usize) {
+		let key = &mut self.keys[index];
+		key.short_left = key.available_short(Instant::now());
+		key.long_left = 0;
+		key.updated = Some(Instant::now());
+	}
+}
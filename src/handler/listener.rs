@@ -0,0 +1,85 @@
+//! Hooks for observing the searches made by a Handler, such as for monitoring.
+
+use super::{deserialize, AccountType, Error, QuotaStatus};
+use std::fmt;
+
+/// The header of a response from SauceNAO, describing the search limits and status of the search.
+///
+/// Values that were missing from the response, or were not in a recognised shape, are None.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ResultHeader {
+	/// The status of the search.  0 is a success, a negative status is a client side error and a positive status is a server side error.
+	pub status: Option<i32>,
+	/// The message explaining an unsuccessful status.
+	pub message: Option<String>,
+	/// The number of searches allowed every 30 seconds.
+	pub short_limit: Option<u32>,
+	/// The number of searches allowed every day.
+	pub long_limit: Option<u32>,
+	/// The number of searches left in the current 30 second window.
+	pub short_remaining: Option<u32>,
+	/// The number of searches left in the current day.
+	pub long_remaining: Option<u32>,
+	/// The type of the account.
+	pub account_type: Option<AccountType>,
+}
+
+impl From<&deserialize::ResultHeader> for ResultHeader {
+	fn from(header: &deserialize::ResultHeader) -> ResultHeader {
+		ResultHeader {
+			status: header.status.value().cloned(),
+			message: header.message.value().cloned(),
+			short_limit: header.short_limit.value().cloned(),
+			long_limit: header.long_limit.value().cloned(),
+			short_remaining: header.short_remaining.value().cloned(),
+			long_remaining: header.long_remaining.value().cloned(),
+			account_type: header.account_type.value().cloned().map(AccountType::from_code),
+		}
+	}
+}
+
+/// A trait to observe the searches made by a Handler.  Every method does nothing by default, so only the events of interest need to be implemented.
+///
+/// Listeners are called synchronously while searching, so they should return quickly.
+/// ### Example
+/// A listener that logs every error:
+/// ```
+/// use rustnao::{Error, HandlerBuilder, SearchListener};
+///
+/// #[derive(Debug)]
+/// struct ErrorLogger;
+///
+/// impl SearchListener for ErrorLogger {
+///     fn error(&self, error: &Error) {
+///         eprintln!("search failed: {}", error);
+///     }
+/// }
+///
+/// let handle = HandlerBuilder::default().api_key("your_api_key").listener(ErrorLogger).build();
+/// ```
+pub trait SearchListener: fmt::Debug + Send + Sync {
+	/// Called before a request is sent.  ``attempt`` starts at 1, and increases when the search is retried with another API key.
+	fn request_started(&self, _image_path: &str, _attempt: u32) {}
+
+	/// Called when a response from SauceNAO has been read, before its results are processed.
+	fn response_received(&self, _header: &ResultHeader) {}
+
+	/// Called after a successful response if the API key used has at most one search left in the 30 second window, or at most a tenth of its daily
+	/// limit left.
+	fn rate_limit_approached(&self, _status: &QuotaStatus) {}
+
+	/// Called when SauceNAO reports that a search limit was exceeded.  The error is of the kind ``ErrType::ShortLimitExceeded`` or
+	/// ``ErrType::DailyLimitExceeded``.
+	fn rate_limit_exceeded(&self, _error: &Error) {}
+
+	/// Called when a search is about to be retried with another API key because of the given error.  ``attempt`` is the number of the next attempt.
+	fn retry_scheduled(&self, _error: &Error, _attempt: u32) {}
+
+	/// Called when a search fails, with the error that is returned.
+	fn error(&self, _error: &Error) {}
+}
+
+/// Returns whether a key with the given status is close to one of its search limits.
+pub(crate) fn is_limit_approached(status: &QuotaStatus) -> bool {
+	status.short_remaining <= 1 || status.long_remaining <= status.long_limit / 10
+}
@@ -0,0 +1,283 @@
+//! Metrics of the searches made by Handlers, exported in the Prometheus text format.  The ``record_metrics!`` macro records to the metrics of a Handler
+//! with the ``metrics`` feature enabled, and otherwise expands to nothing.
+
+#[cfg(feature = "metrics")]
+macro_rules! record_metrics {
+	($handler:expr, |$metrics:ident| $body:expr) => {
+		if let Some($metrics) = &$handler.metrics {
+			$body
+		}
+	};
+}
+
+#[cfg(not(feature = "metrics"))]
+macro_rules! record_metrics {
+	($($arg:tt)*) => {};
+}
+
+#[cfg(feature = "metrics")]
+pub use self::registry::Metrics;
+
+#[cfg(feature = "metrics")]
+mod registry {
+	use super::super::keys::KeyState;
+	use super::super::quota;
+	use super::super::trace::FilterStats;
+	use super::super::{ErrType, Result, SauceResponse};
+	use std::collections::BTreeMap;
+	use std::fmt::Write;
+	use std::sync::{Arc, Mutex};
+	use std::time::{Duration, Instant};
+
+	/// The upper bounds of the buckets of the search duration histogram, in seconds.
+	const DURATION_BUCKETS: [f64; 10] = [0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0];
+
+	/// Counters, histograms and gauges of the searches made by one or more Handlers, which can be exported in the Prometheus text format.
+	///
+	/// A Metrics is shared by cloning it: every clone records to and exports the same values, so one can be given to any number of Handlers with
+	/// ``HandlerBuilder::metrics(...)`` and kept to export them all.  The following metrics are recorded:
+	/// * ``rustnao_searches_total`` - Searches, by ``result`` (``success`` or ``error``).
+	/// * ``rustnao_search_errors_total`` - Failed searches, by the ``type`` of the ``ErrType``, such as ``timeout`` or ``short_limit_exceeded``.
+	/// * ``rustnao_search_duration_seconds`` - A histogram of how long searches took, including retries with other API keys.
+	/// * ``rustnao_requests_total`` - Requests sent to SauceNAO, which is more than the searches when they are retried.
+	/// * ``rustnao_upload_bytes_total`` - The size of the local files uploaded.
+	/// * ``rustnao_results_returned_total`` - Results that passed the filters of the Handler.
+	/// * ``rustnao_results_filtered_total`` - Results that were dropped, by ``reason`` (``invalid``, ``unparseable_header``, ``below_similarity`` or
+	///   ``empty_urls``).
+	/// * ``rustnao_quota_short_remaining`` and ``rustnao_quota_long_remaining`` - The estimated searches left for each API key, by ``key_id``, a
+	///   fingerprint of the key as used by quota stores.
+	///
+	/// There is deliberately no cache hit metric.  Handlers do not cache responses (a ``Cassette`` replays recorded responses for tests, and is not a
+	/// cache), so it would always be zero.  An application that caches results in front of a Handler should count its own hits, next to these.
+	/// ### Example
+	/// ```
+	/// use rustnao::{HandlerBuilder, Metrics};
+	/// let metrics = Metrics::new();
+	/// let handle = HandlerBuilder::default().api_key("your_api_key").metrics(metrics.clone()).build();
+	/// handle.get_sauce("https://i.imgur.com/W42kkKS.jpg", None, None);
+	/// println!("{}", metrics.to_prometheus());
+	/// ```
+	#[derive(Debug, Clone, Default)]
+	pub struct Metrics {
+		inner: Arc<Mutex<Registry>>,
+	}
+
+	#[derive(Debug, Default)]
+	struct Registry {
+		searches: BTreeMap<&'static str, u64>,
+		errors: BTreeMap<&'static str, u64>,
+		duration_buckets: [u64; DURATION_BUCKETS.len()],
+		duration_sum: f64,
+		duration_count: u64,
+		requests: u64,
+		upload_bytes: u64,
+		results_returned: u64,
+		results_filtered: BTreeMap<&'static str, u64>,
+		/// The estimated searches left in the short and daily windows, by key id.
+		quota: BTreeMap<String, (u32, u32)>,
+	}
+
+	impl Metrics {
+		/// Creates a Metrics with every value at zero.
+		pub fn new() -> Metrics {
+			Metrics::default()
+		}
+
+		/// Returns the metrics in the Prometheus text exposition format, such as for serving from a ``/metrics`` endpoint.
+		pub fn to_prometheus(&self) -> String {
+			let registry = self.registry();
+			let mut output = String::new();
+
+			header(&mut output, "rustnao_searches_total", "The number of searches, by result.", "counter");
+			for result in &["success", "error"] {
+				sample(
+					&mut output,
+					"rustnao_searches_total",
+					&[("result", result)],
+					registry.searches.get(result).cloned().unwrap_or(0),
+				);
+			}
+
+			header(
+				&mut output,
+				"rustnao_search_errors_total",
+				"The number of failed searches, by type of error.",
+				"counter",
+			);
+			for (kind, count) in &registry.errors {
+				sample(&mut output, "rustnao_search_errors_total", &[("type", kind)], count);
+			}
+
+			header(
+				&mut output,
+				"rustnao_search_duration_seconds",
+				"How long searches took, in seconds.",
+				"histogram",
+			);
+			let mut cumulative = 0;
+			for (bound, count) in DURATION_BUCKETS.iter().zip(registry.duration_buckets.iter()) {
+				cumulative += count;
+				sample(
+					&mut output,
+					"rustnao_search_duration_seconds_bucket",
+					&[("le", &bound.to_string())],
+					cumulative,
+				);
+			}
+			sample(
+				&mut output,
+				"rustnao_search_duration_seconds_bucket",
+				&[("le", "+Inf")],
+				registry.duration_count,
+			);
+			sample(&mut output, "rustnao_search_duration_seconds_sum", &[], registry.duration_sum);
+			sample(&mut output, "rustnao_search_duration_seconds_count", &[], registry.duration_count);
+
+			header(
+				&mut output,
+				"rustnao_requests_total",
+				"The number of requests sent to SauceNAO.",
+				"counter",
+			);
+			sample(&mut output, "rustnao_requests_total", &[], registry.requests);
+
+			header(
+				&mut output,
+				"rustnao_upload_bytes_total",
+				"The number of bytes of local files uploaded to SauceNAO.",
+				"counter",
+			);
+			sample(&mut output, "rustnao_upload_bytes_total", &[], registry.upload_bytes);
+
+			header(
+				&mut output,
+				"rustnao_results_returned_total",
+				"The number of results returned by searches.",
+				"counter",
+			);
+			sample(&mut output, "rustnao_results_returned_total", &[], registry.results_returned);
+
+			header(
+				&mut output,
+				"rustnao_results_filtered_total",
+				"The number of results dropped from responses, by reason.",
+				"counter",
+			);
+			for reason in &["invalid", "unparseable_header", "below_similarity", "empty_urls"] {
+				let count = registry.results_filtered.get(reason).cloned().unwrap_or(0);
+				sample(&mut output, "rustnao_results_filtered_total", &[("reason", reason)], count);
+			}
+
+			header(
+				&mut output,
+				"rustnao_quota_short_remaining",
+				"The estimated searches left in the 30 second window, by API key.",
+				"gauge",
+			);
+			for (key_id, (short, _)) in &registry.quota {
+				sample(&mut output, "rustnao_quota_short_remaining", &[("key_id", key_id)], short);
+			}
+
+			header(
+				&mut output,
+				"rustnao_quota_long_remaining",
+				"The estimated searches left in the day, by API key.",
+				"gauge",
+			);
+			for (key_id, (_, long)) in &registry.quota {
+				sample(&mut output, "rustnao_quota_long_remaining", &[("key_id", key_id)], long);
+			}
+			output
+		}
+
+		fn registry(&self) -> std::sync::MutexGuard<'_, Registry> {
+			// A panic while recording cannot leave the values inconsistent enough to matter, so a poisoned lock is still used
+			self.inner.lock().unwrap_or_else(|err| err.into_inner())
+		}
+
+		/// Records a finished search.
+		pub(crate) fn record_search(&self, duration: Duration, result: &Result<SauceResponse>) {
+			let mut registry = self.registry();
+			let outcome = match result {
+				Ok(_) => "success",
+				Err(err) => {
+					*registry.errors.entry(error_label(err.kind())).or_insert(0) += 1;
+					"error"
+				}
+			};
+			*registry.searches.entry(outcome).or_insert(0) += 1;
+
+			let seconds = duration.as_secs_f64();
+			if let Some(bucket) = DURATION_BUCKETS.iter().position(|bound| seconds <= *bound) {
+				registry.duration_buckets[bucket] += 1;
+			}
+			registry.duration_sum += seconds;
+			registry.duration_count += 1;
+		}
+
+		/// Records a request sent to SauceNAO, with the size of the file uploaded.
+		pub(crate) fn record_request(&self, upload_bytes: u64) {
+			let mut registry = self.registry();
+			registry.requests += 1;
+			registry.upload_bytes += upload_bytes;
+		}
+
+		/// Records the results of a response that were returned and dropped.
+		pub(crate) fn record_results(&self, stats: &FilterStats, returned: usize) {
+			let mut registry = self.registry();
+			registry.results_returned += returned as u64;
+			for (reason, count) in &[
+				("invalid", stats.invalid),
+				("unparseable_header", stats.unparseable_header),
+				("below_similarity", stats.below_similarity),
+				("empty_urls", stats.empty_urls),
+			] {
+				*registry.results_filtered.entry(reason).or_insert(0) += *count as u64;
+			}
+		}
+
+		/// Records the estimated searches left for an API key.
+		pub(crate) fn record_quota(&self, key: &KeyState) {
+			let now = Instant::now();
+			let remaining = if key.quarantined {
+				(0, 0)
+			} else {
+				(key.available_short(now), key.available_long(now))
+			};
+			self.registry().quota.insert(quota::key_id(key.api_key.as_str()), remaining);
+		}
+	}
+
+	/// Returns the label of the type of an error.
+	fn error_label(kind: &ErrType) -> &'static str {
+		match kind {
+			ErrType::InvalidParse(_) => "invalid_parse",
+			ErrType::InvalidFile(_) => "invalid_file",
+			ErrType::InvalidSerde(_) => "invalid_serde",
+			ErrType::InvalidCode { .. } => "invalid_code",
+			ErrType::InvalidRequest(_) => "invalid_request",
+			ErrType::InvalidParameters(_) => "invalid_parameters",
+			ErrType::InvalidApiKey(_) => "invalid_api_key",
+			ErrType::ShortLimitExceeded { .. } => "short_limit_exceeded",
+			ErrType::DailyLimitExceeded { .. } => "daily_limit_exceeded",
+			ErrType::FileTooLarge(_) => "file_too_large",
+			ErrType::UnsupportedImage(_) => "unsupported_image",
+			ErrType::ServerError { .. } => "server_error",
+			ErrType::Timeout(_) => "timeout",
+		}
+	}
+
+	fn header(output: &mut String, name: &str, help: &str, kind: &str) {
+		let _ = writeln!(output, "# HELP {} {}", name, help);
+		let _ = writeln!(output, "# TYPE {} {}", name, kind);
+	}
+
+	fn sample<V: std::fmt::Display>(output: &mut String, name: &str, labels: &[(&str, &str)], value: V) {
+		output.push_str(name);
+		if !labels.is_empty() {
+			let labels: Vec<String> = labels.iter().map(|(label, value)| format!("{}=\"{}\"", label, value)).collect();
+			let _ = write!(output, "{{{}}}", labels.join(","));
+		}
+		let _ = writeln!(output, " {}", value);
+	}
+}
@@ -0,0 +1,180 @@
+//! Persistence of the search limits of API keys, so that a restarted process knows how many searches it has left.
+
+use super::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// The search limits of an API key, as reported by SauceNAO at a point in time.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct QuotaRecord {
+	/// The number of searches allowed every 30 seconds.
+	pub short_limit: u32,
+	/// The number of searches allowed every day.
+	pub long_limit: u32,
+	/// The number of searches left in the current 30 second window.
+	pub short_remaining: u32,
+	/// The number of searches left in the current day.
+	pub long_remaining: u32,
+	/// When SauceNAO reported these values.
+	pub updated_at: SystemTime,
+	/// The type of the account the key belongs to, if SauceNAO reported it.
+	#[serde(default)]
+	pub account_type: Option<AccountType>,
+}
+
+/// The type of SauceNAO account an API key belongs to, which determines its search limits.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccountType {
+	/// No account, such as when searching without an API key.
+	Anonymous,
+	/// A free account.
+	Basic,
+	/// A paid account with higher limits.
+	Enhanced,
+	/// An account type this library does not know about, with the number SauceNAO uses for it.
+	Other(u32),
+}
+
+impl AccountType {
+	pub(crate) fn from_code(code: u32) -> AccountType {
+		match code {
+			0 => AccountType::Anonymous,
+			1 => AccountType::Basic,
+			2 => AccountType::Enhanced,
+			other => AccountType::Other(other),
+		}
+	}
+}
+
+/// A snapshot of the search limits of an API key, with estimates of when they reset.
+///
+/// SauceNAO does not report when its limits reset, so the reset times are estimated from the last response: each window is assumed to be reset at the
+/// latest one window length after it.  The remaining counts take this into account, so they may be higher than SauceNAO last reported.
+/// ### Example
+/// ```
+/// use rustnao::HandlerBuilder;
+/// let handle = HandlerBuilder::default().api_key("your_api_key").build();
+/// let status = handle.get_quota_status();
+/// println!("{} of {} searches left today", status.long_remaining, status.long_limit);
+/// if let Some(reset_at) = status.short_reset_at {
+///     println!("More searches are available at {:?}", reset_at);
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuotaStatus {
+	/// The number of searches allowed every 30 seconds.
+	pub short_limit: u32,
+	/// The number of searches allowed every day.
+	pub long_limit: u32,
+	/// The estimated number of searches left in the current 30 second window.
+	pub short_remaining: u32,
+	/// The estimated number of searches left in the current day.
+	pub long_remaining: u32,
+	/// When the 30 second window is expected to reset, or None if its full limit is available.
+	pub short_reset_at: Option<SystemTime>,
+	/// When the daily window is expected to reset, or None if its full limit is available.
+	pub long_reset_at: Option<SystemTime>,
+	/// The type of the account, or None if no response has reported it yet.
+	pub account_type: Option<AccountType>,
+	/// Whether SauceNAO rejected the API key, in which case it is no longer used.
+	pub quarantined: bool,
+}
+
+/// A trait for storing the search limits of API keys, which a Handler loads when it is built and saves after every search.
+///
+/// Keys are identified by a fingerprint of the API key rather than the key itself, so that stores do not need to keep the key secret.
+/// ### Example
+/// A store that keeps the records in memory:
+/// ```
+/// use rustnao::{HandlerBuilder, QuotaRecord, QuotaStore, Result};
+/// use std::collections::HashMap;
+/// use std::sync::Mutex;
+///
+/// #[derive(Debug, Default)]
+/// struct MemoryQuotaStore(Mutex<HashMap<String, QuotaRecord>>);
+///
+/// impl QuotaStore for MemoryQuotaStore {
+///     fn load(&self, key_id: &str) -> Result<Option<QuotaRecord>> {
+///         Ok(self.0.lock().unwrap().get(key_id).cloned())
+///     }
+///
+///     fn save(&self, key_id: &str, record: &QuotaRecord) -> Result<()> {
+///         self.0.lock().unwrap().insert(key_id.to_string(), record.clone());
+///         Ok(())
+///     }
+/// }
+///
+/// let handle = HandlerBuilder::default().api_key("your_api_key").quota_store(MemoryQuotaStore::default()).build();
+/// ```
+pub trait QuotaStore: fmt::Debug + Send + Sync {
+	/// Loads the last saved record of a key, or None if there is none.
+	fn load(&self, key_id: &str) -> Result<Option<QuotaRecord>>;
+
+	/// Saves the record of a key, replacing any previous one.
+	fn save(&self, key_id: &str, record: &QuotaRecord) -> Result<()>;
+}
+
+/// A ``QuotaStore`` that keeps the records of every key in a single JSON file.
+///
+/// The file is created when the first record is saved, and is replaced as a whole on every save.  It is not locked, so processes sharing one file may
+/// overwrite each other's latest records, which only makes their estimates briefly out of date.
+/// ### Example
+/// ```
+/// use rustnao::{FileQuotaStore, HandlerBuilder};
+/// let handle = HandlerBuilder::default().api_key("your_api_key").quota_store(FileQuotaStore::new("./quota.json")).build();
+/// ```
+#[derive(Debug, Clone)]
+pub struct FileQuotaStore {
+	path: PathBuf,
+}
+
+impl FileQuotaStore {
+	/// Creates a store that uses the file at the given path.
+	///
+	/// ### Arguments
+	/// * path - The path of the JSON file to keep the records in.
+	pub fn new<P: AsRef<Path>>(path: P) -> FileQuotaStore {
+		FileQuotaStore {
+			path: path.as_ref().to_path_buf(),
+		}
+	}
+
+	fn read(&self) -> Result<HashMap<String, QuotaRecord>> {
+		match fs::read_to_string(&self.path) {
+			Ok(contents) => Ok(serde_json::from_str(contents.as_str())?),
+			Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(HashMap::new()),
+			Err(err) => Err(err.into()),
+		}
+	}
+}
+
+impl QuotaStore for FileQuotaStore {
+	fn load(&self, key_id: &str) -> Result<Option<QuotaRecord>> {
+		Ok(self.read()?.remove(key_id))
+	}
+
+	fn save(&self, key_id: &str, record: &QuotaRecord) -> Result<()> {
+		let mut records = self.read()?;
+		records.insert(key_id.to_string(), record.clone());
+
+		// Written to a temporary file first so that a crash never leaves a partially written file behind
+		let mut temporary = self.path.clone().into_os_string();
+		temporary.push(".tmp");
+		fs::write(&temporary, serde_json::to_string_pretty(&records)?)?;
+		fs::rename(&temporary, &self.path)?;
+		Ok(())
+	}
+}
+
+/// Returns the identifier of an API key used in quota stores, a 64 bit FNV-1a hash of the key.
+pub(crate) fn key_id(api_key: &str) -> String {
+	let hash = api_key.bytes().fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+		(hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+	});
+	format!("{:016x}", hash)
+}
@@ -0,0 +1,247 @@
+//! Ranking of results by the site they come from, to find the original post of a work rather than a mirror of it.
+
+use super::cluster::{normalize_url, source_url};
+use super::{Sauce, SourceRef};
+use std::cmp::Ordering;
+use std::fmt;
+
+/// The sites that artists post their work to first, in order of preference, as in ``Sauce::site`` and ``SourceRef::site``.
+const DEFAULT_ORIGINS: [&str; 4] = ["Pixiv", "deviantArt", "Nijie Images", "Twitter"];
+
+/// A policy for ranking results, which prefers results from the sites that artists post their work to over results from boorus and other
+/// aggregators that mirror it.
+///
+/// By default the origin sites are Pixiv, deviantArt, Nijie and Twitter, in that order.  Site names are compared ignoring case.
+/// ### Example
+/// ```
+/// use rustnao::SourcePolicy;
+/// let mut policy = SourcePolicy::new();
+/// policy.origins(vec!["Twitter", "Pixiv"]).min_similarity(60);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourcePolicy {
+	origins: Vec<String>,
+	min_similarity: f32,
+}
+
+impl Default for SourcePolicy {
+	fn default() -> SourcePolicy {
+		SourcePolicy {
+			origins: DEFAULT_ORIGINS.iter().map(|site| site.to_string()).collect(),
+			min_similarity: 0.0,
+		}
+	}
+}
+
+impl SourcePolicy {
+	/// Creates a policy with the default origin sites.
+	pub fn new() -> SourcePolicy {
+		SourcePolicy::default()
+	}
+
+	/// Sets the origin sites, replacing the default ones.
+	///
+	/// ### Arguments
+	/// * origins - A vector of the names of the sites, most preferred first, as in ``Sauce::site`` and ``SourceRef::site``.
+	///
+	/// ### Examples
+	/// ```
+	/// use rustnao::SourcePolicy;
+	/// let mut policy = SourcePolicy::new();
+	/// policy.origins(vec!["Pixiv", "Twitter"]);
+	/// ```
+	pub fn origins(&mut self, origins: Vec<&str>) -> &mut SourcePolicy {
+		self.origins = origins.into_iter().map(str::to_string).collect();
+		self
+	}
+
+	/// Sets the minimum similarity for a result to be preferred for its origin.  Less similar results are ranked by similarity alone, after the
+	/// preferred ones, so that a poor match on Pixiv does not beat a close match on a booru.  The default is 0.
+	///
+	/// ### Arguments
+	/// * min_similarity - The minimum similarity, which can be any value that converts to a f64, from 0.0 to 100.0.
+	///
+	/// ### Examples
+	/// ```
+	/// use rustnao::SourcePolicy;
+	/// let mut policy = SourcePolicy::new();
+	/// policy.min_similarity(70.5);
+	/// ```
+	pub fn min_similarity<T: Into<f64>>(&mut self, min_similarity: T) -> &mut SourcePolicy {
+		self.min_similarity = min_similarity.into() as f32;
+		self
+	}
+
+	/// Returns the preference of an origin site, from 0 for the most preferred, or None if the site is not an origin.
+	fn preference(&self, site: &str) -> Option<usize> {
+		self.origins.iter().position(|origin| origin.eq_ignore_ascii_case(site))
+	}
+
+	/// Ranks a result, returning its link to the original post and why it was ranked where it was.
+	fn rank(&self, sauce: &Sauce) -> (Option<String>, RankReason) {
+		let primary_url = sauce.primary_url().map(str::to_string);
+		if sauce.similarity < self.min_similarity {
+			return (primary_url, RankReason::Similarity);
+		}
+
+		let from_site = self
+			.preference(&sauce.site)
+			.map(|preference| (preference, primary_url.clone(), sauce.site.clone()));
+		let from_url = sauce
+			.ext_urls
+			.iter()
+			.filter_map(|url| SourceRef::parse(url))
+			.filter_map(|source_ref| {
+				self.preference(source_ref.site())
+					.map(|preference| (preference, Some(source_ref.url()), source_ref.site().to_string()))
+			})
+			.min_by_key(|(preference, _, _)| *preference);
+		let origin = from_site.into_iter().chain(from_url).min_by_key(|(preference, _, _)| *preference);
+		if let Some((preference, url, site)) = origin {
+			return (url, RankReason::Origin { site, preference });
+		}
+
+		let source = source_url(sauce).and_then(|url| SourceRef::parse(url).map(|source_ref| (normalize_url(url), source_ref)));
+		if let Some((url, source_ref)) = source {
+			if let Some(preference) = self.preference(source_ref.site()) {
+				let site = source_ref.site().to_string();
+				return (Some(url), RankReason::OriginSource { site, preference });
+			}
+		}
+		(primary_url, RankReason::Similarity)
+	}
+}
+
+/// Why a result was ranked where it was by a ``SourcePolicy``.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RankReason {
+	/// The result is from an origin site, such as a Pixiv illustration.
+	Origin {
+		/// The name of the origin site.
+		site: String,
+		/// The preference of the site, from 0 for the most preferred.
+		preference: usize,
+	},
+	/// The result is from an aggregator, such as a booru, that gives an origin site as the source of the post.
+	OriginSource {
+		/// The name of the origin site of the source.
+		site: String,
+		/// The preference of the site, from 0 for the most preferred.
+		preference: usize,
+	},
+	/// The result is not from an origin site, or is less similar than the policy prefers, so it was ranked by similarity alone.
+	Similarity,
+}
+
+impl RankReason {
+	/// Returns the preference of the origin site, or None if the result was ranked by similarity alone.
+	fn preference(&self) -> Option<usize> {
+		match self {
+			RankReason::Origin { preference, .. } | RankReason::OriginSource { preference, .. } => Some(*preference),
+			RankReason::Similarity => None,
+		}
+	}
+}
+
+impl fmt::Display for RankReason {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			RankReason::Origin { site, preference } => write!(f, "it is from {}, origin site #{}", site, preference + 1),
+			RankReason::OriginSource { site, preference } => write!(f, "its source is on {}, origin site #{}", site, preference + 1),
+			RankReason::Similarity => write!(f, "it is not from a preferred origin site, so it was ranked by similarity"),
+		}
+	}
+}
+
+/// A result ranked by a ``SourcePolicy``, with the link to the original post and why it was ranked where it was.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RankedSauce {
+	/// The result.
+	pub sauce: Sauce,
+	/// The link to the original post: the link to the origin site, whether it is one of the ``ext_urls`` or the source of a booru post, or otherwise
+	/// the primary URL of the result.
+	pub url: Option<String>,
+	/// Why the result was ranked where it was.
+	pub reason: RankReason,
+}
+
+impl RankedSauce {
+	/// Returns an explanation of why the result was ranked where it was, such as
+	/// ``Danbooru at 93.08% similarity, because its source is on Pixiv, origin site #1``.
+	pub fn explain(&self) -> String {
+		format!("{} at {:.2}% similarity, because {}", self.sauce.site, self.sauce.similarity, self.reason)
+	}
+}
+
+/// A trait to rank results with a ``SourcePolicy``, to find the original post of a work rather than the most similar mirror of it.
+///
+/// Results are ordered by the preference of their origin site, whether they are from the site or a booru gives it as their source, with results from
+/// the site itself first.  Results without an origin site come last.  Results that are ranked the same are ordered by similarity, and then by the
+/// order they were found in.
+/// ### Example
+/// ```
+/// use rustnao::{HandlerBuilder, RankSources, SourcePolicy};
+/// let handle = HandlerBuilder::default().api_key("your_api_key").build();
+/// if let Ok(result) = handle.get_sauce("./tests/test.jpg", None, None) {
+///     if let Some(best) = result.best(&SourcePolicy::new()) {
+///         println!("{:?}: {}", best.url, best.explain());
+///     }
+/// }
+/// ```
+pub trait RankSources {
+	/// Ranks the results, best first.
+	/// ### Example
+	/// ```
+	/// use rustnao::{RankSources, SauceBuilder, SourcePolicy};
+	/// let results = vec![
+	///     SauceBuilder::default().site("Danbooru").similarity(95.0).build(),
+	///     SauceBuilder::default().site("Pixiv").similarity(90.0).build(),
+	/// ];
+	/// let ranked = results.rank(&SourcePolicy::new());
+	/// assert_eq!(ranked[0].sauce.site, "Pixiv");
+	/// ```
+	fn rank(&self, policy: &SourcePolicy) -> Vec<RankedSauce>;
+
+	/// Returns the best result, or None if there are no results.
+	/// ### Example
+	/// ```
+	/// use rustnao::{RankSources, RankReason, SauceBuilder, SourcePolicy};
+	/// let results = vec![
+	///     SauceBuilder::default().site("Danbooru").similarity(95.0)
+	///         .additional_fields(serde_json::json!({"source": "https://twitter.com/user/status/1160000000000000000"})).build(),
+	/// ];
+	/// let best = results.best(&SourcePolicy::new()).unwrap();
+	/// assert_eq!(best.url.as_deref(), Some("https://twitter.com/i/web/status/1160000000000000000"));
+	/// assert_eq!(best.reason, RankReason::OriginSource { site: "Twitter".to_string(), preference: 3 });
+	/// ```
+	fn best(&self, policy: &SourcePolicy) -> Option<RankedSauce> {
+		self.rank(policy).into_iter().next()
+	}
+}
+
+impl RankSources for Vec<Sauce> {
+	fn rank(&self, policy: &SourcePolicy) -> Vec<RankedSauce> {
+		let mut ranked: Vec<RankedSauce> = self
+			.iter()
+			.map(|sauce| {
+				let (url, reason) = policy.rank(sauce);
+				RankedSauce {
+					sauce: sauce.clone(),
+					url,
+					reason,
+				}
+			})
+			.collect();
+		// The sort is stable, so results ranked the same stay in the order they were found in
+		ranked.sort_by(|a, b| {
+			let key = |ranked: &RankedSauce| {
+				let via_source = matches!(ranked.reason, RankReason::OriginSource { .. });
+				(ranked.reason.preference().unwrap_or(usize::MAX), via_source)
+			};
+			key(a)
+				.cmp(&key(b))
+				.then_with(|| b.sauce.similarity.partial_cmp(&a.sauce.similarity).unwrap_or(Ordering::Equal))
+		});
+		ranked
+	}
+}
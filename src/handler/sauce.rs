@@ -1,16 +1,27 @@
-use serde::Serialize;
+use super::author::{self, Author};
+use super::{constants, SourceRef};
+use serde::{de, Deserialize, Deserializer, Serialize};
+use std::convert::TryFrom;
 use std::fmt;
+use std::time::Duration;
 
 /// A Sauce struct contains one result from a API call made by the Handler.  
+///
+/// Sauce objects can be serialized and deserialized, so results can be stored and reloaded later.  The serialized form carries a ``schema_version`` field;
+/// JSON written before the field existed is read as version 1, and JSON from a newer, unknown schema version is rejected.
 /// ## Example
 /// ```
 /// use rustnao::{Sauce, HandlerBuilder};
 /// let mut handle = HandlerBuilder::default().api_key("your_api_key").build();
 /// let result : rustnao::Result<Vec<Sauce>> = handle.get_sauce("https://i.pximg.net/img-master/img/2019/02/10/03/11/39/73095123_p0_master1200.jpg", None, None);
 /// ```
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
 pub struct Sauce {
+	/// The version of the serialized schema this Sauce was created with
+	#[serde(default = "default_schema_version", deserialize_with = "deserialize_schema_version")]
+	schema_version: u32,
 	/// A Vec of Strings representing the external URLs for the image
+	#[serde(default)]
 	pub ext_urls: Vec<String>,
 	/// An optional String to represent the title of the image
 	pub title: Option<String>,
@@ -28,49 +39,476 @@ pub struct Sauce {
 	pub additional_fields: Option<serde_json::Value>,
 }
 
-// TODO: Consider making the sauce object a builder...
+fn default_schema_version() -> u32 {
+	1
+}
 
-/// Creates a new Sauce object.
-#[allow(clippy::too_many_arguments)]
-pub(in crate::handler) fn new_sauce(
-	ext_urls: Vec<String>, title: Option<String>, site: String, index: u32, index_id: u32, similarity: f32, thumbnail: String,
+fn deserialize_schema_version<'de, D: Deserializer<'de>>(deserializer: D) -> std::result::Result<u32, D::Error> {
+	let version = u32::deserialize(deserializer)?;
+	if version == 0 || version > Sauce::SCHEMA_VERSION {
+		Err(de::Error::custom(format!(
+			"unsupported Sauce schema version {}, expected at most {}",
+			version,
+			Sauce::SCHEMA_VERSION
+		)))
+	} else {
+		Ok(version)
+	}
+}
+
+/// A builder to create a Sauce, for example when constructing results for tests or from your own data.
+/// ## Example
+/// ```
+/// use rustnao::{Handler, SauceBuilder};
+/// let sauce = SauceBuilder::default()
+///     .ext_urls(vec!["https://www.pixiv.net/member_illust.php?mode=medium&illust_id=61477678".to_string()])
+///     .title("Title")
+///     .site("Pixiv")
+///     .index(Handler::PIXIV)
+///     .similarity(92.5)
+///     .build();
+/// assert_eq!(sauce.index_id, Handler::PIXIV);
+/// ```
+#[derive(Default, Debug, Clone)]
+pub struct SauceBuilder {
+	ext_urls: Option<Vec<String>>,
+	title: Option<String>,
+	site: Option<String>,
+	index: Option<u32>,
+	index_id: Option<u32>,
+	similarity: Option<f32>,
+	thumbnail: Option<String>,
 	additional_fields: Option<serde_json::Value>,
-) -> Sauce {
-	Sauce {
-		ext_urls,
-		title,
-		site,
-		index,
-		index_id,
-		similarity,
-		thumbnail,
-		additional_fields,
+}
+
+impl SauceBuilder {
+	/// Sets the external URLs of the Sauce.  If this is not set, the Sauce has no external URLs.
+	///
+	/// ### Arguments
+	/// * ext_urls - A vector of Strings representing the external URLs for the image.
+	///
+	/// ### Examples
+	/// ```
+	/// use rustnao::SauceBuilder;
+	/// let sauce = SauceBuilder::default().ext_urls(vec!["https://danbooru.donmai.us/post/show/3441187".to_string()]).build();
+	/// ```
+	pub fn ext_urls(&mut self, ext_urls: Vec<String>) -> &mut SauceBuilder {
+		self.ext_urls = Some(ext_urls);
+		self
+	}
+
+	/// Sets the title of the Sauce.  If this is not set, the Sauce has no title.
+	///
+	/// ### Arguments
+	/// * title - A string reference representing the title of the image.
+	///
+	/// ### Examples
+	/// ```
+	/// use rustnao::SauceBuilder;
+	/// let sauce = SauceBuilder::default().title("Title").build();
+	/// ```
+	pub fn title(&mut self, title: &str) -> &mut SauceBuilder {
+		self.title = Some(title.to_string());
+		self
+	}
+
+	/// Sets the site of the Sauce.  If this is not set, it is an empty string.
+	///
+	/// ### Arguments
+	/// * site - A string reference representing the site the image is from.
+	///
+	/// ### Examples
+	/// ```
+	/// use rustnao::SauceBuilder;
+	/// let sauce = SauceBuilder::default().site("Pixiv").build();
+	/// ```
+	pub fn site(&mut self, site: &str) -> &mut SauceBuilder {
+		self.site = Some(site.to_string());
+		self
+	}
+
+	/// Sets the index of the Sauce.  If this is not set, it is 0.
+	///
+	/// ### Arguments
+	/// * index - A u32 representing the official SauceNAO index.
+	///
+	/// ### Examples
+	/// ```
+	/// use rustnao::{Handler, SauceBuilder};
+	/// let sauce = SauceBuilder::default().index(Handler::PIXIV).build();
+	/// ```
+	pub fn index(&mut self, index: u32) -> &mut SauceBuilder {
+		self.index = Some(index);
+		self
+	}
+
+	/// Sets the index ID of the Sauce.  If this is not set, it is the same as the index.
+	///
+	/// ### Arguments
+	/// * index_id - A u32 representing the index returned by the SauceNAO API.
+	///
+	/// ### Examples
+	/// ```
+	/// use rustnao::{Handler, SauceBuilder};
+	/// let sauce = SauceBuilder::default().index(Handler::SANKAKU_CHANNEL).index_id(27).build();
+	/// ```
+	pub fn index_id(&mut self, index_id: u32) -> &mut SauceBuilder {
+		self.index_id = Some(index_id);
+		self
+	}
+
+	/// Sets the similarity of the Sauce.  If this is not set, it is 0.0.
+	///
+	/// ### Arguments
+	/// * similarity - A f32 representing the similarity (in percent) of the result.
+	///
+	/// ### Examples
+	/// ```
+	/// use rustnao::SauceBuilder;
+	/// let sauce = SauceBuilder::default().similarity(92.5).build();
+	/// ```
+	pub fn similarity(&mut self, similarity: f32) -> &mut SauceBuilder {
+		self.similarity = Some(similarity);
+		self
+	}
+
+	/// Sets the thumbnail URL of the Sauce.  If this is not set, it is an empty string.
+	///
+	/// ### Arguments
+	/// * thumbnail - A string reference representing the URL of the thumbnail.
+	///
+	/// ### Examples
+	/// ```
+	/// use rustnao::SauceBuilder;
+	/// let sauce = SauceBuilder::default().thumbnail("https://img1.saucenao.com/res/pixiv/6147/61477678_s.jpg").build();
+	/// ```
+	pub fn thumbnail(&mut self, thumbnail: &str) -> &mut SauceBuilder {
+		self.thumbnail = Some(thumbnail.to_string());
+		self
+	}
+
+	/// Sets the additional, source specific fields of the Sauce.  If this is not set, there are no additional fields.
+	///
+	/// ### Arguments
+	/// * additional_fields - A JSON value containing the fields.
+	///
+	/// ### Examples
+	/// ```
+	/// use rustnao::SauceBuilder;
+	/// let sauce = SauceBuilder::default().additional_fields(serde_json::json!({ "member_id": 4754550 })).build();
+	/// ```
+	pub fn additional_fields(&mut self, additional_fields: serde_json::Value) -> &mut SauceBuilder {
+		self.additional_fields = Some(additional_fields);
+		self
+	}
+
+	/// Builds the SauceBuilder, returning a Sauce.
+	///
+	/// ### Examples
+	/// ```
+	/// use rustnao::SauceBuilder;
+	/// let sauce = SauceBuilder::default().site("Pixiv").similarity(92.5).build();
+	/// ```
+	pub fn build(&self) -> Sauce {
+		let index = self.index.unwrap_or(0);
+		Sauce {
+			schema_version: Sauce::SCHEMA_VERSION,
+			ext_urls: self.ext_urls.clone().unwrap_or_default(),
+			title: self.title.clone(),
+			site: self.site.clone().unwrap_or_default(),
+			index,
+			index_id: self.index_id.unwrap_or(index),
+			similarity: self.similarity.unwrap_or(0.0),
+			thumbnail: self.thumbnail.clone().unwrap_or_default(),
+			additional_fields: self.additional_fields.clone(),
+		}
 	}
 }
 
 impl Sauce {
+	/// The current version of the serialized Sauce schema.
+	pub const SCHEMA_VERSION: u32 = 1;
+
+	/// Returns the schema version this Sauce was serialized with.
+	/// ## Example
+	/// ```
+	/// use rustnao::{Sauce, SauceBuilder};
+	/// let sauce = SauceBuilder::default().build();
+	/// assert_eq!(sauce.schema_version(), Sauce::SCHEMA_VERSION);
+	/// ```
+	pub fn schema_version(&self) -> u32 {
+		self.schema_version
+	}
+
 	/// Returns whether the Sauce struct contains an empty ext_url field.
 	/// ## Example
 	/// ```
 	/// use rustnao::{HandlerBuilder, Sauce};
 	/// let file = "https://i.imgur.com/W42kkKS.jpg";
-	///	let mut handle = HandlerBuilder::default().api_key("your_api_key").build();
-	///	handle.set_min_similarity(45);
-	///	let result = handle.get_sauce(file, None, None);
-	///	if result.is_ok() {
-	///		let res : Vec<Sauce> = result.unwrap().into_iter().filter(|sauce| sauce.has_empty_url()).collect();
-	///		for i in res {
-	///			println!("{:?}", i);
-	///		}
-	///	}
-	///	else {
-	///		println!("Failed to make a query.");
-	///	}
+	///    let mut handle = HandlerBuilder::default().api_key("your_api_key").build();
+	///    handle.set_min_similarity(45);
+	///    let result = handle.get_sauce(file, None, None);
+	///    if result.is_ok() {
+	///        let res : Vec<Sauce> = result.unwrap().into_iter().filter(|sauce| sauce.has_empty_url()).collect();
+	///        for i in res {
+	///            println!("{:?}", i);
+	///        }
+	///    }
+	///    else {
+	///        println!("Failed to make a query.");
+	///    }
 	///
 	/// ```
 	pub fn has_empty_url(&self) -> bool {
 		self.ext_urls.is_empty()
 	}
+
+	/// Returns the first external URL of the Sauce, if there is one.
+	/// ## Example
+	/// ```
+	/// use rustnao::SauceBuilder;
+	/// let sauce = SauceBuilder::default().ext_urls(vec!["https://danbooru.donmai.us/post/show/3441187".to_string()]).build();
+	/// assert_eq!(sauce.primary_url(), Some("https://danbooru.donmai.us/post/show/3441187"));
+	/// ```
+	pub fn primary_url(&self) -> Option<&str> {
+		self.ext_urls.first().map(String::as_str)
+	}
+
+	/// Returns the sites and IDs of the works the external URLs point to, in order and without duplicates.  URLs that are not links to a work on a
+	/// known site are left out.
+	/// ## Example
+	/// ```
+	/// use rustnao::{SauceBuilder, SourceRef};
+	/// let sauce = SauceBuilder::default()
+	///     .ext_urls(vec!["https://www.pixiv.net/member_illust.php?mode=medium&illust_id=61477678".to_string(), "https://danbooru.donmai.us/post/show/3441187".to_string()])
+	///     .build();
+	/// assert_eq!(sauce.source_refs(), vec![SourceRef::Pixiv(61477678), SourceRef::Danbooru(3441187)]);
+	/// ```
+	pub fn source_refs(&self) -> Vec<SourceRef> {
+		let mut source_refs: Vec<SourceRef> = Vec::new();
+		for source_ref in self.ext_urls.iter().filter_map(|url| SourceRef::parse(url)) {
+			if !source_refs.contains(&source_ref) {
+				source_refs.push(source_ref);
+			}
+		}
+		source_refs
+	}
+
+	/// Returns the artists and creators of the Sauce, from whichever fields its index gives them in, in order and without duplicates.  Pixiv and
+	/// other art sites give a member name and ID, boorus give the artist tag as the creator, and some indexes, such as the anime ones, give none.
+	/// ## Example
+	/// ```
+	/// use rustnao::SauceBuilder;
+	/// let sauce = SauceBuilder::default()
+	///     .site("Danbooru")
+	///     .additional_fields(serde_json::json!({ "creator": "lin_yuu", "source": "https://i.pximg.net/img-original/img/2017/02/19/00/00/39/61477678_p0.jpg" }))
+	///     .build();
+	/// let authors = sauce.authors();
+	/// assert_eq!(authors.len(), 1);
+	/// assert_eq!(authors[0].to_string(), "lin_yuu (Danbooru)");
+	/// ```
+	pub fn authors(&self) -> Vec<Author> {
+		author::authors(self)
+	}
+
+	/// Returns the episode of an anime or show that the Sauce is from, for results from the ``ANIME``, ``H_ANIME``, ``MOVIES`` and ``SHOWS``
+	/// indexes, or from trace.moe.
+	/// ## Example
+	/// ```
+	/// use rustnao::{Handler, SauceBuilder};
+	/// let sauce = SauceBuilder::default().index(Handler::ANIME).additional_fields(serde_json::json!({ "part": "03" })).build();
+	/// assert_eq!(sauce.episode(), Some(3));
+	/// ```
+	pub fn episode(&self) -> Option<u32> {
+		// SauceNAO gives the episode as a string, such as "03", and trace.moe gives it as a number
+		match self.video_field("part").or_else(|| self.video_field("episode"))? {
+			serde_json::Value::Number(episode) => episode.as_u64().and_then(|episode| u32::try_from(episode).ok()),
+			serde_json::Value::String(episode) => first_number(episode),
+			_ => None,
+		}
+	}
+
+	/// Returns the year an anime, movie or show that the Sauce is from was released, for results from the ``ANIME``, ``H_ANIME``, ``MOVIES`` and
+	/// ``SHOWS`` indexes.  For a range of years, such as ``2017-2018``, this is the first year.
+	/// ## Example
+	/// ```
+	/// use rustnao::{Handler, SauceBuilder};
+	/// let sauce = SauceBuilder::default().index(Handler::ANIME).additional_fields(serde_json::json!({ "year": "2017-2018" })).build();
+	/// assert_eq!(sauce.year(), Some(2017));
+	/// ```
+	pub fn year(&self) -> Option<u32> {
+		match self.video_field("year")? {
+			serde_json::Value::Number(year) => year.as_u64().and_then(|year| u32::try_from(year).ok()),
+			serde_json::Value::String(year) => first_number(year),
+			_ => None,
+		}
+	}
+
+	/// Returns the estimated time into the video that the matching frame is at, for results from the ``ANIME``, ``H_ANIME``, ``MOVIES`` and
+	/// ``SHOWS`` indexes, or from trace.moe.
+	/// ## Example
+	/// ```
+	/// use rustnao::{Handler, SauceBuilder};
+	/// use std::time::Duration;
+	/// let sauce = SauceBuilder::default()
+	///     .index(Handler::ANIME)
+	///     .additional_fields(serde_json::json!({ "est_time": "00:12:34 / 00:24:00" }))
+	///     .build();
+	/// assert_eq!(sauce.timestamp(), Some(Duration::from_secs(754)));
+	/// assert_eq!(sauce.video_length(), Some(Duration::from_secs(1440)));
+	/// ```
+	pub fn timestamp(&self) -> Option<Duration> {
+		// SauceNAO gives the time as "00:12:34 / 00:24:00", or "00:12:34 - 00:12:36 / 00:24:00", and trace.moe gives it in seconds
+		if let Some(est_time) = self.video_field("est_time").and_then(serde_json::Value::as_str) {
+			let start = est_time.split('/').next()?.split('-').next()?;
+			return parse_time(start);
+		}
+		let from = self.video_field("from").and_then(serde_json::Value::as_f64)?;
+		// Negative times and ones too long for a Duration are None rather than a panic
+		Duration::try_from_secs_f64(from).ok()
+	}
+
+	/// Returns the total length of the video, for results from the ``ANIME``, ``H_ANIME``, ``MOVIES`` and ``SHOWS`` indexes that give it.
+	/// ## Example
+	/// ```
+	/// use rustnao::{Handler, SauceBuilder};
+	/// use std::time::Duration;
+	/// let sauce = SauceBuilder::default()
+	///     .index(Handler::MOVIES)
+	///     .additional_fields(serde_json::json!({ "est_time": "01:02:03 - 01:02:05 / 02:22:00" }))
+	///     .build();
+	/// assert_eq!(sauce.video_length(), Some(Duration::from_secs(8520)));
+	/// ```
+	pub fn video_length(&self) -> Option<Duration> {
+		let est_time = self.video_field("est_time").and_then(serde_json::Value::as_str)?;
+		parse_time(est_time.split('/').nth(1)?)
+	}
+
+	/// Returns the AniDB ID of the anime the Sauce is from, from its ``anidb_aid`` or an AniDB link in its external URLs.
+	/// ## Example
+	/// ```
+	/// use rustnao::SauceBuilder;
+	/// let sauce = SauceBuilder::default().ext_urls(vec!["https://anidb.net/perl-bin/animedb.pl?show=anime&aid=4037".to_string()]).build();
+	/// assert_eq!(sauce.anidb_id(), Some(4037));
+	/// ```
+	pub fn anidb_id(&self) -> Option<u64> {
+		let field = self.additional_fields.as_ref().and_then(|fields| fields.get("anidb_aid"));
+		let aid = match field {
+			Some(serde_json::Value::Number(aid)) => aid.as_u64(),
+			Some(serde_json::Value::String(aid)) => aid.trim().parse().ok(),
+			_ => None,
+		};
+		aid.or_else(|| {
+			self.source_refs().into_iter().find_map(|source_ref| match source_ref {
+				SourceRef::AniDb(aid) => Some(aid),
+				_ => None,
+			})
+		})
+	}
+
+	/// Returns the IMDb ID of the movie or show the Sauce is from, such as ``tt0111161``, from its ``imdb_id`` or an IMDb link in its external URLs.
+	/// ## Example
+	/// ```
+	/// use rustnao::SauceBuilder;
+	/// let sauce = SauceBuilder::default().ext_urls(vec!["https://www.imdb.com/title/tt0111161/".to_string()]).build();
+	/// assert_eq!(sauce.imdb_id(), Some("tt0111161".to_string()));
+	/// ```
+	pub fn imdb_id(&self) -> Option<String> {
+		let field = self
+			.additional_fields
+			.as_ref()
+			.and_then(|fields| fields.get("imdb_id"))
+			.and_then(serde_json::Value::as_str)
+			.map(str::trim)
+			.filter(|id| !id.is_empty())
+			.map(str::to_string);
+		field.or_else(|| {
+			self.source_refs().into_iter().find_map(|source_ref| match source_ref {
+				SourceRef::Imdb(id) => Some(id),
+				_ => None,
+			})
+		})
+	}
+
+	/// Returns a field of ``additional_fields``, if the Sauce is from one of the anime and video indexes.
+	fn video_field(&self, name: &str) -> Option<&serde_json::Value> {
+		let video_indexes = [
+			constants::ANIME.index,
+			constants::H_ANIME.index,
+			constants::MOVIES.index,
+			constants::SHOWS.index,
+		];
+		if video_indexes.contains(&self.index) {
+			self.additional_fields.as_ref()?.get(name)
+		} else {
+			None
+		}
+	}
+
+	/// Returns the names of the authors of the Sauce, separated by commas, or None if the index gives none.
+	pub(crate) fn author_names(&self) -> Option<String> {
+		let names: Vec<String> = self.authors().into_iter().map(|author| author.name).collect();
+		if names.is_empty() {
+			None
+		} else {
+			Some(names.join(", "))
+		}
+	}
+}
+
+/// Parses the first number in a string, such as the episode in ``03`` or ``Episode 3``.
+fn first_number(text: &str) -> Option<u32> {
+	let digits: String = text
+		.chars()
+		.skip_while(|c| !c.is_ascii_digit())
+		.take_while(char::is_ascii_digit)
+		.collect();
+	digits.parse().ok()
+}
+
+/// Parses a time in the form ``HH:MM:SS``, ``MM:SS`` or seconds.
+fn parse_time(time: &str) -> Option<Duration> {
+	let parts: Vec<&str> = time.trim().split(':').collect();
+	if parts.len() > 3 {
+		return None;
+	}
+	let mut seconds: u64 = 0;
+	for part in parts {
+		if part.is_empty() || !part.chars().all(|c| c.is_ascii_digit()) {
+			return None;
+		}
+		seconds = seconds.checked_mul(60)?.checked_add(part.parse().ok()?)?;
+	}
+	Some(Duration::from_secs(seconds))
+}
+
+/// Displays a Sauce on a single line, in the form ``[similarity%] site: title by authors <url>``.
+/// ## Example
+/// ```
+/// use rustnao::SauceBuilder;
+/// let sauce = SauceBuilder::default()
+///     .ext_urls(vec!["https://danbooru.donmai.us/post/show/3441187".to_string()])
+///     .site("Danbooru")
+///     .similarity(92.5)
+///     .additional_fields(serde_json::json!({ "creator": "artist" }))
+///     .build();
+/// assert_eq!(sauce.to_string(), "[92.50%] Danbooru: (untitled) by artist <https://danbooru.donmai.us/post/show/3441187>");
+/// ```
+impl fmt::Display for Sauce {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "[{:.2}%] {}: ", self.similarity, self.site)?;
+		match &self.title {
+			Some(title) if !title.is_empty() => write!(f, "{}", title)?,
+			_ => write!(f, "(untitled)")?,
+		}
+		if let Some(authors) = self.author_names() {
+			write!(f, " by {}", authors)?;
+		}
+		if let Some(url) = self.primary_url() {
+			write!(f, " <{}>", url)?;
+		}
+		Ok(())
+	}
 }
 
 impl fmt::Debug for Sauce {
@@ -83,12 +521,9 @@ impl fmt::Debug for Sauce {
 		}
 		result.push_str("\nsite: ");
 		result.push_str(self.site.as_str());
-		match &self.title {
-			Some(x) => {
-				result.push_str("\ntitle: ");
-				result.push_str(x.as_str());
-			}
-			None => (),
+		if let Some(x) = &self.title {
+			result.push_str("\ntitle: ");
+			result.push_str(x.as_str());
 		}
 		result.push_str("\nindex: ");
 		result.push_str(self.index.to_string().as_str());
@@ -98,11 +533,32 @@ impl fmt::Debug for Sauce {
 		result.push_str(self.similarity.to_string().as_str());
 		result.push_str("\nthumbnail: ");
 		result.push_str(format!("\"{}\"", self.thumbnail.as_str()).as_str());
-		result.push_str("\nauthor_id: ");
-		if let Some(author) = self.additional_fields.clone() {
-			result.push_str(author.to_string().as_str());
+		result.push_str("\nadditional_fields: ");
+		if let Some(fields) = &self.additional_fields {
+			result.push_str(fields.to_string().as_str());
 		}
 
 		write!(f, "{}", result)
 	}
 }
+
+/// The results of a single search, with the warnings raised while processing its response, such as values of an unrecognised shape or results
+/// that had to be skipped.  Values SauceNAO is known to send in more than one shape (like a similarity as a number instead of a string) do not
+/// raise warnings.
+/// ## Example
+/// ```
+/// use rustnao::HandlerBuilder;
+/// let handle = HandlerBuilder::default().api_key("your_api_key").build();
+/// if let Ok(response) = handle.get_sauce_with_warnings("./tests/test.jpg", None, None) {
+///     for warning in response.warnings {
+///         println!("{}", warning);
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct SauceResponse {
+	/// The results of the search
+	pub results: Vec<Sauce>,
+	/// The warnings raised while processing the response
+	pub warnings: Vec<String>,
+}
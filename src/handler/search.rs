@@ -0,0 +1,156 @@
+//! A trait for reverse image searches, so that code can be written against it rather than a concrete Handler, and a fake implementation for tests.
+
+use super::{ErrType, Error, Handler, Result, Sauce};
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+/// The future returned by the methods of ``ReverseImageSearch``.
+pub type SearchFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T>> + 'a>>;
+
+/// A trait for anything that can search for the sources of an image, which ``Handler`` implements.
+///
+/// Code that takes a ``ReverseImageSearch`` instead of a ``Handler`` can be tested with a ``FakeSearch``, without network access.  The trait can be used as
+/// a trait object, such as ``Box<dyn ReverseImageSearch>``.
+/// ### Example
+/// ```
+/// use rustnao::{HandlerBuilder, ReverseImageSearch, Sauce};
+///
+/// async fn first_source(search: &dyn ReverseImageSearch, image_path: &str) -> Option<String> {
+///     let results = search.search(image_path, Some(1), None).await.ok()?;
+///     results.into_iter().next().and_then(|sauce| sauce.ext_urls.into_iter().next())
+/// }
+///
+/// let handle = HandlerBuilder::default().api_key("your_api_key").build();
+/// let source = async_std::task::block_on(first_source(&handle, "https://i.imgur.com/W42kkKS.jpg"));
+/// ```
+pub trait ReverseImageSearch {
+	/// Asynchronously searches for the sources of an image, returning a Result of either a vector of Sauce objects or an error.
+	///
+	/// ### Arguments
+	/// * image_path - A string slice that contains the url or local path of the image to look up.
+	/// * num_results - An Option containing a u32 to specify the maximum number of results.
+	/// * min_similarity - An Option containing a f64 to specify the minimum similarity of the results.
+	fn search<'a>(&'a self, image_path: &'a str, num_results: Option<u32>, min_similarity: Option<f64>) -> SearchFuture<'a, Vec<Sauce>>;
+
+	/// Asynchronously searches for the sources of an image, returning the results as a serialized JSON string.  Otherwise identical to ``search(...)``.
+	fn search_as_json<'a>(&'a self, image_path: &'a str, num_results: Option<u32>, min_similarity: Option<f64>) -> SearchFuture<'a, String> {
+		Box::pin(async move {
+			let results = self.search(image_path, num_results, min_similarity).await?;
+			Ok(serde_json::to_string(&results)?)
+		})
+	}
+
+	/// Searches for the sources of an image, blocking until the search is done.  Otherwise identical to ``search(...)``.
+	fn search_blocking(&self, image_path: &str, num_results: Option<u32>, min_similarity: Option<f64>) -> Result<Vec<Sauce>> {
+		async_std::task::block_on(self.search(image_path, num_results, min_similarity))
+	}
+}
+
+impl ReverseImageSearch for Handler {
+	fn search<'a>(&'a self, image_path: &'a str, num_results: Option<u32>, min_similarity: Option<f64>) -> SearchFuture<'a, Vec<Sauce>> {
+		Box::pin(self.async_get_sauce(image_path, num_results, min_similarity))
+	}
+}
+
+#[derive(Debug, Clone)]
+enum Outcome {
+	Results(Vec<Sauce>),
+	Error(ErrType),
+}
+
+/// An in-memory ``ReverseImageSearch`` that returns configured results for each input, for testing code that searches.
+///
+/// Images without configured results have none.  Like a ``Handler``, it rejects invalid parameters, and the results are limited by ``num_results``
+/// and ``min_similarity``.  Every search is recorded, and clones share the recorded searches.
+/// ### Example
+/// ```
+/// use rustnao::{ErrType, FakeSearch, ReverseImageSearch, SauceBuilder};
+/// let mut fake = FakeSearch::new();
+/// fake.results("cat.png", vec![SauceBuilder::default().site("Pixiv").similarity(92.5).build()])
+///     .error("dog.png", ErrType::Timeout("timed out".to_string()));
+///
+/// assert_eq!(fake.search_blocking("cat.png", None, None).unwrap()[0].site, "Pixiv");
+/// assert!(fake.search_blocking("dog.png", None, None).is_err());
+/// assert!(fake.search_blocking("bird.png", None, None).unwrap().is_empty());
+/// assert_eq!(fake.get_searches(), vec!["cat.png", "dog.png", "bird.png"]);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct FakeSearch {
+	outcomes: HashMap<String, Outcome>,
+	searches: Arc<Mutex<Vec<String>>>,
+}
+
+impl FakeSearch {
+	/// Creates a fake without any configured results.
+	pub fn new() -> FakeSearch {
+		FakeSearch::default()
+	}
+
+	/// Sets the results returned for an image, replacing anything set for it before.
+	///
+	/// ### Arguments
+	/// * image_path - A string slice that contains the url or local path of the image, exactly as it will be searched for.
+	/// * results - The results to return, in order.
+	pub fn results(&mut self, image_path: &str, results: Vec<Sauce>) -> &mut FakeSearch {
+		self.outcomes.insert(image_path.to_string(), Outcome::Results(results));
+		self
+	}
+
+	/// Sets the error returned for an image, replacing anything set for it before.
+	///
+	/// ### Arguments
+	/// * image_path - A string slice that contains the url or local path of the image, exactly as it will be searched for.
+	/// * error - The kind of error to return.
+	pub fn error(&mut self, image_path: &str, error: ErrType) -> &mut FakeSearch {
+		self.outcomes.insert(image_path.to_string(), Outcome::Error(error));
+		self
+	}
+
+	/// Gets the images searched for so far, in order, including those a configured error was returned for.
+	pub fn get_searches(&self) -> Vec<String> {
+		self.searches.lock().unwrap_or_else(|err| err.into_inner()).clone()
+	}
+
+	fn find(&self, image_path: &str, num_results: Option<u32>, min_similarity: Option<f64>) -> Result<Vec<Sauce>> {
+		check_parameters(num_results, min_similarity)?;
+		self.searches.lock().unwrap_or_else(|err| err.into_inner()).push(image_path.to_string());
+		match self.outcomes.get(image_path) {
+			Some(Outcome::Error(kind)) => Err(Error::from(kind.clone())),
+			Some(Outcome::Results(results)) => Ok(limit(results.clone(), num_results, min_similarity)),
+			None => Ok(Vec::new()),
+		}
+	}
+}
+
+impl ReverseImageSearch for FakeSearch {
+	fn search<'a>(&'a self, image_path: &'a str, num_results: Option<u32>, min_similarity: Option<f64>) -> SearchFuture<'a, Vec<Sauce>> {
+		let result = self.find(image_path, num_results, min_similarity);
+		Box::pin(async move { result })
+	}
+}
+
+/// Checks the parameters of a search the way a ``Handler`` does.
+pub(crate) fn check_parameters(num_results: Option<u32>, min_similarity: Option<f64>) -> Result<()> {
+	if min_similarity.is_some_and(|min_similarity| !(0.0..=100.0).contains(&min_similarity)) {
+		Err(Error::invalid_parameter(
+			"min_similarity must be less 100.0 and greater than 0.0.".to_string(),
+		))
+	} else if num_results.is_some_and(|num_results| num_results > 999) {
+		Err(Error::invalid_parameter("num_results must be less than 999.".to_string()))
+	} else {
+		Ok(())
+	}
+}
+
+/// Drops the results below the minimum similarity, and keeps at most ``num_results`` of the rest.
+pub(crate) fn limit(results: Vec<Sauce>, num_results: Option<u32>, min_similarity: Option<f64>) -> Vec<Sauce> {
+	// Compared at the precision of the similarity, so that a result is kept if SauceNAO reported exactly the minimum
+	let min_similarity = min_similarity.unwrap_or(0.0) as f32;
+	results
+		.into_iter()
+		.filter(|sauce| sauce.similarity >= min_similarity)
+		.take(num_results.map_or(usize::MAX, |num_results| num_results as usize))
+		.collect()
+}
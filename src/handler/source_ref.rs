@@ -0,0 +1,233 @@
+//! Structured identifiers of the works that the external URLs of results point to.
+
+use std::fmt;
+use url::Url;
+
+/// The site and ID of the work an external URL points to, such as a Pixiv illustration or a Danbooru post.
+///
+/// The different shapes of URL for the same work, such as old and new Pixiv links or links with and without ``www.``, give the same ``SourceRef``.
+/// ### Example
+/// ```
+/// use rustnao::SourceRef;
+/// let source = SourceRef::parse("https://www.pixiv.net/member_illust.php?mode=medium&illust_id=61477678").unwrap();
+/// assert_eq!(source, SourceRef::Pixiv(61477678));
+/// assert_eq!(source, SourceRef::parse("https://www.pixiv.net/en/artworks/61477678").unwrap());
+/// assert_eq!(source.site(), "Pixiv");
+/// assert_eq!(source.id(), "61477678");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SourceRef {
+	/// A Pixiv illustration, by its illust ID.
+	Pixiv(u64),
+	/// A Danbooru post, by its ID.
+	Danbooru(u64),
+	/// A Gelbooru post, by its ID.
+	Gelbooru(u64),
+	/// A yande.re post, by its ID.
+	YandeRe(u64),
+	/// A Konachan post, by its ID.
+	Konachan(u64),
+	/// A DeviantArt deviation, by its ID.
+	DeviantArt(u64),
+	/// A Nijie illustration, by its ID.
+	Nijie(u64),
+	/// A tweet, by its status ID.
+	Twitter(u64),
+	/// A MangaDex chapter, by its ID, which is a UUID for current links and a number for legacy ones.
+	MangaDex(String),
+	/// An AniDB anime, by its aid.
+	AniDb(u64),
+	/// An IMDb title, by its ID, such as ``tt0111161``.
+	Imdb(String),
+}
+
+impl SourceRef {
+	/// Classifies an external URL, returning None if it is not a link to a work on a known site.
+	///
+	/// ### Arguments
+	/// * url - A string slice that contains the URL, such as one of the ``ext_urls`` of a Sauce.
+	///
+	/// ### Examples
+	/// ```
+	/// use rustnao::SourceRef;
+	/// assert_eq!(SourceRef::parse("https://twitter.com/user/status/1160000000000000000"), Some(SourceRef::Twitter(1160000000000000000)));
+	/// assert_eq!(SourceRef::parse("https://www.pixiv.net/users/212801"), None);
+	/// ```
+	pub fn parse(url: &str) -> Option<SourceRef> {
+		let url = Url::parse(url.trim()).ok()?;
+		let host = url.host_str()?.to_lowercase();
+		let host = host.trim_start_matches("www.").trim_start_matches("mobile.");
+		let segments: Vec<&str> = url.path_segments()?.filter(|segment| !segment.is_empty()).collect();
+		let query = |name: &str| url.query_pairs().find(|(key, _)| key == name).map(|(_, value)| value.into_owned());
+
+		match host {
+			"pixiv.net" => match segments.as_slice() {
+				["member_illust.php"] => query("illust_id").and_then(|id| number(&id)).map(SourceRef::Pixiv),
+				["artworks", id] | [_, "artworks", id] | ["i", id] => number(id).map(SourceRef::Pixiv),
+				_ => None,
+			},
+			// Illustrations and their resized copies are named after the illustration, such as 61477678_p0.png, unlike avatars under /user-profile/
+			"i.pximg.net" => match segments.as_slice() {
+				["img-original", "img", .., file] | ["img-master", "img", .., file] | ["c", _, "img-master", "img", .., file] => {
+					number(file.split('_').next()?).map(SourceRef::Pixiv)
+				}
+				_ => None,
+			},
+			"danbooru.donmai.us" => match segments.as_slice() {
+				["post", "show", id] | ["posts", id] => number(id).map(SourceRef::Danbooru),
+				_ => None,
+			},
+			"gelbooru.com" => match query("page").as_deref() {
+				Some("post") => query("id").and_then(|id| number(&id)).map(SourceRef::Gelbooru),
+				_ => None,
+			},
+			"yande.re" => post_show(&segments).map(SourceRef::YandeRe),
+			"konachan.com" | "konachan.net" => post_show(&segments).map(SourceRef::Konachan),
+			"deviantart.com" => match segments.as_slice() {
+				["view", id] | ["deviation", id] => number(id).map(SourceRef::DeviantArt),
+				[_, "art", title] => trailing_number(title).map(SourceRef::DeviantArt),
+				_ => None,
+			},
+			// Deviations used to be on subdomains of their artist, such as https://artist.deviantart.com/art/Title-123456789
+			host if host.ends_with(".deviantart.com") => match segments.as_slice() {
+				["art", title] => trailing_number(title).map(SourceRef::DeviantArt),
+				_ => None,
+			},
+			// Short links are the ID in base 36, after a "d"
+			"fav.me" => u64::from_str_radix(segments.first()?.strip_prefix('d')?, 36)
+				.ok()
+				.map(SourceRef::DeviantArt),
+			"nijie.info" | "sp.nijie.info" => match segments.as_slice() {
+				["view.php"] | ["view_popup.php"] => query("id").and_then(|id| number(&id)).map(SourceRef::Nijie),
+				_ => None,
+			},
+			"twitter.com" | "x.com" => match segments.as_slice() {
+				[_, "status", id, ..] | [_, "statuses", id, ..] | ["i", "web", "status", id, ..] => number(id).map(SourceRef::Twitter),
+				_ => None,
+			},
+			"mangadex.org" => match segments.as_slice() {
+				["chapter", id, ..] if is_mangadex_id(id) => Some(SourceRef::MangaDex(id.to_lowercase())),
+				_ => None,
+			},
+			"anidb.net" => match segments.as_slice() {
+				["perl-bin", "animedb.pl"] if query("show").as_deref() == Some("anime") => {
+					query("aid").and_then(|id| number(&id)).map(SourceRef::AniDb)
+				}
+				["anime", id] => number(id).map(SourceRef::AniDb),
+				[id] => id.strip_prefix('a').and_then(number).map(SourceRef::AniDb),
+				_ => None,
+			},
+			"imdb.com" | "m.imdb.com" => match segments.as_slice() {
+				["title", id, ..] if is_imdb_id(id) => Some(SourceRef::Imdb(id.to_lowercase())),
+				_ => None,
+			},
+			_ => None,
+		}
+	}
+
+	/// Returns the name of the site, as SauceNAO names it where it indexes the site.
+	pub fn site(&self) -> &'static str {
+		match self {
+			SourceRef::Pixiv(_) => "Pixiv",
+			SourceRef::Danbooru(_) => "Danbooru",
+			SourceRef::Gelbooru(_) => "Gelbooru",
+			SourceRef::YandeRe(_) => "Yande.re",
+			SourceRef::Konachan(_) => "Konachan",
+			SourceRef::DeviantArt(_) => "deviantArt",
+			SourceRef::Nijie(_) => "Nijie Images",
+			SourceRef::Twitter(_) => "Twitter",
+			SourceRef::MangaDex(_) => "MangaDex",
+			SourceRef::AniDb(_) => "AniDB",
+			SourceRef::Imdb(_) => "IMDb",
+		}
+	}
+
+	/// Returns the canonical link to the work, which is the same whichever shape of URL the SourceRef was parsed from.
+	/// ### Examples
+	/// ```
+	/// use rustnao::SourceRef;
+	/// let source = SourceRef::parse("http://danbooru.donmai.us/post/show/3441187").unwrap();
+	/// assert_eq!(source.url(), "https://danbooru.donmai.us/posts/3441187");
+	/// ```
+	pub fn url(&self) -> String {
+		match self {
+			SourceRef::Pixiv(id) => format!("https://www.pixiv.net/artworks/{}", id),
+			SourceRef::Danbooru(id) => format!("https://danbooru.donmai.us/posts/{}", id),
+			SourceRef::Gelbooru(id) => format!("https://gelbooru.com/index.php?page=post&s=view&id={}", id),
+			SourceRef::YandeRe(id) => format!("https://yande.re/post/show/{}", id),
+			SourceRef::Konachan(id) => format!("https://konachan.com/post/show/{}", id),
+			SourceRef::DeviantArt(id) => format!("https://www.deviantart.com/deviation/{}", id),
+			SourceRef::Nijie(id) => format!("https://nijie.info/view.php?id={}", id),
+			SourceRef::Twitter(id) => format!("https://twitter.com/i/web/status/{}", id),
+			SourceRef::MangaDex(id) => format!("https://mangadex.org/chapter/{}", id),
+			SourceRef::AniDb(id) => format!("https://anidb.net/anime/{}", id),
+			SourceRef::Imdb(id) => format!("https://www.imdb.com/title/{}", id),
+		}
+	}
+
+	/// Returns the ID of the work on its site.
+	pub fn id(&self) -> String {
+		match self {
+			SourceRef::Pixiv(id)
+			| SourceRef::Danbooru(id)
+			| SourceRef::Gelbooru(id)
+			| SourceRef::YandeRe(id)
+			| SourceRef::Konachan(id)
+			| SourceRef::DeviantArt(id)
+			| SourceRef::Nijie(id)
+			| SourceRef::Twitter(id)
+			| SourceRef::AniDb(id) => id.to_string(),
+			SourceRef::MangaDex(id) | SourceRef::Imdb(id) => id.clone(),
+		}
+	}
+}
+
+/// Displays a SourceRef as the site and ID, such as ``Pixiv 61477678``.
+impl fmt::Display for SourceRef {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{} {}", self.site(), self.id())
+	}
+}
+
+/// Parses an ID made up only of digits.
+fn number(id: &str) -> Option<u64> {
+	if id.chars().all(|c| c.is_ascii_digit()) {
+		id.parse().ok()
+	} else {
+		None
+	}
+}
+
+/// Parses the number at the end of a DeviantArt title, such as ``Title-Words-123456789``.
+fn trailing_number(title: &str) -> Option<u64> {
+	number(title.rsplit('-').next()?)
+}
+
+/// Parses the post ID of a Moebooru link, such as ``/post/show/461215`` or ``/post/show/461215/tags``.
+fn post_show(segments: &[&str]) -> Option<u64> {
+	match segments {
+		["post", "show", id, ..] => number(id),
+		_ => None,
+	}
+}
+
+/// Returns whether a MangaDex chapter ID is a UUID or a legacy numeric ID.
+fn is_mangadex_id(id: &str) -> bool {
+	let is_uuid = id.len() == 36
+		&& id.char_indices().all(|(position, c)| {
+			if [8, 13, 18, 23].contains(&position) {
+				c == '-'
+			} else {
+				c.is_ascii_hexdigit()
+			}
+		});
+	is_uuid || number(id).is_some()
+}
+
+/// Returns whether an IMDb title ID is ``tt`` followed by digits.
+fn is_imdb_id(id: &str) -> bool {
+	match id.get(2..) {
+		Some(digits) if id[..2].eq_ignore_ascii_case("tt") => number(digits).is_some(),
+		_ => false,
+	}
+}
@@ -0,0 +1,126 @@
+//! Rendering of search results as plain text tables.
+
+use super::Sauce;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// The width of tables rendered without a width, that of a standard terminal.
+const DEFAULT_WIDTH: usize = 80;
+
+/// The narrowest a column will be shrunk to when fitting a table into a width.
+const MIN_COLUMN_WIDTH: usize = 4;
+
+const SEPARATOR: &str = " | ";
+
+const HEADERS: [&str; 5] = ["Similarity", "Site", "Title", "Author", "URL"];
+
+/// A trait to render results as a plain text table, with columns for similarity, site, title, author and primary URL.
+/// ### Example
+/// ```
+/// use rustnao::{HandlerBuilder, ToTable};
+/// let handle = HandlerBuilder::default().api_key("your_api_key").num_results(999).db(999).build();
+/// let result = handle.get_sauce("./tests/test.jpg", None, None);
+/// if result.is_ok() {
+///     println!("{}", result.unwrap().to_table());
+/// }
+/// ```
+pub trait ToTable {
+	/// Renders a table truncated to 80 characters, the width of a standard terminal.  Use ``to_table_with_width(...)`` to fit another width, such as
+	/// that of the actual terminal.
+	/// ### Example
+	/// ```
+	/// use rustnao::{SauceBuilder, ToTable};
+	/// let results = vec![SauceBuilder::default().site("Pixiv").similarity(92.5).build()];
+	/// println!("{}", results.to_table());
+	/// ```
+	fn to_table(&self) -> String {
+		self.to_table_with_width(DEFAULT_WIDTH)
+	}
+
+	/// Renders a table truncated to the given width, in characters.  Columns are never shrunk below a few characters, so very small widths may be exceeded.
+	/// ### Example
+	/// ```
+	/// use rustnao::{SauceBuilder, ToTable};
+	/// let results = vec![SauceBuilder::default().site("Pixiv").similarity(92.5).build()];
+	/// let table = results.to_table_with_width(60);
+	/// assert!(table.lines().all(|line| line.chars().count() <= 60));
+	/// ```
+	fn to_table_with_width(&self, width: usize) -> String;
+}
+
+impl ToTable for Vec<Sauce> {
+	fn to_table_with_width(&self, width: usize) -> String {
+		let rows: Vec<[String; 5]> = self
+			.iter()
+			.map(|sauce| {
+				[
+					format!("{:.2}%", sauce.similarity),
+					sauce.site.clone(),
+					sauce.title.clone().unwrap_or_default(),
+					sauce.author_names().unwrap_or_default(),
+					sauce.primary_url().unwrap_or("").to_string(),
+				]
+			})
+			.collect();
+
+		let mut widths: Vec<usize> = HEADERS.iter().map(|header| header.width()).collect();
+		for row in &rows {
+			for (column, cell) in row.iter().enumerate() {
+				widths[column] = widths[column].max(cell.width());
+			}
+		}
+
+		// Shrink the widest of the text columns until the table fits, leaving similarity untouched.
+		let available = width.saturating_sub(SEPARATOR.len() * (HEADERS.len() - 1));
+		while widths.iter().sum::<usize>() > available {
+			let (column, column_width) = widths.iter().cloned().enumerate().skip(1).max_by_key(|&(_, w)| w).unwrap();
+			if column_width <= MIN_COLUMN_WIDTH {
+				break;
+			}
+			widths[column] -= 1;
+		}
+
+		let mut result = render_row(&HEADERS.iter().map(|header| header.to_string()).collect::<Vec<String>>(), &widths);
+		result.push_str(widths.iter().map(|w| "-".repeat(*w)).collect::<Vec<String>>().join("-+-").as_str());
+		result.push('\n');
+		for row in &rows {
+			result.push_str(render_row(row, &widths).as_str());
+		}
+		result
+	}
+}
+
+fn render_row(cells: &[String], widths: &[usize]) -> String {
+	let line = cells
+		.iter()
+		.zip(widths)
+		.map(|(cell, width)| fit(cell, *width))
+		.collect::<Vec<String>>()
+		.join(SEPARATOR);
+	format!("{}\n", line.trim_end())
+}
+
+/// Truncates or pads a cell to exactly the given display width, replacing newlines with spaces.  Wide characters, such as CJK text, take up two
+/// columns of a terminal, so a truncated cell may be padded with a space to make up the width.
+fn fit(cell: &str, width: usize) -> String {
+	let cell = cell.replace(['\n', '\r'], " ");
+	let mut length = cell.width();
+	let mut fitted = if length > width {
+		let mut truncated = String::new();
+		length = 0;
+		for c in cell.chars() {
+			let char_width = c.width().unwrap_or(0);
+			if length + char_width > width.saturating_sub(1) {
+				break;
+			}
+			truncated.push(c);
+			length += char_width;
+		}
+		truncated.push('…');
+		length += 1;
+		truncated
+	} else {
+		cell
+	};
+	fitted.push_str(" ".repeat(width.saturating_sub(length)).as_str());
+	fitted
+}
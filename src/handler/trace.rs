@@ -0,0 +1,93 @@
+//! Macros for the optional ``tracing`` instrumentation.  With the ``tracing`` feature enabled they forward to the macros of the ``tracing`` crate, and
+//! otherwise they expand to nothing, so their arguments are never evaluated.
+
+#[cfg(feature = "tracing")]
+macro_rules! debug {
+	($($arg:tt)*) => {
+		tracing::debug!($($arg)*)
+	};
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! debug {
+	($($arg:tt)*) => {};
+}
+
+#[cfg(feature = "tracing")]
+macro_rules! warn {
+	($($arg:tt)*) => {
+		tracing::warn!($($arg)*)
+	};
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! warn {
+	($($arg:tt)*) => {};
+}
+
+/// Instruments a future with a span at the debug level, or returns the future as is without the ``tracing`` feature.
+#[cfg(feature = "tracing")]
+macro_rules! instrument {
+	($future:expr, $($span:tt)*) => {
+		tracing::Instrument::instrument($future, tracing::debug_span!($($span)*))
+	};
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! instrument {
+	($future:expr, $($span:tt)*) => {
+		$future
+	};
+}
+
+/// How many of the results of a response were dropped, and why.
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(not(any(feature = "tracing", feature = "metrics")), allow(dead_code))]
+pub(crate) struct FilterStats {
+	/// The number of results in the response.
+	pub received: usize,
+	/// Results that were not objects.
+	pub invalid: usize,
+	/// Results whose header could not be parsed.
+	pub unparseable_header: usize,
+	/// Results below the minimum similarity.
+	pub below_similarity: usize,
+	/// Results without URLs, dropped by the empty filter.
+	pub empty_urls: usize,
+}
+
+/// The reasons a result is dropped from a response.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum FilterReason {
+	Invalid,
+	UnparseableHeader,
+	BelowSimilarity,
+	EmptyUrls,
+}
+
+impl FilterStats {
+	/// Counts a result that was dropped for the given reason.
+	pub(crate) fn skip(&mut self, reason: FilterReason) {
+		match reason {
+			FilterReason::Invalid => self.invalid += 1,
+			FilterReason::UnparseableHeader => self.unparseable_header += 1,
+			FilterReason::BelowSimilarity => self.below_similarity += 1,
+			FilterReason::EmptyUrls => self.empty_urls += 1,
+		}
+	}
+}
+
+/// Returns the query of a request URL with the API key replaced, so that it can be recorded.
+#[cfg(feature = "tracing")]
+pub(crate) fn redacted_query(url: &url::Url) -> String {
+	url.query_pairs()
+		.map(|(name, value)| {
+			if name == "api_key" {
+				format!("{}=<redacted>", name)
+			} else {
+				format!("{}={}", name, value)
+			}
+		})
+		.collect::<Vec<String>>()
+		.join("&")
+}
@@ -16,7 +16,7 @@
 //! ## Examples
 //! Here's a simple example:
 //! ```no_run
-//! use rustnao::{Handler, HandlerBuilder, Sauce, Result};
+//! use rustnao::{Handler, HandlerBuilder, MultiSearch, Sauce, Result};
 //!
 //! fn main() {
 //!     let api_key = "your_api_key";
@@ -42,13 +42,38 @@
 // TODO: further docs on use-case (see Sagiri)
 
 #![deny(missing_docs)]
+// The crate example above shows a whole program
+#![allow(clippy::needless_doctest_main)]
 
 extern crate async_std;
-extern crate failure;
 extern crate serde;
 extern crate serde_json;
 extern crate surf;
+extern crate unicode_width;
 extern crate url;
 
 mod handler;
-pub use handler::{ErrType, Error, Handler, HandlerBuilder, Result, Sauce, ToJSON};
+#[cfg(feature = "ascii2d")]
+pub use handler::Ascii2d;
+#[cfg(feature = "iqdb")]
+pub use handler::Iqdb;
+#[cfg(feature = "metrics")]
+pub use handler::Metrics;
+#[cfg(feature = "tracemoe")]
+pub use handler::TraceMoe;
+pub use handler::{
+	normalize_url, AccountType, Author, Cassette, ErrType, Error, Export, ExportFormat, FakeSearch, FallbackChain, FallbackResult, FallbackStep,
+	FileQuotaStore, Handler, HandlerBuilder, MultiSearch, QuotaRecord, QuotaStatus, QuotaStore, RankReason, RankSources, RankedSauce, Result,
+	ResultHeader, ReverseImageSearch, Sauce, SauceBuilder, SauceGroup, SauceResponse, SearchFuture, SearchListener, SourcePolicy, SourceRef,
+	ToGroups, ToJSON, ToTable,
+};
+
+/// Entry points for the fuzz targets in ``fuzz/``, which ``cargo fuzz`` builds with ``--cfg fuzzing``.  They are not part of the API.
+#[cfg(fuzzing)]
+#[doc(hidden)]
+pub mod fuzzing {
+	/// Processes a raw SauceNAO response like a search would, which must never panic whatever the response contains.
+	pub fn process_response(response: &str) {
+		let _ = crate::HandlerBuilder::default().build().process_json(response);
+	}
+}
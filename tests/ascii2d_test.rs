@@ -0,0 +1,95 @@
+#![cfg(feature = "ascii2d")]
+
+mod common;
+
+use common::{MockResponse, MockServer};
+use rustnao::{Ascii2d, ErrType, ReverseImageSearch};
+
+/// Returns an ascii2d backend that searches the mock server
+fn ascii2d(server: &MockServer) -> Ascii2d {
+	let mut ascii2d = Ascii2d::new();
+	ascii2d.api_url(server.base_url().as_str());
+	ascii2d
+}
+
+/// Returns the ascii2d result page fixture as a response
+fn page() -> MockResponse {
+	MockResponse::fixture("ascii2d.html").header("Content-Type", "text/html; charset=utf-8")
+}
+
+/// Tests that a URL search follows the redirect to the result page, which is parsed
+#[test]
+fn test_url_search() {
+	let server = MockServer::start();
+	server.push(MockResponse::redirect("/search/color/114a6f2d"));
+	server.push(page());
+	let result = ascii2d(&server).search_blocking("https://i.imgur.com/W42kkKS.jpg", None, None).unwrap();
+
+	let requests = server.requests();
+	assert_eq!(requests.len(), 2);
+	assert_eq!(requests[0].method, "GET");
+	assert_eq!(requests[0].path, "/search/url/https%3A%2F%2Fi.imgur.com%2FW42kkKS.jpg");
+	assert_eq!(requests[1].path, "/search/color/114a6f2d");
+
+	assert_eq!(result.len(), 2);
+	assert_eq!(result[0].site, "Pixiv");
+	assert_eq!(result[0].index, 5);
+	assert_eq!(result[0].similarity, 0.0);
+	assert_eq!(result[0].title.as_deref(), Some("Swimsuit & Sunset"));
+	assert_eq!(result[0].ext_urls, vec!["https://www.pixiv.net/artworks/75279092"]);
+	assert_eq!(result[0].thumbnail, format!("{}thumbnail/5/2/7/c/527c.jpg", server.base_url()));
+	let fields = result[0].additional_fields.as_ref().unwrap();
+	assert_eq!(fields["engine"], "ascii2d");
+	assert_eq!(fields["author_name"], "Mikan");
+	assert_eq!(fields["author_url"], "https://www.pixiv.net/users/212801");
+
+	assert_eq!(result[1].site, "twitter.com");
+	assert_eq!(result[1].index, 999);
+	assert_eq!(result[1].title.as_deref(), Some("2019.08.10"));
+	assert!(result[1].additional_fields.as_ref().unwrap().get("author_name").is_none());
+}
+
+/// Tests that a local file is uploaded
+#[test]
+fn test_file_search() {
+	let server = MockServer::start();
+	server.push(page());
+	let result = ascii2d(&server).search_blocking("./tests/test.jpg", Some(1), None).unwrap();
+	assert_eq!(result.len(), 1);
+
+	let request = &server.requests()[0];
+	assert_eq!(request.method, "POST");
+	assert_eq!(request.path, "/search/file");
+	assert!(String::from_utf8_lossy(&request.body).contains("name=\"file\"; filename=\"test.jpg\""));
+}
+
+/// Tests that a minimum similarity does not reject results, as ascii2d reports none, but is still validated
+#[test]
+fn test_min_similarity() {
+	let server = MockServer::start();
+	server.push(page());
+	let ascii2d = ascii2d(&server);
+	let result = ascii2d.search_blocking("./tests/test.jpg", None, Some(50.0)).unwrap();
+	assert_eq!(result.len(), 2);
+
+	let err = ascii2d.search_blocking("./tests/test.jpg", None, Some(101.0)).unwrap_err();
+	assert!(matches!(err.kind(), ErrType::InvalidParameters(_)), "{:?}", err);
+	assert_eq!(server.requests().len(), 1);
+}
+
+/// Tests a page laid out differently, with an item without a linked work, single quoted attributes, and an absolute thumbnail URL
+#[test]
+fn test_layout_variants() {
+	let server = MockServer::start();
+	server.push(MockResponse::fixture("ascii2d_variants.html"));
+	let result = ascii2d(&server).search_blocking("./tests/test.jpg", None, None).unwrap();
+
+	assert_eq!(result.len(), 1);
+	assert_eq!(result[0].site, "Pixiv");
+	assert_eq!(result[0].title.as_deref(), Some("Night <Sky>"));
+	assert_eq!(result[0].ext_urls, vec!["https://www.pixiv.net/artworks/98765432"]);
+	assert_eq!(result[0].thumbnail, "https://ascii2d.net/thumbnail/2/c/3/d/2c3d.jpg");
+	let fields = result[0].additional_fields.as_ref().unwrap();
+	assert_eq!(fields["author_name"], "Hoshi");
+	assert_eq!(fields["author_url"], "https://www.pixiv.net/users/1234");
+}
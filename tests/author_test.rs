@@ -0,0 +1,111 @@
+mod common;
+
+use common::{MockResponse, MockServer};
+use rustnao::{Author, Handler, HandlerBuilder, SauceBuilder, ToTable};
+use serde_json::json;
+
+/// Creates an author
+fn author(name: &str, id: Option<&str>, profile_url: Option<&str>, site: &str) -> Author {
+	Author {
+		name: name.to_string(),
+		id: id.map(str::to_string),
+		profile_url: profile_url.map(str::to_string),
+		site: site.to_string(),
+	}
+}
+
+/// Returns the authors of a result from the given site and index, with the given additional fields
+fn authors(site: &str, index: u32, additional_fields: serde_json::Value) -> Vec<Author> {
+	SauceBuilder::default()
+		.site(site)
+		.index(index)
+		.additional_fields(additional_fields)
+		.build()
+		.authors()
+}
+
+/// Tests the authors of the Pixiv and Danbooru results of a SauceNAO response, and of the result without any
+#[test]
+fn test_authors_from_response() {
+	let server = MockServer::start();
+	server.push(MockResponse::fixture("pixiv_danbooru.json"));
+	let handle = HandlerBuilder::default().api_key("test_api_key").api_url(server.url().as_str()).build();
+	let result = handle.get_sauce("https://i.imgur.com/W42kkKS.jpg", None, None).unwrap();
+
+	assert_eq!(
+		result[0].authors(),
+		vec![author(
+			"リン☆ユウ＠1日目西れ44b",
+			Some("4754550"),
+			Some("https://www.pixiv.net/users/4754550"),
+			"Pixiv"
+		)]
+	);
+	assert_eq!(result[1].authors(), vec![author("lin_yuu", None, None, "Danbooru")]);
+	assert!(result[2].authors().is_empty());
+}
+
+/// Tests the fields that each kind of index gives its authors in
+#[test]
+fn test_authors_by_index() {
+	assert_eq!(
+		authors("Nijie Images", Handler::NIJIE, json!({ "member_name": " someone ", "member_id": "728" })),
+		vec![author(
+			"someone",
+			Some("728"),
+			Some("https://nijie.info/members.php?id=728"),
+			"Nijie Images"
+		)]
+	);
+	assert_eq!(
+		authors("drawr Images", Handler::DRAWR, json!({ "member_name": "someone", "member_id": 12 })),
+		vec![author("someone", Some("12"), None, "drawr Images")]
+	);
+	assert_eq!(
+		authors(
+			"deviantArt",
+			Handler::DEVIANTART,
+			json!({ "author_name": "artist", "author_url": "https://www.deviantart.com/artist" })
+		),
+		vec![author("artist", None, Some("https://www.deviantart.com/artist"), "deviantArt")]
+	);
+	assert_eq!(
+		authors(
+			"Twitter",
+			41,
+			json!({ "twitter_user_handle": "@artist", "twitter_user_id": "1234", "tweet_id": "1160000000000000000" })
+		),
+		vec![author("artist", Some("1234"), Some("https://twitter.com/artist"), "Twitter")]
+	);
+	assert_eq!(
+		authors(
+			"Pawoo.net",
+			Handler::PAWOO_NET,
+			json!({ "pawoo_user_acct": "artist", "pawoo_user_display_name": "Artist" })
+		),
+		vec![author("Artist", Some("artist"), Some("https://pawoo.net/@artist"), "Pawoo.net")]
+	);
+	assert_eq!(
+		authors("H-Misc", Handler::H_MISC, json!({ "creator": ["first", "", "second", "first"] })),
+		vec![author("first", None, None, "H-Misc"), author("second", None, None, "H-Misc")]
+	);
+	assert_eq!(
+		authors("MangaDex", Handler::MANGADEX, json!({ "author": "writer", "artist": "illustrator" })),
+		vec![author("illustrator", None, None, "MangaDex"), author("writer", None, None, "MangaDex")]
+	);
+	assert!(authors("Anime", Handler::ANIME, json!({ "source": "Show", "anidb_aid": 4037 })).is_empty());
+	assert!(SauceBuilder::default().build().authors().is_empty());
+}
+
+/// Tests that every author is shown in the single line display and the table
+#[test]
+fn test_authors_display() {
+	let sauce = SauceBuilder::default()
+		.site("MangaDex")
+		.similarity(90.0)
+		.additional_fields(json!({ "author": "writer", "artist": "illustrator" }))
+		.build();
+	assert_eq!(sauce.to_string(), "[90.00%] MangaDex: (untitled) by illustrator, writer");
+	assert!(vec![sauce].to_table_with_width(500).contains("illustrator, writer"));
+	assert_eq!(author("lin_yuu", None, None, "Danbooru").to_string(), "lin_yuu (Danbooru)");
+}
@@ -1,136 +1,152 @@
+mod common;
+
+use common::{MockResponse, MockServer};
 use rustnao::{Handler, HandlerBuilder, Sauce};
 
 const FILE: &str = "https://i.imgur.com/W42kkKS.jpg";
 const LOCAL_FILE: &str = "./tests/test.jpg";
 
-/// Creates a handler for testing purposes
-fn create_handler(dbmask: Vec<u32>, dbmaski: Vec<u32>, db_option: Option<u32>, numres: u32) -> Handler {
-	let mut api_key = "".to_string();
-
-	let data = std::fs::read_to_string("config.json");
-	if let Ok(val) = data {
-		let json: serde_json::Value = serde_json::from_str(val.as_str()).expect("JSON not well formatted.");
-		let json_api_key = json["api_key"].as_str();
-
-		if let Some(key) = json_api_key {
-			api_key = key.to_string();
-		}
-	}
-
-	match db_option {
-		Some(db) => HandlerBuilder::default()
-			.db_mask(dbmask)
-			.db_mask_i(dbmaski)
-			.db(db)
-			.num_results(numres)
-			.api_key(api_key.as_str())
-			.build(),
-		None => HandlerBuilder::default()
-			.db_mask(dbmask)
-			.db_mask_i(dbmaski)
-			.num_results(numres)
-			.api_key(api_key.as_str())
-			.build(),
+/// Creates a handler for testing purposes, which searches with the mock server
+fn create_handler(server: &MockServer, dbmask: Vec<u32>, dbmaski: Vec<u32>, db_option: Option<u32>, numres: u32) -> Handler {
+	let mut builder = HandlerBuilder::default();
+	builder
+		.db_mask(dbmask)
+		.db_mask_i(dbmaski)
+		.num_results(numres)
+		.api_key("test_api_key")
+		.api_url(server.url().as_str());
+	if let Some(db) = db_option {
+		builder.db(db);
 	}
+	builder.build()
 }
 
 /// Tests handler creation
 #[test]
 fn test_check_handler_creation() {
-	create_handler([].to_vec(), [].to_vec(), Some(999), 999);
+	let server = MockServer::start();
+	create_handler(&server, [].to_vec(), [].to_vec(), Some(999), 999);
+	assert!(server.requests().is_empty());
 }
 
 /// Tests short and long limit checks (which should change after a search)
 #[test]
 fn test_get_short_and_long_limits() {
-	let handle = create_handler([].to_vec(), [].to_vec(), Some(999), 999);
-	let cur_short_before = handle.get_current_short_limit();
-	let cur_long_before = handle.get_current_long_limit();
-	let result = handle.get_sauce(FILE, None, None);
-	if result.is_ok() {
-		assert!(
-			cur_short_before > handle.get_current_short_limit(),
-			format!("{} vs {}", cur_short_before, handle.get_current_short_limit())
-		);
-		assert!(
-			cur_long_before > handle.get_current_long_limit(),
-			format!("{} vs {}", cur_long_before, handle.get_current_long_limit())
-		);
-	}
+	let server = MockServer::start();
+	server.push(MockResponse::fixture("pixiv_danbooru.json"));
+	let handle = create_handler(&server, [].to_vec(), [].to_vec(), Some(999), 999);
+	assert_eq!(handle.get_current_short_limit(), 12);
+	assert_eq!(handle.get_current_long_limit(), 200);
+	handle.get_sauce(FILE, None, None).unwrap();
+	assert_eq!(handle.get_short_limit(), 4);
+	assert_eq!(handle.get_long_limit(), 100);
+	assert_eq!(handle.get_current_short_limit(), 3);
+	assert_eq!(handle.get_current_long_limit(), 98);
 }
 
 /// Tests searching for filtering empty sourced URLs
 #[test]
 fn test_filter_empty_sauce() {
-	let handle = create_handler([].to_vec(), [].to_vec(), Some(999), 999);
-	let vec: rustnao::Result<Vec<Sauce>> = handle.get_sauce(FILE, None, None);
-	if let Ok(vec_unwrap) = vec {
-		let only_empty: Vec<Sauce> = vec_unwrap.into_iter().filter(|sauce| !sauce.has_empty_url()).collect();
-		for o in only_empty {
-			assert!(!o.ext_urls.is_empty());
-		}
+	let server = MockServer::start();
+	server.push(MockResponse::fixture("pixiv_danbooru.json"));
+	server.push(MockResponse::fixture("pixiv_danbooru.json"));
+	let handle = create_handler(&server, [].to_vec(), [].to_vec(), Some(999), 999);
+	let vec: Vec<Sauce> = handle.get_sauce(FILE, None, None).unwrap();
+	assert_eq!(vec.len(), 3);
+	let only_empty: Vec<Sauce> = vec.into_iter().filter(|sauce| !sauce.has_empty_url()).collect();
+	assert_eq!(only_empty.len(), 2);
+	for o in only_empty {
+		assert!(!o.ext_urls.is_empty());
 	}
+
+	handle.set_empty_filter(true);
+	assert_eq!(handle.get_sauce(FILE, None, None).unwrap().len(), 2);
 }
 
 /// Tests local searching (local file)
 #[test]
 fn test_local() {
-	let handle = create_handler([].to_vec(), [].to_vec(), Some(999), 2);
-	let vec = handle.get_sauce_as_json(LOCAL_FILE, None, None);
-	match vec {
-		Ok(result) => println!("Passed, {}", result),
-		Err(result) => println!(
-			"
-		 out, {}",
-			result
-		),
-	}
+	let server = MockServer::start();
+	server.push(MockResponse::fixture("pixiv_danbooru.json"));
+	let handle = create_handler(&server, [].to_vec(), [].to_vec(), Some(999), 2);
+	let result = handle.get_sauce_as_json(LOCAL_FILE, None, None).unwrap();
+	assert!(result.contains("61477678"), "{}", result);
+
+	let request = &server.requests()[0];
+	assert_eq!(request.method, "POST");
+	assert_eq!(request.query("url"), None);
+	assert_eq!(request.body, std::fs::read(LOCAL_FILE).unwrap());
+}
+
+/// Tests the parameters sent with a search
+#[test]
+fn test_parameters() {
+	let server = MockServer::start();
+	server.push(MockResponse::empty(3, 99));
+	let handle = create_handler(&server, [].to_vec(), [].to_vec(), Some(999), 999);
+	handle.get_sauce(FILE, None, None).unwrap();
+
+	let request = &server.requests()[0];
+	assert_eq!(request.path, "/search.php");
+	assert_eq!(request.query("api_key"), Some("test_api_key"));
+	assert_eq!(request.query("output_type"), Some("2"));
+	assert_eq!(request.query("db"), Some("999"));
+	assert_eq!(request.query("url"), Some(FILE));
+	assert!(request.body.is_empty());
 }
 
 /// Tests setting a max number of results
 #[test]
 fn test_limiting() {
-	let handle = create_handler([].to_vec(), [].to_vec(), Some(999), 2);
-	let vec = handle.get_sauce(FILE, None, None);
-	if let Ok(vec_unwrap) = vec {
-		assert!(vec_unwrap.len() <= 2);
-	}
+	let server = MockServer::start();
+	server.push(MockResponse::empty(3, 99));
+	server.push(MockResponse::empty(2, 98));
+	let handle = create_handler(&server, [].to_vec(), [].to_vec(), Some(999), 2);
+	handle.get_sauce(FILE, None, None).unwrap();
+	handle.get_sauce(FILE, Some(5), None).unwrap();
+
+	let requests = server.requests();
+	assert_eq!(requests[0].query("numres"), Some("2"));
+	assert_eq!(requests[1].query("numres"), Some("5"));
 }
 
 /// Tests db bit masks
 #[test]
 fn test_db_bit_mask() {
-	let handle = create_handler([27].to_vec(), [].to_vec(), None, 999);
-	let vec = handle.get_sauce(FILE, None, None);
-	if let Ok(vec_unwrap) = vec {
-		for v in vec_unwrap {
-			assert!(v.index >= 27, "saw {}", v.index);
-		}
-	}
+	let server = MockServer::start();
+	server.push(MockResponse::empty(3, 99));
+	let handle = create_handler(&server, [27].to_vec(), [].to_vec(), None, 999);
+	handle.get_sauce(FILE, None, None).unwrap();
+
+	let request = &server.requests()[0];
+	assert_eq!(request.query("dbmask"), Some((1u32 << 26).to_string().as_str()));
+	assert_eq!(request.query("dbmaski"), None);
+	assert_eq!(request.query("db"), None);
 }
 
 /// Tests db bit mask for exclusion
 #[test]
 fn test_db_bit_mask_i() {
-	let handle = create_handler([].to_vec(), [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10].to_vec(), None, 999);
-	let vec = handle.get_sauce(FILE, None, None);
-	if let Ok(vec_unwrap) = vec {
-		for v in vec_unwrap {
-			assert!(v.index >= 11);
-		}
-	}
+	let server = MockServer::start();
+	server.push(MockResponse::empty(3, 99));
+	let handle = create_handler(&server, [].to_vec(), [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10].to_vec(), None, 999);
+	handle.get_sauce(FILE, None, None).unwrap();
+
+	let request = &server.requests()[0];
+	assert_eq!(request.query("dbmaski"), Some("2047"));
+	assert_eq!(request.query("dbmask"), None);
 }
 
 /// Tests min similarity and capping the number of results
 #[test]
 fn test_min_similarity_and_num_results() {
-	let handle = create_handler([].to_vec(), [].to_vec(), Some(999), 2);
-	let vec = handle.get_sauce(LOCAL_FILE, Some(5), Some(50_f64));
-	if let Ok(vec_unwrap) = vec {
-		let res = vec_unwrap;
-		assert!(res.len() <= 5);
-		for v in res {
-			assert!(v.similarity >= 49.0);
-		}
+	let server = MockServer::start();
+	server.push(MockResponse::fixture("pixiv_danbooru.json"));
+	let handle = create_handler(&server, [].to_vec(), [].to_vec(), Some(999), 2);
+	let res = handle.get_sauce(LOCAL_FILE, Some(5), Some(50_f64)).unwrap();
+	assert_eq!(res.len(), 2);
+	for v in res {
+		assert!(v.similarity >= 49.0);
 	}
+	assert_eq!(server.requests()[0].query("numres"), Some("5"));
 }
@@ -0,0 +1,163 @@
+mod common;
+
+use common::{MockResponse, MockServer};
+use rustnao::{Cassette, ErrType, HandlerBuilder};
+use std::path::PathBuf;
+
+const FILE: &str = "https://i.imgur.com/W42kkKS.jpg";
+const LOCAL_FILE: &str = "./tests/test.jpg";
+
+/// Returns a path in the temporary directory that is unique to the test
+fn temporary_path(name: &str) -> PathBuf {
+	let path = std::env::temp_dir().join(format!("rustnao-cassette-{}-{}.json", name, std::process::id()));
+	let _ = std::fs::remove_file(&path);
+	path
+}
+
+/// Records the responses of a URL and a local file search to a cassette, returning the URL of the API they were recorded from
+fn record(path: &PathBuf) -> String {
+	let server = MockServer::start();
+	server.push(MockResponse::fixture("pixiv_danbooru.json"));
+	server.push(MockResponse::fixture("anime.json"));
+	let handle = HandlerBuilder::default()
+		.api_key("secret_api_key")
+		.api_url(server.url().as_str())
+		.cassette(Cassette::record(path))
+		.build();
+	assert_eq!(handle.get_sauce(FILE, None, None).unwrap().len(), 3);
+	assert_eq!(handle.get_sauce(LOCAL_FILE, None, None).unwrap().len(), 1);
+	assert_eq!(server.requests().len(), 2);
+	server.url()
+}
+
+/// Tests that responses are recorded without the API key
+#[test]
+fn test_record() {
+	let path = temporary_path("record");
+	record(&path);
+
+	let contents = std::fs::read_to_string(&path).unwrap();
+	assert!(!contents.contains("secret_api_key"), "{}", contents);
+	let json: serde_json::Value = serde_json::from_str(contents.as_str()).unwrap();
+	let interactions = json["interactions"].as_array().unwrap();
+	assert_eq!(interactions.len(), 2);
+	assert_eq!(interactions[0]["method"], "POST");
+	assert_eq!(interactions[0]["status"], 200);
+	assert!(interactions[0]["url"].as_str().unwrap().contains("api_key=REDACTED"));
+	assert!(interactions[1]["body"].as_str().unwrap().contains("anidb"));
+	std::fs::remove_file(&path).unwrap();
+}
+
+/// Tests that the user ID and the signatures of thumbnails are scrubbed from the recorded responses
+#[test]
+fn test_record_scrubs_responses() {
+	let path = temporary_path("scrub");
+	let server = MockServer::start();
+	server.push(MockResponse::json(
+		r#"{
+			"header": {"user_id": "123456", "status": 0, "short_limit": "4", "long_limit": "100", "short_remaining": 3, "long_remaining": 99},
+			"results": [{
+				"header": {
+					"similarity": "90.00",
+					"thumbnail": "https://img1.saucenao.com/res/pixiv/1/1_p0.jpg?auth=0f1e2d3c&exp=1571443200",
+					"index_id": 5,
+					"index_name": "Index #5: Pixiv Images - 1_p0.jpg"
+				},
+				"data": {"ext_urls": ["https://www.pixiv.net/member_illust.php?mode=medium&illust_id=1"]}
+			}]
+		}"#,
+	));
+	let handle = HandlerBuilder::default()
+		.api_url(server.url().as_str())
+		.cassette(Cassette::record(&path))
+		.build();
+	handle.get_sauce(FILE, None, None).unwrap();
+
+	let contents = std::fs::read_to_string(&path).unwrap();
+	assert!(!contents.contains("123456"), "{}", contents);
+	assert!(!contents.contains("0f1e2d3c"), "{}", contents);
+
+	let handle = HandlerBuilder::default()
+		.api_url(server.url().as_str())
+		.cassette(Cassette::replay(&path).unwrap())
+		.build();
+	let result = handle.get_sauce(FILE, None, None).unwrap();
+	assert_eq!(
+		result[0].thumbnail,
+		"https://img1.saucenao.com/res/pixiv/1/1_p0.jpg?auth=REDACTED&exp=1571443200"
+	);
+	std::fs::remove_file(&path).unwrap();
+}
+
+/// Tests that recorded responses are replayed without sending requests, whatever the API key
+#[test]
+fn test_replay() {
+	let path = temporary_path("replay");
+	let api_url = record(&path);
+
+	// The mock server has stopped, so any request that is sent fails
+	let handle = HandlerBuilder::default()
+		.api_key("another_api_key")
+		.api_url(api_url.as_str())
+		.cassette(Cassette::replay(&path).unwrap())
+		.build();
+	let result = handle.get_sauce(FILE, None, Some(90.0)).unwrap();
+	assert_eq!(result.len(), 2);
+	assert_eq!(result[0].site, "Pixiv");
+	assert_eq!(handle.get_current_long_limit(), 98);
+
+	let result = handle.get_sauce(LOCAL_FILE, None, None).unwrap();
+	assert!(result[0].ext_urls[0].contains("anidb"));
+	assert_eq!(handle.get_current_long_limit(), 95);
+
+	// Once every matching response has been replayed, the last one is repeated
+	assert_eq!(handle.get_sauce(LOCAL_FILE, None, None).unwrap().len(), 1);
+	std::fs::remove_file(&path).unwrap();
+}
+
+/// Tests that a request without a recorded response fails
+#[test]
+fn test_replay_unrecorded() {
+	let path = temporary_path("unrecorded");
+	let api_url = record(&path);
+
+	let handle = HandlerBuilder::default()
+		.api_url(api_url.as_str())
+		.cassette(Cassette::replay(&path).unwrap())
+		.build();
+	let err = handle.get_sauce(FILE, Some(5), None).unwrap_err();
+	match err.kind() {
+		ErrType::InvalidRequest(message) => assert!(message.contains("no response recorded"), "{}", message),
+		other => panic!("unexpected error {:?}", other),
+	}
+	std::fs::remove_file(&path).unwrap();
+}
+
+/// Tests that recorded error responses are replayed as errors
+#[test]
+fn test_replay_error() {
+	let path = temporary_path("error");
+	let server = MockServer::start();
+	server.push(MockResponse::short_limit_exceeded());
+	let recording = HandlerBuilder::default()
+		.api_url(server.url().as_str())
+		.cassette(Cassette::record(&path))
+		.build();
+	assert!(recording.get_sauce(FILE, None, None).is_err());
+
+	let handle = HandlerBuilder::default()
+		.api_url(server.url().as_str())
+		.cassette(Cassette::replay(&path).unwrap())
+		.build();
+	let err = handle.get_sauce(FILE, None, None).unwrap_err();
+	assert!(matches!(err.kind(), ErrType::ShortLimitExceeded { .. }), "{:?}", err);
+	assert_eq!(server.requests().len(), 1);
+	std::fs::remove_file(&path).unwrap();
+}
+
+/// Tests that a cassette that cannot be read is reported
+#[test]
+fn test_replay_missing() {
+	let err = Cassette::replay(temporary_path("missing")).unwrap_err();
+	assert!(matches!(err.kind(), ErrType::InvalidFile(_)), "{:?}", err);
+}
@@ -0,0 +1,112 @@
+mod common;
+
+use common::{MockResponse, MockServer};
+use rustnao::{normalize_url, HandlerBuilder, Sauce, SauceBuilder, SauceGroup, SourceRef, ToGroups};
+
+/// Creates a result from the given site with the given URLs and similarity
+fn sauce(site: &str, ext_urls: &[&str], similarity: f32) -> Sauce {
+	SauceBuilder::default()
+		.site(site)
+		.ext_urls(ext_urls.iter().map(|url| url.to_string()).collect())
+		.similarity(similarity)
+		.build()
+}
+
+/// Tests that different links to the same page normalise to the same URL, and that other links are left alone
+#[test]
+fn test_normalize_url() {
+	let cases = [
+		(
+			"http://www.pixiv.net/member_illust.php?mode=medium&illust_id=61477678",
+			"https://www.pixiv.net/artworks/61477678",
+		),
+		("https://www.pixiv.net/en/artworks/61477678", "https://www.pixiv.net/artworks/61477678"),
+		("https://danbooru.donmai.us/post/show/3441187", "https://danbooru.donmai.us/posts/3441187"),
+		(
+			"https://mobile.twitter.com/user/status/1160000000000000000",
+			"https://twitter.com/i/web/status/1160000000000000000",
+		),
+		("http://example.com/gallery/12/", "https://example.com/gallery/12"),
+		("https://www.example.com/", "https://example.com"),
+		(
+			"https://m.example.com/view?id=3&utm_source=feed&ref=home#comments",
+			"https://example.com/view?id=3",
+		),
+		("http://example.com:8080/a", "https://example.com:8080/a"),
+		("https://www.com/a", "https://www.com/a"),
+		(" not a url ", "not a url"),
+		("ftp://example.com/file/", "ftp://example.com/file/"),
+	];
+	for (url, normalized) in cases.iter() {
+		assert_eq!(normalize_url(url), *normalized, "{}", url);
+	}
+}
+
+/// Tests that the Pixiv result and the Danbooru post sourced from it in a SauceNAO response are grouped together, apart from the result without links
+#[test]
+fn test_groups_from_response() {
+	let server = MockServer::start();
+	server.push(MockResponse::fixture("pixiv_danbooru.json"));
+	let handle = HandlerBuilder::default().api_key("test_api_key").api_url(server.url().as_str()).build();
+	let result = handle.get_sauce("https://i.imgur.com/W42kkKS.jpg", None, None).unwrap();
+
+	let groups = result.to_groups();
+	assert_eq!(groups.len(), 2);
+	assert_eq!(groups[0].results.len(), 2);
+	assert_eq!(groups[1].results.len(), 1);
+	assert_eq!(
+		groups[0].urls,
+		vec![
+			"https://www.pixiv.net/artworks/61477678",
+			"https://danbooru.donmai.us/posts/2567218",
+			"https://yande.re/post/show/382340",
+		]
+	);
+	assert_eq!(
+		groups[0].source_refs(),
+		vec![SourceRef::Pixiv(61477678), SourceRef::Danbooru(2567218), SourceRef::YandeRe(382340)]
+	);
+	assert_eq!(groups[0].best().unwrap().site, groups[0].results[0].site);
+}
+
+/// Tests that groups are joined through shared links, keep the order of their first result, and report the best result
+#[test]
+fn test_groups() {
+	let results = vec![
+		sauce("Danbooru", &["https://danbooru.donmai.us/posts/1"], 70.0),
+		sauce("Other", &["https://example.com/a"], 60.0),
+		sauce("Gelbooru", &["https://gelbooru.com/index.php?page=post&s=view&id=2"], 90.0),
+		sauce(
+			"Danbooru",
+			&[
+				"https://danbooru.donmai.us/post/show/1",
+				"https://gelbooru.com/index.php?page=post&s=view&id=2&tags=x",
+			],
+			80.0,
+		),
+		sauce("Other", &["http://www.example.com/a/"], 50.0),
+		sauce("Empty", &[], 40.0),
+	];
+	let groups = results.to_groups();
+
+	let sites: Vec<Vec<&str>> = groups
+		.iter()
+		.map(|group| group.results.iter().map(|sauce| sauce.site.as_str()).collect())
+		.collect();
+	assert_eq!(
+		sites,
+		vec![vec!["Danbooru", "Gelbooru", "Danbooru"], vec!["Other", "Other"], vec!["Empty"]]
+	);
+	assert_eq!(groups[0].similarity(), 90.0);
+	assert_eq!(groups[0].best().unwrap().site, "Gelbooru");
+	assert_eq!(groups[1].urls, vec!["https://example.com/a"]);
+	assert!(groups[2].urls.is_empty());
+	assert!(Vec::<Sauce>::new().to_groups().is_empty());
+
+	let empty = SauceGroup {
+		results: Vec::new(),
+		urls: Vec::new(),
+	};
+	assert_eq!(empty.best(), None);
+	assert_eq!(empty.similarity(), 0.0);
+}
@@ -0,0 +1,274 @@
+//! A mock SauceNAO server for testing Handlers offline.  It serves scripted responses in order and records every request it receives.
+#![allow(dead_code)]
+
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// A scripted response of the mock server
+#[derive(Debug, Clone)]
+pub struct MockResponse {
+	status: u16,
+	body: String,
+	headers: Vec<(String, String)>,
+	delay: Duration,
+}
+
+impl MockResponse {
+	/// A successful response with the given JSON body
+	pub fn json(body: &str) -> MockResponse {
+		MockResponse::status(200, body)
+	}
+
+	/// A successful response with the contents of a file in ``tests/fixtures``
+	pub fn fixture(name: &str) -> MockResponse {
+		let body = std::fs::read_to_string(format!("./tests/fixtures/{}", name)).expect("missing fixture");
+		MockResponse::json(body.as_str())
+	}
+
+	/// A successful response without results, with the given searches left
+	pub fn empty(short_remaining: u32, long_remaining: u32) -> MockResponse {
+		MockResponse::json(
+			format!(
+				r#"{{"header": {{"status": 0, "account_type": "1", "short_limit": "4", "long_limit": "100", "short_remaining": {}, "long_remaining": {}}}, "results": []}}"#,
+				short_remaining, long_remaining
+			)
+			.as_str(),
+		)
+	}
+
+	/// A response with the given HTTP status and body
+	pub fn status(status: u16, body: &str) -> MockResponse {
+		MockResponse {
+			status,
+			body: body.to_string(),
+			headers: vec![("Content-Type".to_string(), "application/json".to_string())],
+			delay: Duration::from_secs(0),
+		}
+	}
+
+	/// An error response from SauceNAO with the given HTTP status, SauceNAO status and message
+	pub fn error(http_status: u16, status: i32, message: &str) -> MockResponse {
+		let body = serde_json::json!({"header": {"status": status, "message": message}});
+		MockResponse::status(http_status, body.to_string().as_str())
+	}
+
+	/// The response SauceNAO sends when the 30 second limit is exceeded
+	pub fn short_limit_exceeded() -> MockResponse {
+		MockResponse::error(
+			429,
+			-2,
+			"Search Rate Too High. Your IP has exceeded the basic account type's rate limit of 4 searches every 30 seconds.",
+		)
+	}
+
+	/// The response SauceNAO sends when the daily limit is exceeded
+	pub fn daily_limit_exceeded() -> MockResponse {
+		MockResponse::error(
+			429,
+			-2,
+			"Daily Search Limit Exceeded. Your IP has exceeded the basic account type's daily limit of 100 searches.",
+		)
+	}
+
+	/// The response SauceNAO sends when the API key is rejected
+	pub fn invalid_api_key() -> MockResponse {
+		MockResponse::error(403, -1, "Invalid API key. Please check your API key and try again.")
+	}
+
+	/// A redirect to the given location, which may be relative to the server
+	pub fn redirect(location: &str) -> MockResponse {
+		MockResponse::status(302, "").header("Location", location)
+	}
+
+	/// Sets a header of the response, replacing any header with the same name
+	pub fn header(mut self, name: &str, value: &str) -> MockResponse {
+		self.headers.retain(|(key, _)| !key.eq_ignore_ascii_case(name));
+		self.headers.push((name.to_string(), value.to_string()));
+		self
+	}
+
+	/// Delays the response by the given duration, after the request has been read
+	pub fn delay(mut self, delay: Duration) -> MockResponse {
+		self.delay = delay;
+		self
+	}
+}
+
+/// A request received by the mock server
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+	pub method: String,
+	pub path: String,
+	pub query: Vec<(String, String)>,
+	/// Headers, with lowercase names
+	pub headers: Vec<(String, String)>,
+	pub body: Vec<u8>,
+}
+
+impl RecordedRequest {
+	/// Returns the value of a query parameter
+	pub fn query(&self, name: &str) -> Option<&str> {
+		self.query.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str())
+	}
+
+	/// Returns the value of a header
+	pub fn header(&self, name: &str) -> Option<&str> {
+		let name = name.to_lowercase();
+		self.headers.iter().find(|(key, _)| *key == name).map(|(_, value)| value.as_str())
+	}
+}
+
+#[derive(Debug, Default)]
+struct State {
+	responses: VecDeque<MockResponse>,
+	requests: Vec<RecordedRequest>,
+}
+
+/// A mock SauceNAO server listening on a local port, which stops when dropped.
+///
+/// Responses are served in the order they were pushed.  Once they run out, a 500 response is sent, so that unexpected requests fail the test.
+#[derive(Debug)]
+pub struct MockServer {
+	address: SocketAddr,
+	state: Arc<Mutex<State>>,
+	stopped: Arc<AtomicBool>,
+}
+
+impl MockServer {
+	/// Starts a server on a free local port
+	pub fn start() -> MockServer {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let server = MockServer {
+			address: listener.local_addr().unwrap(),
+			state: Arc::new(Mutex::new(State::default())),
+			stopped: Arc::new(AtomicBool::new(false)),
+		};
+		let state = server.state.clone();
+		let stopped = server.stopped.clone();
+		thread::spawn(move || {
+			for stream in listener.incoming() {
+				if stopped.load(Ordering::SeqCst) {
+					break;
+				}
+				if let Ok(stream) = stream {
+					let state = state.clone();
+					thread::spawn(move || serve(stream, &state));
+				}
+			}
+		});
+		server
+	}
+
+	/// The URL of the search API of the server, to pass to ``HandlerBuilder::api_url``
+	pub fn url(&self) -> String {
+		format!("http://{}/search.php", self.address)
+	}
+
+	/// The root URL of the server, to pass as the URL of other search engines
+	pub fn base_url(&self) -> String {
+		format!("http://{}/", self.address)
+	}
+
+	/// Adds a response to serve
+	pub fn push(&self, response: MockResponse) -> &MockServer {
+		self.state.lock().unwrap().responses.push_back(response);
+		self
+	}
+
+	/// Returns the requests received so far
+	pub fn requests(&self) -> Vec<RecordedRequest> {
+		self.state.lock().unwrap().requests.clone()
+	}
+}
+
+impl Drop for MockServer {
+	fn drop(&mut self) {
+		self.stopped.store(true, Ordering::SeqCst);
+		// Wakes up the accepting thread so that it notices the server was stopped
+		let _ = TcpStream::connect(self.address);
+	}
+}
+
+/// Reads a request, records it and sends the next scripted response
+fn serve(stream: TcpStream, state: &Mutex<State>) {
+	let mut reader = BufReader::new(stream.try_clone().unwrap());
+	let mut writer = stream;
+	let request = match read_request(&mut reader, &mut writer) {
+		Some(request) => request,
+		None => return,
+	};
+	let response = {
+		let mut state = state.lock().unwrap();
+		state.requests.push(request);
+		state.responses.pop_front()
+	}
+	.unwrap_or_else(|| MockResponse::status(500, "no scripted response left"));
+
+	thread::sleep(response.delay);
+	let headers: String = response.headers.iter().map(|(name, value)| format!("{}: {}\r\n", name, value)).collect();
+	let _ = write!(
+		writer,
+		"HTTP/1.1 {} Mock\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+		response.status,
+		headers,
+		response.body.len(),
+		response.body
+	);
+	let _ = writer.flush();
+	let _ = writer.shutdown(Shutdown::Both);
+}
+
+fn read_request(reader: &mut BufReader<TcpStream>, writer: &mut TcpStream) -> Option<RecordedRequest> {
+	let mut request_line = String::new();
+	reader.read_line(&mut request_line).ok()?;
+	let mut parts = request_line.split_whitespace();
+	let method = parts.next()?.to_string();
+	let target = url::Url::parse(format!("http://localhost{}", parts.next()?).as_str()).ok()?;
+
+	let mut headers = Vec::new();
+	loop {
+		let mut line = String::new();
+		reader.read_line(&mut line).ok()?;
+		let line = line.trim_end();
+		if line.is_empty() {
+			break;
+		}
+		let (name, value) = line.split_at(line.find(':')?);
+		headers.push((name.to_lowercase(), value[1..].trim().to_string()));
+	}
+	let header = |name: &str| headers.iter().find(|(key, _)| key == name).map(|(_, value)| value.clone());
+
+	if header("expect").is_some_and(|value| value.eq_ignore_ascii_case("100-continue")) {
+		writer.write_all(b"HTTP/1.1 100 Continue\r\n\r\n").ok()?;
+	}
+	let mut body = Vec::new();
+	if let Some(length) = header("content-length") {
+		body.resize(length.parse().ok()?, 0);
+		reader.read_exact(&mut body).ok()?;
+	} else if header("transfer-encoding").is_some_and(|value| value.eq_ignore_ascii_case("chunked")) {
+		loop {
+			let mut size = String::new();
+			reader.read_line(&mut size).ok()?;
+			let size = usize::from_str_radix(size.trim(), 16).ok()?;
+			let mut chunk = vec![0; size + 2];
+			reader.read_exact(&mut chunk).ok()?;
+			if size == 0 {
+				break;
+			}
+			body.extend_from_slice(&chunk[..size]);
+		}
+	}
+
+	Some(RecordedRequest {
+		method,
+		path: target.path().to_string(),
+		query: target.query_pairs().map(|(key, value)| (key.into_owned(), value.into_owned())).collect(),
+		headers,
+		body,
+	})
+}
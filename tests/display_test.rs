@@ -0,0 +1,85 @@
+use rustnao::{Handler, Sauce, SauceBuilder, ToTable};
+use unicode_width::UnicodeWidthStr;
+
+/// Creates a list of results for testing purposes
+fn create_results() -> Vec<Sauce> {
+	[
+		SauceBuilder::default()
+			.ext_urls(["https://www.pixiv.net/member_illust.php?mode=medium&illust_id=61477678".to_string()].to_vec())
+			.title("A rather long title that will certainly not fit into a narrow terminal")
+			.site("Pixiv")
+			.index(Handler::PIXIV)
+			.similarity(92.5)
+			.additional_fields(serde_json::json!({ "member_id": 4754550, "member_name": "リン☆ユウ" }))
+			.build(),
+		SauceBuilder::default().site("Danbooru").index(Handler::DANBOORU).similarity(40.0).build(),
+	]
+	.to_vec()
+}
+
+/// Tests the single line display of a Sauce
+#[test]
+fn test_display() {
+	let results = create_results();
+	assert_eq!(
+		results[0].to_string(),
+		"[92.50%] Pixiv: A rather long title that will certainly not fit into a narrow terminal by リン☆ユウ \
+		 <https://www.pixiv.net/member_illust.php?mode=medium&illust_id=61477678>"
+	);
+	assert_eq!(results[1].to_string(), "[40.00%] Danbooru: (untitled)");
+}
+
+/// Tests that the debug output no longer labels the additional fields as an author
+#[test]
+fn test_debug_label() {
+	let debug = format!("{:?}", create_results()[0]);
+	assert!(debug.contains("additional_fields: "));
+	assert!(!debug.contains("author_id"));
+}
+
+/// Tests that the table is truncated to the given width
+#[test]
+fn test_table_width() {
+	let table = create_results().to_table_with_width(70);
+	let lines: Vec<&str> = table.lines().collect();
+	assert_eq!(lines.len(), 4);
+	assert!(lines[0].starts_with("Similarity | Site"));
+	for line in lines {
+		assert!(line.chars().count() <= 70, "{} is too long", line);
+	}
+	assert!(table.contains('…'));
+	assert!(table.contains("92.50%"));
+}
+
+/// Tests that rows with double width CJK text are fitted by their display width rather than their number of characters
+#[test]
+fn test_table_cjk_width() {
+	for &width in &[40, 51, 70] {
+		let table = create_results().to_table_with_width(width);
+		for line in table.lines() {
+			assert!(line.width() <= width, "{} is wider than {}", line, width);
+		}
+	}
+
+	// The URL column starts at the same terminal column on the header and the row with the CJK author
+	let table = create_results().to_table_with_width(500);
+	let lines: Vec<&str> = table.lines().collect();
+	assert!(lines[2].contains("リン☆ユウ"));
+	let url_column = |line: &str| line[..line.match_indices(" | ").nth(3).unwrap().0].width();
+	assert_eq!(url_column(lines[0]), url_column(lines[2]));
+}
+
+/// Tests that a table rendered without a width fits a standard terminal
+#[test]
+fn test_table_default_width() {
+	let results = create_results();
+	assert_eq!(results.to_table(), results.to_table_with_width(80));
+}
+
+/// Tests that a wide table is not truncated
+#[test]
+fn test_table_wide() {
+	let table = create_results().to_table_with_width(500);
+	assert!(!table.contains('…'));
+	assert!(table.contains("https://www.pixiv.net/member_illust.php?mode=medium&illust_id=61477678"));
+}
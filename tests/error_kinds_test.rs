@@ -0,0 +1,146 @@
+mod common;
+
+use common::{MockResponse, MockServer};
+use rustnao::{ErrType, HandlerBuilder, Result, Sauce};
+
+/// Returns the results of a search that the mock server answers with the given response
+fn search(response: serde_json::Value) -> Result<Vec<Sauce>> {
+	let server = MockServer::start();
+	server.push(MockResponse::json(response.to_string().as_str()));
+	let handle = HandlerBuilder::default().api_url(server.url().as_str()).build();
+	handle.get_sauce("https://i.imgur.com/W42kkKS.jpg", None, None)
+}
+
+/// Returns the kind of error produced by a response with the given status and message
+fn kind_of(status: i32, message: &str, long_remaining: u32) -> ErrType {
+	let response = serde_json::json!({
+		"header": { "status": status, "message": message, "long_remaining": long_remaining }
+	});
+	search(response).unwrap_err().kind().clone()
+}
+
+/// Tests invalid API keys
+#[test]
+fn test_invalid_api_key() {
+	let kind = kind_of(-1, "Invalid or wrong API key", 100);
+	assert_eq!(kind, ErrType::InvalidApiKey("Invalid or wrong API key".to_string()));
+	assert!(!kind.is_retryable());
+}
+
+/// Tests exceeding the short search limit
+#[test]
+fn test_short_limit() {
+	let kind = kind_of(
+		-2,
+		"Search Rate Too High. Your IP has exceeded the basic account type's rate limit of 4 searches every 30 seconds.",
+		50,
+	);
+	match kind {
+		ErrType::ShortLimitExceeded { retry_after, .. } => assert_eq!(retry_after.as_secs(), 30),
+		other => panic!("unexpected error {:?}", other),
+	}
+	assert!(kind_of(-2, "", 50).is_retryable());
+}
+
+/// Tests exceeding the daily search limit, with and without a message saying so
+#[test]
+fn test_daily_limit() {
+	for kind in [
+		kind_of(
+			-2,
+			"Daily Search Limit Exceeded. Your IP has exceeded the basic account type's rate limit of 100 searches every 24 hours.",
+			0,
+		),
+		kind_of(-2, "Search Rate Too High.", 0),
+	]
+	.iter()
+	{
+		match kind {
+			ErrType::DailyLimitExceeded { reset_at, .. } => assert!(*reset_at > std::time::SystemTime::now()),
+			other => panic!("unexpected error {:?}", other),
+		}
+		assert!(!kind.is_retryable());
+	}
+}
+
+/// Tests files that are too large
+#[test]
+fn test_file_too_large() {
+	assert_eq!(
+		kind_of(-3, "Specified file too large", 100),
+		ErrType::FileTooLarge("Specified file too large".to_string())
+	);
+}
+
+/// Tests images that cannot be used
+#[test]
+fn test_unsupported_image() {
+	for message in [
+		"Image dimensions too small...",
+		"Supplied URL is not usable...",
+		"The file does not appear to be a valid image.",
+	]
+	.iter()
+	{
+		assert_eq!(kind_of(-4, message, 100), ErrType::UnsupportedImage(message.to_string()));
+	}
+}
+
+/// Tests that unknown messages are not classified by the words they contain
+#[test]
+fn test_unknown_message() {
+	for message in [
+		"Unknown image format in database",
+		"Unsupported file type for this index",
+		"Your api key quota is low",
+	]
+	.iter()
+	{
+		assert_eq!(
+			kind_of(-4, message, 100),
+			ErrType::InvalidCode {
+				code: -4,
+				message: message.to_string()
+			}
+		);
+	}
+}
+
+/// Tests server side errors, which are retryable
+#[test]
+fn test_server_error() {
+	let kind = kind_of(1, "Database problem", 100);
+	assert_eq!(
+		kind,
+		ErrType::ServerError {
+			code: 1,
+			message: "Database problem".to_string()
+		}
+	);
+	assert!(kind.is_retryable());
+}
+
+/// Tests that unknown errors keep the code and message
+#[test]
+fn test_unknown_error() {
+	assert_eq!(
+		kind_of(-9, "Something new", 100),
+		ErrType::InvalidCode {
+			code: -9,
+			message: "Something new".to_string()
+		}
+	);
+}
+
+/// Tests that a positive status with results still returns the results
+#[test]
+fn test_server_error_with_results() {
+	let response = serde_json::json!({
+		"header": { "status": 1, "message": "Some indexes failed" },
+		"results": [{
+			"header": { "similarity": "90.0", "thumbnail": "", "index_id": 9, "index_name": "Index #9: Danbooru - a.jpg" },
+			"data": { "ext_urls": [] }
+		}]
+	});
+	assert_eq!(search(response).unwrap().len(), 1);
+}
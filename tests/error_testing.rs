@@ -1,96 +1,184 @@
 extern crate rustnao;
 
-use rustnao::{Handler, HandlerBuilder};
+mod common;
+
+use common::{MockResponse, MockServer};
+use rustnao::{ErrType, Handler, HandlerBuilder};
+use std::time::Duration;
 
 const FILE: &str = "https://i.imgur.com/W42kkKS.jpg";
 const INVALID_URL: &str = "https://j.jmgur.com";
 const INVALID_FILE: &str = "./fake_file.png";
+const UNUSABLE_URL: &str = "Problem with remote server or url is not usable.";
 
-/// Creates a handler for testing purposes
-fn create_handler(dbmask: Vec<u32>, dbmaski: Vec<u32>, db_option: Option<u32>, numres: u32) -> Handler {
-	let mut api_key = "".to_string();
-
-	let data = std::fs::read_to_string("config.json");
-	if data.is_ok() {
-		if let Ok(val) = data {
-			let json: serde_json::Value = serde_json::from_str(val.as_str()).expect("JSON not well formatted.");
-			let json_api_key = json["api_key"].as_str();
-
-			if let Some(key) = json_api_key {
-				api_key = key.to_string();
-			}
-		}
-	}
-
-	match db_option {
-		Some(db) => HandlerBuilder::default()
-			.db_mask(dbmask)
-			.db_mask_i(dbmaski)
-			.db(db)
-			.num_results(numres)
-			.api_key(api_key.as_str())
-			.build(),
-		None => HandlerBuilder::default()
-			.db_mask(dbmask)
-			.db_mask_i(dbmaski)
-			.num_results(numres)
-			.api_key(api_key.as_str())
-			.build(),
+/// Creates a handler for testing purposes, which searches with the mock server
+fn create_handler(server: &MockServer, dbmask: Vec<u32>, dbmaski: Vec<u32>, db_option: Option<u32>, numres: u32) -> Handler {
+	let mut builder = HandlerBuilder::default();
+	builder
+		.db_mask(dbmask)
+		.db_mask_i(dbmaski)
+		.num_results(numres)
+		.api_key("test_api_key")
+		.api_url(server.url().as_str());
+	if let Some(db) = db_option {
+		builder.db(db);
 	}
+	builder.build()
 }
 
 /// Tests an invalid URL
 #[test]
 fn test_invalid_url() {
-	let handler = create_handler([].to_vec(), [].to_vec(), Some(999), 999);
-	let result = handler.get_sauce(INVALID_URL, None, None);
-	assert!(result.is_err());
+	let server = MockServer::start();
+	server.push(MockResponse::error(200, -3, UNUSABLE_URL));
+	let handler = create_handler(&server, [].to_vec(), [].to_vec(), Some(999), 999);
+	let err = handler.get_sauce(INVALID_URL, None, None).unwrap_err();
+	assert_eq!(err.kind(), &ErrType::UnsupportedImage(UNUSABLE_URL.to_string()));
+	assert_eq!(server.requests()[0].query("url"), Some(INVALID_URL));
 }
 
 /// Tests an invalid URL
 #[test]
 fn test_invalid_url_json() {
-	let handler = create_handler([].to_vec(), [].to_vec(), None, 999);
+	let server = MockServer::start();
+	server.push(MockResponse::error(200, -3, UNUSABLE_URL));
+	let handler = create_handler(&server, [].to_vec(), [].to_vec(), None, 999);
 	let result = handler.get_sauce_as_json(INVALID_URL, None, None);
-	assert!(result.is_err());
+	assert!(matches!(result.unwrap_err().kind(), ErrType::UnsupportedImage(_)));
+}
+
+/// Tests that a response that is not JSON is classified by its HTTP status
+#[test]
+fn test_http_status() {
+	let server = MockServer::start();
+	server.push(MockResponse::status(503, "Service Unavailable"));
+	server.push(MockResponse::status(200, "<html>maintenance</html>"));
+	let handler = create_handler(&server, [].to_vec(), [].to_vec(), None, 999);
+	let err = handler.get_sauce(FILE, None, None).unwrap_err();
+	assert_eq!(
+		err.kind(),
+		&ErrType::ServerError {
+			code: 503,
+			message: "Service Unavailable".to_string()
+		}
+	);
+	let err = handler.get_sauce(FILE, None, None).unwrap_err();
+	assert!(matches!(err.kind(), ErrType::InvalidSerde(_)), "{:?}", err);
+}
+
+/// Tests that a server error without results is a retryable error, and that a server error with results still returns them, even with a 5xx status
+#[test]
+fn test_server_error_status() {
+	let server = MockServer::start();
+	server.push(MockResponse::error(200, 1, "Some indexes are offline."));
+	let handler = create_handler(&server, [].to_vec(), [].to_vec(), None, 999);
+	let err = handler.get_sauce(FILE, None, None).unwrap_err();
+	assert!(matches!(err.kind(), ErrType::ServerError { code: 1, .. }), "{:?}", err);
+	assert!(err.is_retryable());
+
+	let body = serde_json::json!({
+		"header": { "status": 1, "message": "Some indexes are offline.", "short_remaining": 3, "long_remaining": 99 },
+		"results": [{
+			"header": { "similarity": "90.0", "thumbnail": "", "index_id": 9, "index_name": "Index #9: Danbooru - a.jpg" },
+			"data": { "ext_urls": ["https://danbooru.donmai.us/posts/1"] }
+		}]
+	});
+	server.push(MockResponse::status(503, body.to_string().as_str()));
+	let result = handler.get_sauce(FILE, None, None).unwrap();
+	assert_eq!(result.len(), 1);
+	assert_eq!(result[0].site, "Danbooru");
+	assert_eq!(handler.get_current_long_limit(), 99);
+}
+
+/// Tests that a slow response fails with a timeout error
+#[test]
+fn test_slow_response() {
+	let server = MockServer::start();
+	server.push(MockResponse::empty(3, 99).delay(Duration::from_secs(2)));
+	let handler = create_handler(&server, [].to_vec(), [].to_vec(), None, 999);
+	let err = handler.get_sauce_with_timeout(FILE, None, None, Duration::from_millis(200)).unwrap_err();
+	assert!(matches!(err.kind(), ErrType::Timeout(_)), "{:?}", err);
+	assert_eq!(handler.get_current_long_limit(), 200);
 }
 
 /// Tests an invalid file
 #[test]
 fn test_invalid_file() {
-	let handler = create_handler([].to_vec(), [].to_vec(), Some(999), 999);
+	let server = MockServer::start();
+	let handler = create_handler(&server, [].to_vec(), [].to_vec(), Some(999), 999);
 	let result = handler.get_sauce(INVALID_FILE, None, None);
-	assert!(result.is_err());
+	assert!(matches!(result.unwrap_err().kind(), ErrType::InvalidFile(_)));
+	assert!(server.requests().is_empty());
 }
 
 /// Tests an invalid file
 #[test]
 fn test_invalid_file_json() {
-	let handler = create_handler([].to_vec(), [].to_vec(), Some(999), 999);
-	let result = handler.get_sauce(INVALID_FILE, None, None);
-	assert!(result.is_err());
+	let server = MockServer::start();
+	let handler = create_handler(&server, [].to_vec(), [].to_vec(), Some(999), 999);
+	let result = handler.get_sauce_as_json(INVALID_FILE, None, None);
+	assert!(matches!(result.unwrap_err().kind(), ErrType::InvalidFile(_)));
 }
 
 /// Tests an invalid number of results
 #[test]
 fn test_invalid_num_results() {
-	let handle = create_handler([].to_vec(), [].to_vec(), Some(999), 2);
+	let server = MockServer::start();
+	let handle = create_handler(&server, [].to_vec(), [].to_vec(), Some(999), 2);
 	let vec = handle.get_sauce(FILE, Some(1000), None);
-	assert!(vec.is_err());
+	assert!(matches!(vec.unwrap_err().kind(), ErrType::InvalidParameters(_)));
+	assert!(server.requests().is_empty());
 }
 
 /// Tests an invalid minimum similarity option (upper)
 #[test]
 fn test_invalid_min_similarity_upper() {
-	let handle = create_handler([].to_vec(), [].to_vec(), Some(999), 2);
+	let server = MockServer::start();
+	let handle = create_handler(&server, [].to_vec(), [].to_vec(), Some(999), 2);
 	let vec_two = handle.get_sauce(FILE, None, Some(100.1));
-	assert!(vec_two.is_err());
+	assert!(matches!(vec_two.unwrap_err().kind(), ErrType::InvalidParameters(_)));
+	assert!(server.requests().is_empty());
 }
 
 /// Tests an invalid minimum similarity option (lower)
 #[test]
 fn test_invalid_min_similarity_lower() {
-	let handle = create_handler([].to_vec(), [].to_vec(), Some(999), 2);
+	let server = MockServer::start();
+	let handle = create_handler(&server, [].to_vec(), [].to_vec(), Some(999), 2);
 	let vec_two = handle.get_sauce(FILE, None, Some(-0.1));
-	assert!(vec_two.is_err());
+	assert!(matches!(vec_two.unwrap_err().kind(), ErrType::InvalidParameters(_)));
+	assert!(server.requests().is_empty());
+}
+
+/// Tests that the underlying error is kept as the source
+#[test]
+fn test_error_source() {
+	let server = MockServer::start();
+	let handler = create_handler(&server, [].to_vec(), [].to_vec(), Some(999), 999);
+	let err = handler.get_sauce(INVALID_FILE, None, None).unwrap_err();
+	let source = std::error::Error::source(&err).expect("no source");
+	assert!(source.downcast_ref::<std::io::Error>().is_some());
+
+	server.push(MockResponse::json("not json"));
+	let err = handler.get_sauce(FILE, None, None).unwrap_err();
+	let source = std::error::Error::source(&err).expect("no source");
+	assert!(source.downcast_ref::<serde_json::Error>().is_some());
+}
+
+/// Tests that errors convert into boxed standard errors with ?
+#[test]
+fn test_error_boxed() {
+	fn search(handler: &Handler) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+		handler.get_sauce(INVALID_FILE, None, None)?;
+		Ok(())
+	}
+
+	let server = MockServer::start();
+	let handler = create_handler(&server, [].to_vec(), [].to_vec(), Some(999), 999);
+	let err = search(&handler).unwrap_err();
+	let err = err.downcast::<rustnao::Error>().expect("not a rustnao error");
+	match err.kind() {
+		rustnao::ErrType::InvalidFile(_) => (),
+		other => panic!("unexpected error {:?}", other),
+	}
 }
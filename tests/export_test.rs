@@ -0,0 +1,124 @@
+use rustnao::{Export, ExportFormat, Handler, Sauce, SauceBuilder};
+
+/// Creates a list of results for testing purposes
+fn create_results() -> Vec<Sauce> {
+	[
+		SauceBuilder::default()
+			.ext_urls(["https://www.pixiv.net/member_illust.php?mode=medium&illust_id=61477678".to_string()].to_vec())
+			.title("A title, with \"quotes\" | and a pipe")
+			.site("Pixiv")
+			.index(Handler::PIXIV)
+			.similarity(92.5)
+			.thumbnail("https://img1.saucenao.com/res/pixiv/6147/61477678_s.jpg?auth=a&exp=b")
+			.additional_fields(serde_json::json!({ "member_id": 4754550 }))
+			.build(),
+		SauceBuilder::default().site("<script>").index(Handler::DANBOORU).similarity(40.0).build(),
+	]
+	.to_vec()
+}
+
+/// Tests CSV quoting and the header row
+#[test]
+fn test_csv() {
+	let csv = create_results().to_csv().unwrap();
+	let lines: Vec<&str> = csv.split("\r\n").collect();
+	assert_eq!(lines[0], "similarity,site,index,index_id,title,ext_urls,thumbnail,additional_fields");
+	assert!(lines[1].contains("\"A title, with \"\"quotes\"\" | and a pipe\""));
+	assert!(lines[1].contains("\"{\"\"member_id\"\":4754550}\""));
+	assert_eq!(lines[2], "40,<script>,9,9,,,,");
+	assert_eq!(lines.len(), 4);
+}
+
+/// Tests that NDJSON has one reloadable Sauce per line
+#[test]
+fn test_ndjson() {
+	let results = create_results();
+	let ndjson = results.to_ndjson().unwrap();
+	let reloaded: Vec<Sauce> = ndjson.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+	assert_eq!(results, reloaded);
+}
+
+/// Tests that Markdown cells are escaped
+#[test]
+fn test_markdown() {
+	let markdown = create_results().export(ExportFormat::Markdown).unwrap();
+	assert_eq!(markdown.lines().count(), 4);
+	assert!(markdown.contains("\\| and a pipe"));
+	assert!(markdown.contains("[1](https://www.pixiv.net/member_illust.php?mode=medium&illust_id=61477678)"));
+	assert!(markdown.contains("| 92.50% |"));
+}
+
+/// Tests that CSV fields a spreadsheet would run as a formula are prefixed with a quote
+#[test]
+fn test_csv_formulas() {
+	let results = vec![SauceBuilder::default()
+		.title("=HYPERLINK(\"https://example.com\")")
+		.site("@SUM(A1)")
+		.thumbnail("-1+1")
+		.build()];
+	let csv = results.to_csv().unwrap();
+	let row = csv.split("\r\n").nth(1).unwrap();
+	assert!(row.contains(",'@SUM(A1),"), "{}", row);
+	assert!(row.contains(",\"'=HYPERLINK(\"\"https://example.com\"\")\","), "{}", row);
+	assert!(row.contains(",'-1+1,"), "{}", row);
+	assert!(row.starts_with("0,"), "{}", row);
+
+	let results = vec![SauceBuilder::default().title("\tcmd").site("+1").build()];
+	let row = results.to_csv().unwrap().split("\r\n").nth(1).unwrap().to_string();
+	assert!(row.contains(",'+1,"), "{}", row);
+	assert!(row.contains(",'\tcmd,"), "{}", row);
+}
+
+/// Tests that Markdown and HTML in titles are escaped, so that they cannot add images, links or formatting
+#[test]
+fn test_markdown_injection() {
+	let results = vec![SauceBuilder::default()
+		.title("<img src=x onerror=alert(1)> [link](https://example.com) *bold* _em_ `code` & more")
+		.site("Other")
+		.build()];
+	let markdown = results.to_markdown().unwrap();
+	assert!(!markdown.contains("<img"), "{}", markdown);
+	assert!(markdown.contains("&lt;img src=x onerror=alert(1)&gt; \\[link\\](https://example.com) \\*bold\\* \\_em\\_ \\`code\\` &amp; more"));
+}
+
+/// Tests that the HTML report escapes its content
+#[test]
+fn test_html() {
+	let html = create_results().to_html().unwrap();
+	assert!(html.starts_with("<!DOCTYPE html>"));
+	assert!(html.contains("&lt;script&gt;"));
+	assert!(!html.contains("<script>"));
+	assert!(html.contains("<img src=\"https://img1.saucenao.com/res/pixiv/6147/61477678_s.jpg?auth=a&amp;exp=b\""));
+}
+
+/// Tests an empty export
+#[test]
+fn test_empty() {
+	let results: Vec<Sauce> = Vec::new();
+	assert_eq!(results.to_ndjson().unwrap(), "");
+	assert_eq!(results.to_csv().unwrap().lines().count(), 1);
+}
+
+/// Tests that only HTTP and HTTPS URLs are exported as links, and that parentheses do not end a Markdown link early
+#[test]
+fn test_unsafe_links() {
+	let results = vec![SauceBuilder::default()
+		.ext_urls(vec![
+			"javascript:alert(1)".to_string(),
+			"https://en.wikipedia.org/wiki/Link_(disambiguation)".to_string(),
+		])
+		.site("Other")
+		.thumbnail("JavaScript:alert(2)")
+		.build()];
+
+	let html = results.to_html().unwrap();
+	assert!(!html.contains("href=\"javascript:"));
+	assert!(!html.contains("src=\"JavaScript:"));
+	assert!(html.contains("<td>javascript:alert(1)<br><a href=\"https://en.wikipedia.org/wiki/Link_(disambiguation)\">"));
+	assert!(html.contains("<td>JavaScript:alert(2)</td>"));
+
+	let markdown = results.to_markdown().unwrap();
+	assert!(!markdown.contains("](javascript:"));
+	assert!(!markdown.contains("](JavaScript:"));
+	assert!(markdown.contains("| javascript:alert(1) [2](https://en.wikipedia.org/wiki/Link_%28disambiguation%29) | JavaScript:alert(2) |"));
+}
@@ -0,0 +1,137 @@
+mod common;
+
+use common::{MockResponse, MockServer};
+use rustnao::{ErrType, FakeSearch, FallbackChain, FallbackStep, Handler, HandlerBuilder, SauceBuilder};
+
+const FILE: &str = "https://i.imgur.com/W42kkKS.jpg";
+
+/// Returns a Handler searching Pixiv on the mock server
+fn handler(server: &MockServer) -> Handler {
+	HandlerBuilder::default()
+		.api_url(server.url().as_str())
+		.db(Handler::PIXIV)
+		.db_mask_i(vec![Handler::DANBOORU])
+		.build()
+}
+
+/// Returns a fake engine that finds a result on Twitter
+fn twitter() -> FakeSearch {
+	let mut fake = FakeSearch::new();
+	fake.results(FILE, vec![SauceBuilder::default().site("Twitter").similarity(70.0).build()]);
+	fake
+}
+
+/// Tests that the chain stops at the initial search if it finds something
+#[test]
+fn test_initial() {
+	let server = MockServer::start();
+	server.push(MockResponse::fixture("pixiv_danbooru.json"));
+	let mut chain = FallbackChain::new(handler(&server));
+	chain.all_databases().engine("twitter", twitter());
+
+	let result = chain.get_sauce(FILE, None, Some(90.0)).unwrap();
+	assert_eq!(result.step, Some(FallbackStep::Initial));
+	assert_eq!(result.results.len(), 2);
+	assert!(result.errors.is_empty());
+	let requests = server.requests();
+	assert_eq!(requests.len(), 1);
+	assert_eq!(requests[0].query("db"), Some("5"));
+}
+
+/// Tests that every database is searched when the initial search finds nothing, and that the masks are left out
+#[test]
+fn test_all_databases() {
+	let server = MockServer::start();
+	server.push(MockResponse::empty(3, 99));
+	server.push(MockResponse::fixture("pixiv_danbooru.json"));
+	let mut chain = FallbackChain::new(handler(&server));
+	chain.all_databases();
+
+	let result = chain.get_sauce(FILE, None, Some(90.0)).unwrap();
+	assert_eq!(result.step, Some(FallbackStep::AllDatabases));
+	assert_eq!(result.results.len(), 2);
+	let requests = server.requests();
+	assert_eq!(requests.len(), 2);
+	assert_eq!(requests[0].query("dbmaski"), Some("512"));
+	assert_eq!(requests[1].query("db"), Some("999"));
+	assert_eq!(requests[1].query("dbmaski"), None);
+	assert_eq!(chain.handler().get_current_long_limit(), 98);
+}
+
+/// Tests that lowering the minimum similarity filters the results again without searching again
+#[test]
+fn test_min_similarity() {
+	let server = MockServer::start();
+	server.push(MockResponse::fixture("pixiv_danbooru.json"));
+	let mut chain = FallbackChain::new(handler(&server));
+	chain.min_similarity(60).min_similarity(40.12);
+
+	let result = chain.get_sauce(FILE, Some(5), Some(95.0)).unwrap();
+	assert_eq!(result.step, Some(FallbackStep::MinSimilarity(60.0)));
+	assert_eq!(result.results.len(), 2);
+	assert_eq!(server.requests().len(), 1);
+
+	server.push(MockResponse::fixture("pixiv_danbooru.json"));
+	let mut chain = FallbackChain::new(handler(&server));
+	chain.min_similarity(95.0).min_similarity(40.12);
+	let result = chain.get_sauce(FILE, None, Some(99.0)).unwrap();
+	assert_eq!(result.step, Some(FallbackStep::MinSimilarity(40.12)));
+	assert_eq!(result.results.len(), 3);
+}
+
+/// Tests that another engine takes over when SauceNAO fails, and that the failure is reported
+#[test]
+fn test_engine() {
+	let server = MockServer::start();
+	server.push(MockResponse::short_limit_exceeded());
+	let mut chain = FallbackChain::new(handler(&server));
+	chain.min_similarity(10).engine("twitter", twitter());
+
+	let result = chain.get_sauce(FILE, None, Some(50.0)).unwrap();
+	assert_eq!(result.step, Some(FallbackStep::Engine("twitter".to_string())));
+	assert_eq!(result.results[0].site, "Twitter");
+	assert_eq!(result.errors.len(), 1);
+	assert_eq!(result.errors[0].0, FallbackStep::Initial);
+	assert!(matches!(result.errors[0].1.kind(), ErrType::ShortLimitExceeded { .. }));
+	assert_eq!(server.requests().len(), 1);
+}
+
+/// Tests the outcome when no step finds anything, and when every step fails
+#[test]
+fn test_nothing_found() {
+	let server = MockServer::start();
+	server.push(MockResponse::empty(3, 99));
+	let mut chain = FallbackChain::new(handler(&server));
+	chain.engine("empty", FakeSearch::new());
+	let result = chain.get_sauce(FILE, None, None).unwrap();
+	assert_eq!(result.step, None);
+	assert!(result.results.is_empty());
+	assert!(result.errors.is_empty());
+
+	server.push(MockResponse::invalid_api_key());
+	let mut failing = FakeSearch::new();
+	failing.error(FILE, ErrType::Timeout("timed out".to_string()));
+	let mut chain = FallbackChain::new(handler(&server));
+	chain.all_databases().engine("failing", failing);
+	let err = chain.get_sauce(FILE, None, None).unwrap_err();
+	assert!(matches!(err.kind(), ErrType::InvalidApiKey(_)), "{:?}", err);
+}
+
+/// Tests that invalid minimum similarities, and ones that raise the minimum similarity, are rejected before searching
+#[test]
+fn test_invalid_parameters() {
+	let server = MockServer::start();
+	let mut chain = FallbackChain::new(handler(&server));
+	chain.min_similarity(120);
+	let err = chain.get_sauce(FILE, None, None).unwrap_err();
+	assert!(matches!(err.kind(), ErrType::InvalidParameters(_)), "{:?}", err);
+
+	// Each step may only lower the minimum similarity, starting from the one searched with
+	let mut chain = FallbackChain::new(handler(&server));
+	chain.min_similarity(60).min_similarity(70);
+	let err = chain.get_sauce(FILE, None, Some(80.0)).unwrap_err();
+	assert!(matches!(err.kind(), ErrType::InvalidParameters(_)), "{:?}", err);
+	let err = chain.get_sauce(FILE, None, Some(50.0)).unwrap_err();
+	assert!(matches!(err.kind(), ErrType::InvalidParameters(_)), "{:?}", err);
+	assert!(server.requests().is_empty());
+}
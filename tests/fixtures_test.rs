@@ -0,0 +1,193 @@
+mod common;
+
+use common::{MockResponse, MockServer};
+use rustnao::{ErrType, Handler, HandlerBuilder, Result, Sauce, SauceResponse};
+
+const PIXIV_DANBOORU: &str = include_str!("fixtures/pixiv_danbooru.json");
+const NUMERIC_VALUES: &str = include_str!("fixtures/numeric_values.json");
+const SINGLE_EXT_URL: &str = include_str!("fixtures/single_ext_url.json");
+const ANIME: &str = include_str!("fixtures/anime.json");
+const UNRECOGNISED_SHAPES: &str = include_str!("fixtures/unrecognised_shapes.json");
+const ERROR_STATUS: &str = include_str!("fixtures/error_status.json");
+const FILE: &str = "https://i.imgur.com/W42kkKS.jpg";
+
+/// Returns a Handler that searches the mock server
+fn handler(server: &MockServer) -> Handler {
+	HandlerBuilder::default().api_url(server.url().as_str()).build()
+}
+
+/// Returns the response of a search of the Handler that the mock server answers with the given body
+fn respond(handle: &Handler, server: &MockServer, body: &str, min_similarity: Option<f64>) -> Result<SauceResponse> {
+	server.push(MockResponse::json(body));
+	handle.get_sauce_with_warnings(FILE, None, min_similarity)
+}
+
+/// Returns the results of a search of a new Handler that the mock server answers with the given body
+fn search(body: &str) -> Result<Vec<Sauce>> {
+	let server = MockServer::start();
+	respond(&handler(&server), &server, body, None).map(|response| response.results)
+}
+
+/// Tests that every well formed fixture parses without warnings
+#[test]
+fn test_fixtures_parse() {
+	let server = MockServer::start();
+	let handle = handler(&server);
+	for fixture in [PIXIV_DANBOORU, NUMERIC_VALUES, SINGLE_EXT_URL, ANIME].iter() {
+		let response = respond(&handle, &server, fixture, None).unwrap();
+		assert!(!response.results.is_empty());
+		assert!(response.warnings.is_empty(), "{:?}", response.warnings);
+	}
+}
+
+/// Tests a typical response
+#[test]
+fn test_pixiv_danbooru() {
+	let server = MockServer::start();
+	let handle = handler(&server);
+	let result = respond(&handle, &server, PIXIV_DANBOORU, None).unwrap().results;
+	assert_eq!(result.len(), 3);
+	assert_eq!(result[0].index, Handler::PIXIV);
+	assert_eq!(result[0].title, Some("ガヴリール".to_string()));
+	assert_eq!(result[1].site, "Danbooru");
+	assert_eq!(result[1].ext_urls.len(), 2);
+	assert!(result[2].has_empty_url());
+	assert_eq!(handle.get_short_limit(), 4);
+	assert_eq!(handle.get_long_limit(), 100);
+	assert_eq!(handle.get_current_short_limit(), 3);
+	assert_eq!(handle.get_current_long_limit(), 98);
+}
+
+/// Tests that the similarity and empty filters apply to responses, and that an invalid minimum similarity fails before searching
+#[test]
+fn test_filters() {
+	let server = MockServer::start();
+	let handle = HandlerBuilder::default()
+		.api_url(server.url().as_str())
+		.empty_filter_enabled(true)
+		.build();
+	assert_eq!(respond(&handle, &server, PIXIV_DANBOORU, None).unwrap().results.len(), 2);
+	assert_eq!(respond(&handle, &server, PIXIV_DANBOORU, Some(94.0)).unwrap().results.len(), 1);
+	assert!(respond(&handle, &server, PIXIV_DANBOORU, Some(101.0)).is_err());
+	assert_eq!(server.requests().len(), 2);
+}
+
+/// Tests numbers sent as strings and strings sent as numbers
+#[test]
+fn test_numeric_values() {
+	let server = MockServer::start();
+	let handle = handler(&server);
+	let result = respond(&handle, &server, NUMERIC_VALUES, None).unwrap().results;
+	assert_eq!(result.len(), 1);
+	assert_eq!(result[0].index, Handler::NIJIE);
+	assert_eq!(result[0].index_id, 11);
+	assert!((result[0].similarity - 88.5).abs() < 0.001);
+	assert_eq!(result[0].title, Some("2019".to_string()));
+	assert_eq!(handle.get_short_limit(), 6);
+	assert_eq!(handle.get_current_long_limit(), 199);
+}
+
+/// Tests a single ext_url that is not wrapped in a list
+#[test]
+fn test_single_ext_url() {
+	let result = search(SINGLE_EXT_URL).unwrap();
+	assert_eq!(result[0].ext_urls, ["https://seiga.nicovideo.jp/seiga/im9000000".to_string()].to_vec());
+}
+
+/// Tests that the additional fields of anime results are kept
+#[test]
+fn test_anime() {
+	let result = search(ANIME).unwrap();
+	assert_eq!(result[0].index, Handler::ANIME);
+	let fields = result[0].additional_fields.as_ref().unwrap();
+	assert_eq!(fields["est_time"], "00:12:34 / 00:24:00");
+	assert_eq!(fields["anidb_aid"], 12345);
+}
+
+/// Tests that unrecognised shapes are reported as warnings of that response instead of failing the search
+#[test]
+fn test_unrecognised_shapes() {
+	let server = MockServer::start();
+	let handle = handler(&server);
+	let response = respond(&handle, &server, UNRECOGNISED_SHAPES, None).unwrap();
+	let result = response.results;
+	assert_eq!(result.len(), 1);
+	assert!(result[0].ext_urls.is_empty());
+	assert_eq!(result[0].thumbnail, "");
+
+	let warnings = response.warnings;
+	let expected = [
+		"header.short_limit",
+		"header.long_remaining",
+		"result 0: unrecognised value for header.thumbnail",
+		"result 0: unrecognised value for data.ext_urls",
+		"result 1: skipped, unrecognised result",
+		"result 2: unrecognised value for header.similarity",
+		"result 2: skipped",
+	];
+	assert_eq!(warnings.len(), expected.len(), "{:?}", warnings);
+	for (warning, expected) in warnings.iter().zip(expected.iter()) {
+		assert!(warning.contains(expected), "{} does not contain {}", warning, expected);
+	}
+
+	// Values that could not be read leave the limits untouched
+	assert_eq!(handle.get_short_limit(), 12);
+	assert_eq!(handle.get_long_limit(), 100);
+
+	// The warnings belong to the response they were raised for, not to the next one
+	assert!(respond(&handle, &server, PIXIV_DANBOORU, None).unwrap().warnings.is_empty());
+}
+
+/// Tests that elements of the URLs of a result that are not URLs are reported one by one, keeping the other URLs
+#[test]
+fn test_mixed_ext_urls() {
+	let response = r#"{
+		"header": {"status": 0, "short_limit": "4", "long_limit": "100", "short_remaining": 3, "long_remaining": 99},
+		"results": [{
+			"header": {"similarity": "90.00", "thumbnail": "", "index_id": 9, "index_name": "Index #9: Danbooru - a.jpg"},
+			"data": {"ext_urls": ["https://danbooru.donmai.us/post/show/1", {"gelbooru": 2}, "https://gelbooru.com/index.php?page=post&s=view&id=2", null]}
+		}]
+	}"#;
+	let server = MockServer::start();
+	let response = respond(&handler(&server), &server, response, None).unwrap();
+	assert_eq!(
+		response.results[0].ext_urls,
+		[
+			"https://danbooru.donmai.us/post/show/1",
+			"https://gelbooru.com/index.php?page=post&s=view&id=2"
+		]
+	);
+	assert_eq!(
+		response.warnings,
+		[
+			r#"result 0: unrecognised element of data.ext_urls: {"gelbooru":2}"#,
+			"result 0: unrecognised element of data.ext_urls: null",
+		]
+	);
+}
+
+/// Tests that an error status from SauceNAO is returned as an error
+#[test]
+fn test_error_status() {
+	match search(ERROR_STATUS).unwrap_err().kind() {
+		ErrType::ShortLimitExceeded { retry_after, .. } => assert_eq!(retry_after.as_secs(), 30),
+		other => panic!("unexpected error {:?}", other),
+	}
+}
+
+/// Tests that a status which is missing or of an unrecognised shape is an error rather than taken as a success
+#[test]
+fn test_unrecognised_status() {
+	for header in [r#"{"status": "ok"}"#, r#"{"status": null}"#, "{}"].iter() {
+		let response = format!(r#"{{"header": {}, "results": []}}"#, header);
+		let err = search(response.as_str()).unwrap_err();
+		assert!(matches!(err.kind(), ErrType::InvalidSerde(_)), "{:?}", err);
+	}
+}
+
+/// Tests that a response which is not JSON is an error
+#[test]
+fn test_invalid_json() {
+	assert!(search("<html>502 Bad Gateway</html>").is_err());
+	assert!(search("{}").is_err());
+}
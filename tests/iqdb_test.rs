@@ -0,0 +1,140 @@
+#![cfg(feature = "iqdb")]
+
+mod common;
+
+use common::{MockResponse, MockServer};
+use rustnao::{ErrType, Iqdb, ReverseImageSearch};
+
+/// Returns an iqdb backend that searches the mock server
+fn iqdb(server: &MockServer) -> Iqdb {
+	let mut iqdb = Iqdb::new();
+	iqdb.api_url(server.base_url().as_str());
+	iqdb
+}
+
+/// Returns the iqdb result page fixture as a response
+fn page() -> MockResponse {
+	MockResponse::fixture("iqdb.html").header("Content-Type", "text/html; charset=utf-8")
+}
+
+/// Tests that the matches of a result page are parsed, skipping the searched image and the no relevant matches heading
+#[test]
+fn test_parse() {
+	let server = MockServer::start();
+	server.push(page());
+	let result = iqdb(&server).search_blocking("https://i.imgur.com/W42kkKS.jpg", None, None).unwrap();
+
+	assert_eq!(result.len(), 3);
+	assert_eq!(result[0].site, "Danbooru");
+	assert_eq!(result[0].index, 9);
+	assert_eq!(result[0].similarity, 95.0);
+	assert_eq!(
+		result[0].ext_urls,
+		vec![
+			"https://danbooru.donmai.us/posts/3441187",
+			"https://gelbooru.com/index.php?page=post&s=view&id=4425381"
+		]
+	);
+	assert_eq!(result[0].thumbnail, format!("{}danbooru/a/b/c/abc.jpg", server.base_url()));
+	let fields = result[0].additional_fields.as_ref().unwrap();
+	assert_eq!(fields["engine"], "iqdb");
+	assert_eq!(fields["match"], "Best match");
+	assert_eq!(fields["tags"], "1girl solo long_hair smile");
+	assert_eq!(fields["rating"], "Safe");
+
+	assert_eq!(result[1].site, "Yande.re");
+	assert_eq!(result[1].additional_fields.as_ref().unwrap()["rating"], "Ero");
+	assert_eq!(result[2].site, "zerochan.net");
+	assert_eq!(result[2].index, 999);
+	assert_eq!(result[2].ext_urls, vec!["https://www.zerochan.net/1234567"]);
+}
+
+/// Tests that links are sent as a form field and local files are uploaded
+#[test]
+fn test_request() {
+	let server = MockServer::start();
+	server.push(page());
+	server.push(page());
+	let iqdb = iqdb(&server);
+	iqdb.search_blocking("https://i.imgur.com/W42kkKS.jpg", None, None).unwrap();
+	iqdb.search_blocking("./tests/test.jpg", None, None).unwrap();
+
+	let requests = server.requests();
+	assert_eq!(requests[0].method, "POST");
+	assert_eq!(requests[0].path, "/");
+	assert!(requests[0].header("content-type").unwrap().starts_with("multipart/form-data; boundary="));
+	let body = String::from_utf8_lossy(&requests[0].body);
+	assert!(body.contains("name=\"url\"\r\n\r\nhttps://i.imgur.com/W42kkKS.jpg\r\n"), "{}", body);
+
+	let body = &requests[1].body;
+	let file = std::fs::read("./tests/test.jpg").unwrap();
+	let text = String::from_utf8_lossy(body);
+	assert!(
+		text.contains("name=\"file\"; filename=\"test.jpg\"\r\nContent-Type: image/jpeg"),
+		"{}",
+		&text[..200.min(text.len())]
+	);
+	assert!(body.windows(file.len()).any(|window| window == file.as_slice()));
+}
+
+/// Tests that the results are limited like those of a Handler
+#[test]
+fn test_limits() {
+	let server = MockServer::start();
+	server.push(page());
+	server.push(page());
+	let iqdb = iqdb(&server);
+
+	assert_eq!(iqdb.search_blocking("https://i.imgur.com/W42kkKS.jpg", Some(1), None).unwrap().len(), 1);
+	assert_eq!(
+		iqdb.search_blocking("https://i.imgur.com/W42kkKS.jpg", None, Some(80.0)).unwrap().len(),
+		2
+	);
+	let err = iqdb.search_blocking("https://i.imgur.com/W42kkKS.jpg", None, Some(101.0)).unwrap_err();
+	assert!(matches!(err.kind(), ErrType::InvalidParameters(_)), "{:?}", err);
+	assert_eq!(server.requests().len(), 2);
+}
+
+/// Tests that unsuccessful statuses are returned as errors
+#[test]
+fn test_error_status() {
+	let server = MockServer::start();
+	server.push(MockResponse::status(413, "File too large"));
+	let err = iqdb(&server).search_blocking("./tests/test.jpg", None, None).unwrap_err();
+	assert!(matches!(err.kind(), ErrType::FileTooLarge(_)), "{:?}", err);
+}
+
+/// Tests that a page whose tables close a heading before opening one is parsed without panicking
+#[test]
+fn test_malformed_page() {
+	let server = MockServer::start();
+	server.push(MockResponse::status(
+		200,
+		"<table></th><tr><th>Best match</tr></table><table><th>Your image</th></table>",
+	));
+	let result = iqdb(&server).search_blocking("https://i.imgur.com/W42kkKS.jpg", None, None).unwrap();
+	assert!(result.is_empty());
+}
+
+/// Tests a page laid out differently, with whitespace between tags, unquoted attributes, links with an explicit scheme and a match without a link
+#[test]
+fn test_layout_variants() {
+	let server = MockServer::start();
+	server.push(MockResponse::fixture("iqdb_variants.html"));
+	let result = iqdb(&server).search_blocking("https://i.imgur.com/W42kkKS.jpg", None, None).unwrap();
+
+	assert_eq!(result.len(), 2);
+	assert_eq!(result[0].site, "Danbooru");
+	assert_eq!(result[0].similarity, 91.0);
+	assert_eq!(result[0].ext_urls, vec!["http://danbooru.donmai.us/posts/5012345"]);
+	assert_eq!(result[0].thumbnail, "https://iqdb.org/danbooru/1/2/3/123.jpg");
+	let fields = result[0].additional_fields.as_ref().unwrap();
+	assert_eq!(fields["tags"], "2girls hug");
+	assert_eq!(fields["rating"], "Explicit");
+
+	assert_eq!(result[1].site, "Gelbooru");
+	assert_eq!(result[1].similarity, 83.0);
+	assert_eq!(result[1].ext_urls, vec!["https://gelbooru.com/index.php?page=post&s=view&id=7654321"]);
+	assert_eq!(result[1].thumbnail, format!("{}gelbooru/4/5/6/456.jpg", server.base_url()));
+	assert!(result[1].additional_fields.as_ref().unwrap()["rating"].is_null());
+}
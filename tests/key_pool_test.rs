@@ -0,0 +1,62 @@
+mod common;
+
+use common::{MockResponse, MockServer};
+use rustnao::HandlerBuilder;
+
+const RESPONSE: &str =
+	r#"{"header": {"status": 0, "short_limit": "4", "long_limit": "100", "short_remaining": 3, "long_remaining": 99}, "results": []}"#;
+const FILE: &str = "https://i.imgur.com/W42kkKS.jpg";
+
+/// Tests that a single key keeps the usual default limits
+#[test]
+fn test_single_key_limits() {
+	let handle = HandlerBuilder::default().api_key("your_api_key").build();
+	assert_eq!(handle.get_short_limit(), 12);
+	assert_eq!(handle.get_long_limit(), 200);
+	assert_eq!(handle.get_current_short_limit(), 12);
+	assert_eq!(handle.get_current_long_limit(), 200);
+}
+
+/// Tests that the limits of a pool are the total of its keys
+#[test]
+fn test_pool_limits() {
+	let handle = HandlerBuilder::default().api_keys(&["first", "second", "third"]).build();
+	assert_eq!(handle.get_short_limit(), 36);
+	assert_eq!(handle.get_long_limit(), 600);
+	assert!(handle.get_quarantined_api_keys().is_empty());
+}
+
+/// Tests that the key set by api_key is part of the pool
+#[test]
+fn test_api_key_in_pool() {
+	let handle = HandlerBuilder::default().api_key("first").api_keys(&["second"]).build();
+	assert_eq!(handle.get_short_limit(), 24);
+	assert_eq!(handle.get_current_long_limit(), 400);
+}
+
+/// Tests that a response only updates the limits of the key it was for
+#[test]
+fn test_pool_update() {
+	let server = MockServer::start();
+	server.push(MockResponse::json(RESPONSE));
+	let handle = HandlerBuilder::default()
+		.api_url(server.url().as_str())
+		.api_keys(&["first", "second"])
+		.build();
+	handle.get_sauce(FILE, None, None).unwrap();
+	assert_eq!(handle.get_short_limit(), 4 + 12);
+	assert_eq!(handle.get_long_limit(), 100 + 200);
+	assert_eq!(handle.get_current_short_limit(), 3 + 12);
+	assert_eq!(handle.get_current_long_limit(), 99 + 200);
+}
+
+/// Tests that an empty pool behaves like a Handler without an API key
+#[test]
+fn test_empty_pool() {
+	let server = MockServer::start();
+	server.push(MockResponse::json(RESPONSE));
+	let handle = HandlerBuilder::default().api_url(server.url().as_str()).api_keys(&[]).build();
+	assert_eq!(handle.get_short_limit(), 12);
+	handle.get_sauce(FILE, None, None).unwrap();
+	assert_eq!(handle.get_current_short_limit(), 3);
+}
@@ -0,0 +1,97 @@
+mod common;
+
+use common::{MockResponse, MockServer};
+use rustnao::{ErrType, Error, HandlerBuilder, QuotaStatus, ResultHeader, SearchListener};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// A listener that records every event it receives
+#[derive(Debug, Default, Clone)]
+struct RecordingListener(Arc<Mutex<Vec<String>>>);
+
+impl RecordingListener {
+	fn events(&self) -> Vec<String> {
+		self.0.lock().unwrap().clone()
+	}
+
+	fn record(&self, event: String) {
+		self.0.lock().unwrap().push(event);
+	}
+}
+
+impl SearchListener for RecordingListener {
+	fn request_started(&self, image_path: &str, attempt: u32) {
+		self.record(format!("request_started {} {}", image_path, attempt));
+	}
+
+	fn response_received(&self, header: &ResultHeader) {
+		self.record(format!("response_received {:?}", header.status));
+	}
+
+	fn rate_limit_approached(&self, status: &QuotaStatus) {
+		self.record(format!("rate_limit_approached {}", status.short_remaining));
+	}
+
+	fn rate_limit_exceeded(&self, error: &Error) {
+		self.record(format!("rate_limit_exceeded {}", error));
+	}
+
+	fn retry_scheduled(&self, error: &Error, attempt: u32) {
+		self.record(format!("retry_scheduled {} {}", error, attempt));
+	}
+
+	fn error(&self, error: &Error) {
+		self.record(format!("error {:?}", error.kind()));
+	}
+}
+
+/// A listener that ignores every event
+#[derive(Debug)]
+struct SilentListener;
+
+impl SearchListener for SilentListener {}
+
+/// Tests the events of a search that times out
+#[test]
+fn test_timeout_events() {
+	let server = MockServer::start();
+	server.push(MockResponse::empty(3, 99).delay(Duration::from_millis(500)));
+	let listener = RecordingListener::default();
+	let handle = HandlerBuilder::default()
+		.api_url(server.url().as_str())
+		.timeout(Duration::from_millis(100))
+		.listener(listener.clone())
+		.build();
+	assert!(handle.get_sauce("https://i.imgur.com/W42kkKS.jpg", None, None).is_err());
+
+	let events = listener.events();
+	assert_eq!(events.len(), 2, "{:?}", events);
+	assert_eq!(events[0], "request_started https://i.imgur.com/W42kkKS.jpg 1");
+	assert!(events[1].starts_with("error Timeout"), "{:?}", events);
+}
+
+/// Tests that invalid parameters are reported as errors without a request
+#[test]
+fn test_invalid_parameter_events() {
+	let listener = RecordingListener::default();
+	let handle = HandlerBuilder::default().listener(listener.clone()).build();
+	let err = handle.get_sauce("https://i.imgur.com/W42kkKS.jpg", Some(1000), None).unwrap_err();
+	assert!(matches!(err.kind(), ErrType::InvalidParameters(_)));
+	assert_eq!(listener.events().len(), 1);
+	assert!(listener.events()[0].starts_with("error InvalidParameters"));
+}
+
+/// Tests that every listener is called, in the order they were added
+#[test]
+fn test_multiple_listeners() {
+	let first = RecordingListener::default();
+	let second = RecordingListener::default();
+	let handle = HandlerBuilder::default()
+		.listener(first.clone())
+		.listener(SilentListener)
+		.listener(second.clone())
+		.build();
+	assert!(handle.get_sauce("https://i.imgur.com/W42kkKS.jpg", Some(1000), None).is_err());
+	assert_eq!(first.events(), second.events());
+	assert_eq!(first.events().len(), 1);
+}
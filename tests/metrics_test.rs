@@ -0,0 +1,116 @@
+#![cfg(feature = "metrics")]
+
+mod common;
+
+use common::{MockResponse, MockServer};
+use rustnao::{HandlerBuilder, Metrics};
+use std::time::Duration;
+
+const PIXIV_DANBOORU: &str = include_str!("fixtures/pixiv_danbooru.json");
+const FILE: &str = "https://i.imgur.com/W42kkKS.jpg";
+
+/// Returns the value of a sample in exported metrics, or None if it is missing
+fn value(exported: &str, sample: &str) -> Option<f64> {
+	exported
+		.lines()
+		.find(|line| line.starts_with(sample) && line[sample.len()..].starts_with(' '))
+		.map(|line| line[sample.len()..].trim().parse().unwrap())
+}
+
+/// Tests that the results of a response and the remaining limits are recorded
+#[test]
+fn test_results_and_quota() {
+	let metrics = Metrics::new();
+	let server = MockServer::start();
+	server.push(MockResponse::json(PIXIV_DANBOORU));
+	let handle = HandlerBuilder::default().api_url(server.url().as_str()).metrics(metrics.clone()).build();
+	handle.get_sauce(FILE, None, Some(90.0)).unwrap();
+
+	let exported = metrics.to_prometheus();
+	assert_eq!(value(&exported, "rustnao_results_returned_total"), Some(2.0));
+	assert_eq!(
+		value(&exported, r#"rustnao_results_filtered_total{reason="below_similarity"}"#),
+		Some(1.0)
+	);
+	assert_eq!(value(&exported, r#"rustnao_results_filtered_total{reason="empty_urls"}"#), Some(0.0));
+	let short = exported.lines().find(|line| line.starts_with("rustnao_quota_short_remaining{")).unwrap();
+	assert!(
+		short.starts_with(r#"rustnao_quota_short_remaining{key_id=""#) && short.ends_with(" 3"),
+		"{}",
+		short
+	);
+	let long = exported.lines().find(|line| line.starts_with("rustnao_quota_long_remaining{")).unwrap();
+	assert!(long.ends_with(" 98"), "{}", long);
+}
+
+/// Tests that failed searches are counted by the type of error, along with their duration
+#[test]
+fn test_errors() {
+	let metrics = Metrics::new();
+	let server = MockServer::start();
+	server.push(MockResponse::empty(3, 99).delay(Duration::from_millis(500)));
+	let handle = HandlerBuilder::default()
+		.api_url(server.url().as_str())
+		.timeout(Duration::from_millis(100))
+		.metrics(metrics.clone())
+		.build();
+	assert!(handle.get_sauce(FILE, None, None).is_err());
+	assert!(handle.get_sauce(FILE, Some(1000), None).is_err());
+
+	let exported = metrics.to_prometheus();
+	assert_eq!(value(&exported, r#"rustnao_searches_total{result="error"}"#), Some(2.0));
+	assert_eq!(value(&exported, r#"rustnao_searches_total{result="success"}"#), Some(0.0));
+	assert_eq!(value(&exported, r#"rustnao_search_errors_total{type="timeout"}"#), Some(1.0));
+	assert_eq!(value(&exported, r#"rustnao_search_errors_total{type="invalid_parameters"}"#), Some(1.0));
+	assert_eq!(value(&exported, r#"rustnao_search_duration_seconds_bucket{le="+Inf"}"#), Some(2.0));
+	assert_eq!(value(&exported, "rustnao_search_duration_seconds_count"), Some(2.0));
+}
+
+/// Tests that the size of uploaded files is recorded
+#[test]
+fn test_upload_bytes() {
+	let metrics = Metrics::new();
+	let server = MockServer::start();
+	server.push(MockResponse::json(PIXIV_DANBOORU));
+	let handle = HandlerBuilder::default().api_url(server.url().as_str()).metrics(metrics.clone()).build();
+	handle.get_sauce("./tests/test.jpg", None, None).unwrap();
+
+	let exported = metrics.to_prometheus();
+	let size = std::fs::metadata("./tests/test.jpg").unwrap().len();
+	assert_eq!(value(&exported, "rustnao_requests_total"), Some(1.0));
+	assert_eq!(value(&exported, "rustnao_upload_bytes_total"), Some(size as f64));
+}
+
+/// Tests that Handlers given clones of the same metrics record to them together
+#[test]
+fn test_shared_metrics() {
+	let metrics = Metrics::new();
+	let server = MockServer::start();
+	server.push(MockResponse::json(PIXIV_DANBOORU));
+	server.push(MockResponse::json(PIXIV_DANBOORU));
+	let first = HandlerBuilder::default().api_url(server.url().as_str()).metrics(metrics.clone()).build();
+	let second = HandlerBuilder::default().api_url(server.url().as_str()).metrics(metrics.clone()).build();
+	first.get_sauce(FILE, None, None).unwrap();
+	second.get_sauce(FILE, None, None).unwrap();
+	assert_eq!(value(&metrics.to_prometheus(), "rustnao_results_returned_total"), Some(6.0));
+}
+
+/// Tests that every metric is exported with its type, even before anything is recorded
+#[test]
+fn test_exposition_format() {
+	let exported = Metrics::new().to_prometheus();
+	for (name, kind) in &[
+		("rustnao_searches_total", "counter"),
+		("rustnao_search_errors_total", "counter"),
+		("rustnao_search_duration_seconds", "histogram"),
+		("rustnao_requests_total", "counter"),
+		("rustnao_upload_bytes_total", "counter"),
+		("rustnao_results_returned_total", "counter"),
+		("rustnao_results_filtered_total", "counter"),
+		("rustnao_quota_short_remaining", "gauge"),
+		("rustnao_quota_long_remaining", "gauge"),
+	] {
+		assert!(exported.contains(format!("# TYPE {} {}\n", name, kind).as_str()), "{}", exported);
+	}
+	assert_eq!(value(&exported, "rustnao_search_duration_seconds_sum"), Some(0.0));
+}
@@ -0,0 +1,182 @@
+mod common;
+
+use common::{MockResponse, MockServer};
+use rustnao::{AccountType, ErrType, Error, HandlerBuilder, ResultHeader, SearchListener};
+use std::sync::{Arc, Mutex};
+
+const FILE: &str = "https://i.imgur.com/W42kkKS.jpg";
+
+/// A listener that records every event it receives
+#[derive(Debug, Default, Clone)]
+struct RecordingListener(Arc<Mutex<Vec<String>>>);
+
+impl SearchListener for RecordingListener {
+	fn request_started(&self, _image_path: &str, attempt: u32) {
+		self.0.lock().unwrap().push(format!("request_started {}", attempt));
+	}
+
+	fn response_received(&self, header: &ResultHeader) {
+		self.0
+			.lock()
+			.unwrap()
+			.push(format!("response_received {:?} {:?}", header.status, header.account_type));
+	}
+
+	fn retry_scheduled(&self, _error: &Error, attempt: u32) {
+		self.0.lock().unwrap().push(format!("retry_scheduled {}", attempt));
+	}
+}
+
+/// Tests that a rejected API key is quarantined and the search is retried with the next key
+#[test]
+fn test_invalid_key_rotation() {
+	let server = MockServer::start();
+	server.push(MockResponse::invalid_api_key());
+	server.push(MockResponse::fixture("pixiv_danbooru.json"));
+	let listener = RecordingListener::default();
+	let handle = HandlerBuilder::default()
+		.api_keys(&["first_api_key", "second_api_key"])
+		.api_url(server.url().as_str())
+		.listener(listener.clone())
+		.build();
+	assert_eq!(handle.get_sauce(FILE, None, None).unwrap().len(), 3);
+	assert_eq!(handle.get_quarantined_api_keys(), vec!["first_api_key".to_string()]);
+
+	let requests = server.requests();
+	assert_eq!(requests.len(), 2);
+	assert_eq!(requests[0].query("api_key"), Some("first_api_key"));
+	assert_eq!(requests[1].query("api_key"), Some("second_api_key"));
+	assert_eq!(
+		*listener.0.lock().unwrap(),
+		vec![
+			"request_started 1",
+			"response_received Some(-1) None",
+			"retry_scheduled 2",
+			"request_started 2",
+			"response_received Some(0) Some(Anonymous)",
+		]
+	);
+}
+
+/// Tests that a Handler with a single key keeps using it after SauceNAO rejects it, so that it recovers once the key is accepted again
+#[test]
+fn test_invalid_single_key() {
+	let server = MockServer::start();
+	server.push(MockResponse::invalid_api_key());
+	server.push(MockResponse::fixture("pixiv_danbooru.json"));
+	let handle = HandlerBuilder::default().api_key("only_api_key").api_url(server.url().as_str()).build();
+	let err = handle.get_sauce(FILE, None, None).unwrap_err();
+	assert!(matches!(err.kind(), ErrType::InvalidApiKey(_)), "{:?}", err);
+	assert!(handle.get_quarantined_api_keys().is_empty());
+	assert_eq!(server.requests().len(), 1);
+
+	assert_eq!(handle.get_sauce(FILE, None, None).unwrap().len(), 3);
+	assert_eq!(server.requests()[1].query("api_key"), Some("only_api_key"));
+}
+
+/// Tests that when every key of a pool is rejected, the last one is kept for the next search
+#[test]
+fn test_invalid_every_key() {
+	let server = MockServer::start();
+	server.push(MockResponse::invalid_api_key());
+	server.push(MockResponse::invalid_api_key());
+	server.push(MockResponse::empty(3, 99));
+	let handle = HandlerBuilder::default()
+		.api_keys(&["first_api_key", "second_api_key"])
+		.api_url(server.url().as_str())
+		.build();
+	let err = handle.get_sauce(FILE, None, None).unwrap_err();
+	assert!(matches!(err.kind(), ErrType::InvalidApiKey(_)), "{:?}", err);
+	assert_eq!(handle.get_quarantined_api_keys(), vec!["first_api_key".to_string()]);
+
+	handle.get_sauce(FILE, None, None).unwrap();
+	assert_eq!(server.requests().len(), 3);
+	assert_eq!(server.requests()[2].query("api_key"), Some("second_api_key"));
+}
+
+/// Tests that a key whose short limit is exceeded is skipped until its window resets
+#[test]
+fn test_short_limit_rotation() {
+	let server = MockServer::start();
+	server.push(MockResponse::short_limit_exceeded());
+	server.push(MockResponse::empty(3, 99));
+	server.push(MockResponse::empty(2, 98));
+	let handle = HandlerBuilder::default()
+		.api_keys(&["first_api_key", "second_api_key"])
+		.api_url(server.url().as_str())
+		.build();
+	handle.get_sauce(FILE, None, None).unwrap();
+	handle.get_sauce(FILE, None, None).unwrap();
+	assert!(handle.get_quarantined_api_keys().is_empty());
+
+	let keys: Vec<Option<String>> = server
+		.requests()
+		.iter()
+		.map(|request| request.query("api_key").map(str::to_string))
+		.collect();
+	assert_eq!(
+		keys,
+		vec![
+			Some("first_api_key".to_string()),
+			Some("second_api_key".to_string()),
+			Some("second_api_key".to_string()),
+		]
+	);
+	let statuses = handle.get_quota_statuses();
+	assert_eq!(statuses[0].short_remaining, 0);
+	assert!(statuses[0].short_reset_at.is_some());
+	assert_eq!(statuses[1].short_remaining, 2);
+	assert_eq!(statuses[1].account_type, Some(AccountType::Basic));
+}
+
+/// Tests that a rate limit is returned once no other key has searches left
+#[test]
+fn test_daily_limit_single_key() {
+	let server = MockServer::start();
+	server.push(MockResponse::daily_limit_exceeded());
+	let handle = HandlerBuilder::default().api_key("test_api_key").api_url(server.url().as_str()).build();
+	let err = handle.get_sauce(FILE, None, None).unwrap_err();
+	assert!(matches!(err.kind(), ErrType::DailyLimitExceeded { .. }), "{:?}", err);
+	assert_eq!(handle.get_current_long_limit(), 0);
+	assert_eq!(server.requests().len(), 1);
+}
+
+/// Tests that the User-Agent and additional headers are sent with searches
+#[test]
+fn test_headers() {
+	let server = MockServer::start();
+	server.push(MockResponse::empty(3, 99));
+	let handle = HandlerBuilder::default()
+		.api_url(server.url().as_str())
+		.user_agent("rustnao-test/1.0")
+		.header("X-Request-Source", "mock")
+		.build();
+	handle.get_sauce(FILE, None, None).unwrap();
+
+	let request = &server.requests()[0];
+	assert_eq!(request.header("User-Agent"), Some("rustnao-test/1.0"));
+	assert_eq!(request.header("X-Request-Source"), Some("mock"));
+}
+
+/// Tests the asynchronous search functions
+#[test]
+fn test_async() {
+	let server = MockServer::start();
+	server.push(MockResponse::fixture("pixiv_danbooru.json"));
+	server.push(MockResponse::fixture("anime.json"));
+	let handle = HandlerBuilder::default().api_url(server.url().as_str()).build();
+	async_std::task::block_on(async {
+		assert_eq!(handle.async_get_sauce(FILE, None, Some(90.0)).await.unwrap().len(), 2);
+		let json = handle.async_get_sauce_as_json(FILE, None, None).await.unwrap();
+		assert!(json.contains("anidb"), "{}", json);
+	});
+	assert_eq!(handle.get_current_long_limit(), 95);
+}
+
+/// Tests that an invalid API URL is reported when searching
+#[test]
+fn test_invalid_api_url() {
+	let handle = HandlerBuilder::default().api_url("not a url").build();
+	let err = handle.get_sauce(FILE, None, None).unwrap_err();
+	assert!(matches!(err.kind(), ErrType::InvalidParse(_)), "{:?}", err);
+}
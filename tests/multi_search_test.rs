@@ -0,0 +1,120 @@
+mod common;
+
+use common::{MockResponse, MockServer};
+use rustnao::{ErrType, FakeSearch, HandlerBuilder, MultiSearch, ReverseImageSearch, Sauce, SauceBuilder};
+
+/// Creates a result from the given site with the given URLs and similarity
+fn sauce(site: &str, ext_urls: &[&str], similarity: f32) -> Sauce {
+	SauceBuilder::default()
+		.site(site)
+		.ext_urls(ext_urls.iter().map(|url| url.to_string()).collect())
+		.similarity(similarity)
+		.build()
+}
+
+/// Returns a fake that finds the given results for cat.png
+fn fake(results: Vec<Sauce>) -> FakeSearch {
+	let mut fake = FakeSearch::new();
+	fake.results("cat.png", results);
+	fake
+}
+
+/// Tests that results sharing a URL are merged in the order of the engines, whether the engines are searched in parallel or not
+#[test]
+fn test_merge() {
+	for &parallel in &[false, true] {
+		let first = fake(vec![
+			sauce("Danbooru", &["https://danbooru.donmai.us/posts/1"], 80.0),
+			sauce("Pixiv", &["https://www.pixiv.net/artworks/2"], 70.0),
+		]);
+		let second = fake(vec![
+			sauce("Gelbooru", &["https://gelbooru.com/3"], 90.0),
+			sauce("Danbooru", &["https://danbooru.donmai.us/posts/1", "https://yande.re/post/show/4"], 85.0),
+		]);
+		let mut search = MultiSearch::new();
+		search.engine(first.clone()).engine(second.clone()).parallel(parallel);
+
+		let result = search.search_blocking("cat.png", None, None).unwrap();
+		let sites: Vec<&str> = result.iter().map(|sauce| sauce.site.as_str()).collect();
+		assert_eq!(sites, vec!["Danbooru", "Pixiv", "Gelbooru"]);
+		assert_eq!(
+			result[0].ext_urls,
+			vec!["https://danbooru.donmai.us/posts/1", "https://yande.re/post/show/4"]
+		);
+		assert_eq!(result[0].similarity, 85.0);
+		assert_eq!(first.get_searches(), vec!["cat.png"]);
+		assert_eq!(second.get_searches(), vec!["cat.png"]);
+
+		assert_eq!(search.search_blocking("cat.png", Some(2), Some(75.0)).unwrap().len(), 2);
+	}
+}
+
+/// Tests that results are merged when their URLs are different links to the same page, keeping the URLs of the earlier result
+#[test]
+fn test_merge_normalised() {
+	let first = fake(vec![sauce(
+		"Pixiv",
+		&["https://www.pixiv.net/member_illust.php?mode=medium&illust_id=61477678"],
+		80.0,
+	)]);
+	let second = fake(vec![sauce(
+		"Pixiv",
+		&["https://www.pixiv.net/en/artworks/61477678", "http://www.example.com/61477678/"],
+		90.0,
+	)]);
+	let mut search = MultiSearch::new();
+	search.engine(first).engine(second);
+
+	let result = search.search_blocking("cat.png", None, None).unwrap();
+	assert_eq!(result.len(), 1);
+	assert_eq!(
+		result[0].ext_urls,
+		vec![
+			"https://www.pixiv.net/member_illust.php?mode=medium&illust_id=61477678",
+			"http://www.example.com/61477678/"
+		]
+	);
+	assert_eq!(result[0].similarity, 90.0);
+}
+
+/// Tests that failed engines are skipped, and that an error is only returned if every engine failed
+#[test]
+fn test_errors() {
+	let mut failing = FakeSearch::new();
+	failing.error("cat.png", ErrType::Timeout("timed out".to_string()));
+	let mut search = MultiSearch::new();
+	search.engine(failing.clone()).engine(fake(vec![sauce("Pixiv", &[], 90.0)]));
+	assert_eq!(search.search_blocking("cat.png", None, None).unwrap()[0].site, "Pixiv");
+
+	let mut other = FakeSearch::new();
+	other.error("cat.png", ErrType::InvalidApiKey("rejected".to_string()));
+	let mut search = MultiSearch::new();
+	search.engine(failing).engine(other).parallel(true);
+	let err = search.search_blocking("cat.png", None, None).unwrap_err();
+	assert_eq!(err.kind(), &ErrType::Timeout("timed out".to_string()));
+
+	let err = search.search_blocking("cat.png", Some(1000), None).unwrap_err();
+	assert!(matches!(err.kind(), ErrType::InvalidParameters(_)), "{:?}", err);
+	assert!(MultiSearch::new().search_blocking("cat.png", None, None).unwrap().is_empty());
+}
+
+/// Tests that slow engines are searched at the same time in parallel
+#[test]
+fn test_parallel_handlers() {
+	let delay = std::time::Duration::from_millis(500);
+	let first = MockServer::start();
+	first.push(MockResponse::fixture("pixiv_danbooru.json").delay(delay));
+	let second = MockServer::start();
+	second.push(MockResponse::fixture("anime.json").delay(delay));
+
+	let mut search = MultiSearch::new();
+	search
+		.engine(HandlerBuilder::default().api_url(first.url().as_str()).build())
+		.engine(HandlerBuilder::default().api_url(second.url().as_str()).build())
+		.parallel(true);
+	let started = std::time::Instant::now();
+	let result = search.search_blocking("https://i.imgur.com/W42kkKS.jpg", None, None).unwrap();
+	assert!(started.elapsed() < delay * 2, "{:?}", started.elapsed());
+	assert_eq!(result.len(), 4);
+	assert!(result[3].ext_urls[0].contains("anidb"));
+}
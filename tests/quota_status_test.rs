@@ -0,0 +1,125 @@
+mod common;
+
+use common::{MockResponse, MockServer};
+use rustnao::{AccountType, ErrType, Handler, HandlerBuilder, QuotaRecord, QuotaStore, Result};
+use std::time::{Duration, Instant, SystemTime};
+
+const PIXIV_DANBOORU: &str = include_str!("fixtures/pixiv_danbooru.json");
+const NUMERIC_VALUES: &str = include_str!("fixtures/numeric_values.json");
+const FILE: &str = "https://i.imgur.com/W42kkKS.jpg";
+
+/// Returns a Handler with the given API keys that has searched once, with the mock server answering with the given response
+fn searched(api_keys: &[&str], response: &str) -> Handler {
+	let server = MockServer::start();
+	server.push(MockResponse::json(response));
+	let handle = HandlerBuilder::default().api_url(server.url().as_str()).api_keys(api_keys).build();
+	handle.get_sauce(FILE, None, None).unwrap();
+	handle
+}
+
+/// A store that returns the same record for every key
+#[derive(Debug)]
+struct FixedQuotaStore(QuotaRecord);
+
+impl QuotaStore for FixedQuotaStore {
+	fn load(&self, _key_id: &str) -> Result<Option<QuotaRecord>> {
+		Ok(Some(self.0.clone()))
+	}
+
+	fn save(&self, _key_id: &str, _record: &QuotaRecord) -> Result<()> {
+		Ok(())
+	}
+}
+
+/// Returns a record without searches left in the short window, that was saved the given time ago
+fn exhausted_record(ago: Duration) -> QuotaRecord {
+	QuotaRecord {
+		short_limit: 4,
+		long_limit: 100,
+		short_remaining: 0,
+		long_remaining: 50,
+		updated_at: SystemTime::now() - ago,
+		account_type: Some(AccountType::Basic),
+	}
+}
+
+/// Tests the status before any search
+#[test]
+fn test_default_status() {
+	let handle = HandlerBuilder::default().api_key("your_api_key").build();
+	let status = handle.get_quota_status();
+	assert_eq!(status.short_limit, 12);
+	assert_eq!(status.long_limit, 200);
+	assert_eq!(status.short_remaining, 12);
+	assert_eq!(status.long_remaining, 200);
+	assert_eq!(status.short_reset_at, None);
+	assert_eq!(status.long_reset_at, None);
+	assert_eq!(status.account_type, None);
+	assert!(!status.quarantined);
+}
+
+/// Tests the status after a response, including the estimated reset times
+#[test]
+fn test_status_after_response() {
+	let before = SystemTime::now();
+	let handle = searched(&[], PIXIV_DANBOORU);
+	let status = handle.get_quota_status();
+	assert_eq!(status.short_remaining, 3);
+	assert_eq!(status.long_remaining, 98);
+	assert_eq!(status.account_type, Some(AccountType::Anonymous));
+
+	let short_reset_at = status.short_reset_at.unwrap();
+	assert!(short_reset_at > before && short_reset_at <= SystemTime::now() + Duration::from_secs(30));
+	let long_reset_at = status.long_reset_at.unwrap();
+	assert!(long_reset_at > before + Duration::from_secs(23 * 60 * 60));
+}
+
+/// Tests that the account type is read from numeric values as well
+#[test]
+fn test_account_type() {
+	let handle = searched(&[], NUMERIC_VALUES);
+	assert_eq!(handle.get_quota_status().account_type, Some(AccountType::Basic));
+}
+
+/// Tests the status of every key of a pool
+#[test]
+fn test_pool_statuses() {
+	let handle = searched(&["first", "second"], PIXIV_DANBOORU);
+	let statuses = handle.get_quota_statuses();
+	assert_eq!(statuses.len(), 2);
+	assert_eq!(statuses[0].long_remaining, 98);
+	assert_eq!(statuses[1].long_remaining, 200);
+	// The key with the most searches left is used next
+	assert_eq!(handle.get_quota_status().long_remaining, 200);
+}
+
+/// Tests that waiting returns immediately when enough searches are available
+#[test]
+fn test_wait_available() {
+	let handle = HandlerBuilder::default().build();
+	let start = Instant::now();
+	handle.wait_for_searches(12).unwrap();
+	assert!(start.elapsed() < Duration::from_secs(1));
+}
+
+/// Tests waiting for the short window to reset
+#[test]
+fn test_wait_for_reset() {
+	let handle = HandlerBuilder::default()
+		.quota_store(FixedQuotaStore(exhausted_record(Duration::from_millis(29_500))))
+		.build();
+	assert_eq!(handle.get_quota_status().short_remaining, 0);
+	handle.wait_for_searches(4).unwrap();
+	assert_eq!(handle.get_quota_status().short_remaining, 4);
+	assert_eq!(handle.get_quota_status().short_reset_at, None);
+}
+
+/// Tests that waiting for more searches than the limits allow fails
+#[test]
+fn test_wait_too_many() {
+	let handle = HandlerBuilder::default()
+		.quota_store(FixedQuotaStore(exhausted_record(Duration::from_secs(1))))
+		.build();
+	let err = handle.wait_for_searches(5).unwrap_err();
+	assert!(matches!(err.kind(), ErrType::InvalidParameters(_)), "{:?}", err);
+}
@@ -0,0 +1,137 @@
+mod common;
+
+use common::{MockResponse, MockServer};
+use rustnao::{FileQuotaStore, HandlerBuilder, QuotaRecord, QuotaStore, Result};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+const RESPONSE: &str =
+	r#"{"header": {"status": 0, "short_limit": "4", "long_limit": "100", "short_remaining": 3, "long_remaining": 99}, "results": []}"#;
+const FILE: &str = "https://i.imgur.com/W42kkKS.jpg";
+
+/// A store that returns the same record for every key
+#[derive(Debug)]
+struct FixedQuotaStore(QuotaRecord);
+
+impl QuotaStore for FixedQuotaStore {
+	fn load(&self, _key_id: &str) -> Result<Option<QuotaRecord>> {
+		Ok(Some(self.0.clone()))
+	}
+
+	fn save(&self, _key_id: &str, _record: &QuotaRecord) -> Result<()> {
+		Ok(())
+	}
+}
+
+/// Returns a path in the temporary directory that is unique to the test
+fn temporary_path(name: &str) -> PathBuf {
+	let path = std::env::temp_dir().join(format!("rustnao-{}-{}.json", name, std::process::id()));
+	let _ = std::fs::remove_file(&path);
+	path
+}
+
+/// Returns a record that was saved the given time ago
+fn record_from(ago: Duration) -> QuotaRecord {
+	QuotaRecord {
+		short_limit: 4,
+		long_limit: 100,
+		short_remaining: 0,
+		long_remaining: 42,
+		updated_at: SystemTime::now() - ago,
+		account_type: None,
+	}
+}
+
+/// Tests that limits saved by one Handler are loaded by the next
+#[test]
+fn test_file_store_round_trip() {
+	let path = temporary_path("round-trip");
+	let server = MockServer::start();
+	server.push(MockResponse::json(RESPONSE));
+	let handle = HandlerBuilder::default()
+		.api_key("secret_api_key")
+		.api_url(server.url().as_str())
+		.quota_store(FileQuotaStore::new(&path))
+		.build();
+	let response = handle.get_sauce_with_warnings(FILE, None, None).unwrap();
+	assert!(response.warnings.is_empty(), "{:?}", response.warnings);
+
+	let contents = std::fs::read_to_string(&path).unwrap();
+	assert!(!contents.contains("secret_api_key"));
+
+	let restarted = HandlerBuilder::default()
+		.api_key("secret_api_key")
+		.quota_store(FileQuotaStore::new(&path))
+		.build();
+	assert_eq!(restarted.get_short_limit(), 4);
+	assert_eq!(restarted.get_long_limit(), 100);
+	assert_eq!(restarted.get_current_short_limit(), 3);
+	assert_eq!(restarted.get_current_long_limit(), 99);
+
+	// A different key has no saved limits
+	let other = HandlerBuilder::default()
+		.api_key("other_api_key")
+		.quota_store(FileQuotaStore::new(&path))
+		.build();
+	assert_eq!(other.get_current_long_limit(), 200);
+	std::fs::remove_file(&path).unwrap();
+}
+
+/// Tests that a missing file is treated as an empty store
+#[test]
+fn test_file_store_missing() {
+	let store = FileQuotaStore::new(temporary_path("missing"));
+	assert_eq!(store.load("key").unwrap(), None);
+}
+
+/// Tests that a file that cannot be read is reported as a warning of the first response
+#[test]
+fn test_file_store_invalid() {
+	let path = temporary_path("invalid");
+	std::fs::write(&path, "not json").unwrap();
+	let server = MockServer::start();
+	server.push(MockResponse::json(RESPONSE));
+	server.push(MockResponse::json(RESPONSE));
+	let handle = HandlerBuilder::default()
+		.api_url(server.url().as_str())
+		.quota_store(FileQuotaStore::new(&path))
+		.build();
+	assert_eq!(handle.get_current_long_limit(), 200);
+	// Removed so that saving the limits of the response starts a new file instead of failing too
+	std::fs::remove_file(&path).unwrap();
+	let warnings = handle.get_sauce_with_warnings(FILE, None, None).unwrap().warnings;
+	assert_eq!(warnings.len(), 1, "{:?}", warnings);
+	assert!(warnings[0].contains("could not load quota state"));
+	assert!(handle.get_sauce_with_warnings(FILE, None, None).unwrap().warnings.is_empty());
+	std::fs::remove_file(&path).unwrap();
+}
+
+/// Tests that a recent record is used as is
+#[test]
+fn test_recent_record() {
+	let handle = HandlerBuilder::default()
+		.quota_store(FixedQuotaStore(record_from(Duration::from_secs(5))))
+		.build();
+	assert_eq!(handle.get_current_short_limit(), 0);
+	assert_eq!(handle.get_current_long_limit(), 42);
+}
+
+/// Tests that the short window is assumed to be reset once it has passed
+#[test]
+fn test_short_window_passed() {
+	let handle = HandlerBuilder::default()
+		.quota_store(FixedQuotaStore(record_from(Duration::from_secs(60))))
+		.build();
+	assert_eq!(handle.get_current_short_limit(), 4);
+	assert_eq!(handle.get_current_long_limit(), 42);
+}
+
+/// Tests that records older than a day are ignored
+#[test]
+fn test_old_record() {
+	let handle = HandlerBuilder::default()
+		.quota_store(FixedQuotaStore(record_from(Duration::from_secs(2 * 24 * 60 * 60))))
+		.build();
+	assert_eq!(handle.get_short_limit(), 12);
+	assert_eq!(handle.get_current_long_limit(), 200);
+}
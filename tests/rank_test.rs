@@ -0,0 +1,135 @@
+mod common;
+
+use common::{MockResponse, MockServer};
+use rustnao::{HandlerBuilder, RankReason, RankSources, Sauce, SauceBuilder, SourcePolicy};
+
+/// Creates a result from the given site with the given URLs, similarity and source
+fn sauce(site: &str, ext_urls: &[&str], similarity: f32, source: Option<&str>) -> Sauce {
+	let mut builder = SauceBuilder::default();
+	builder
+		.site(site)
+		.ext_urls(ext_urls.iter().map(|url| url.to_string()).collect())
+		.similarity(similarity);
+	if let Some(source) = source {
+		builder.additional_fields(serde_json::json!({ "source": source }));
+	}
+	builder.build()
+}
+
+/// Returns the sites of the ranked results, in order
+fn sites(results: &[Sauce], policy: &SourcePolicy) -> Vec<String> {
+	results.to_vec().rank(policy).into_iter().map(|ranked| ranked.sauce.site).collect()
+}
+
+/// Tests that the Pixiv result of a SauceNAO response is chosen, with its link and the reason
+#[test]
+fn test_best_from_response() {
+	let server = MockServer::start();
+	server.push(MockResponse::fixture("pixiv_danbooru.json"));
+	let handle = HandlerBuilder::default().api_key("test_api_key").api_url(server.url().as_str()).build();
+	let result = handle.get_sauce("https://i.imgur.com/W42kkKS.jpg", None, None).unwrap();
+
+	let best = result.best(&SourcePolicy::new()).unwrap();
+	assert_eq!(best.sauce.site, "Pixiv");
+	assert_eq!(
+		best.url.as_deref(),
+		Some("https://www.pixiv.net/member_illust.php?mode=medium&illust_id=61477678")
+	);
+	assert_eq!(
+		best.reason,
+		RankReason::Origin {
+			site: "Pixiv".to_string(),
+			preference: 0
+		}
+	);
+	assert_eq!(best.explain(), "Pixiv at 94.57% similarity, because it is from Pixiv, origin site #1");
+	assert!(Vec::<Sauce>::new().best(&SourcePolicy::new()).is_none());
+}
+
+/// Tests that origin sites are preferred over more similar mirrors, and that booru posts sourced from an origin site come next
+#[test]
+fn test_rank() {
+	let results = vec![
+		sauce("Danbooru", &["https://danbooru.donmai.us/posts/1"], 97.0, None),
+		sauce(
+			"Gelbooru",
+			&["https://gelbooru.com/index.php?page=post&s=view&id=2"],
+			96.0,
+			Some("https://x.com/user/status/3"),
+		),
+		sauce("Twitter", &["https://twitter.com/user/status/3"], 90.0, None),
+		sauce(
+			"Yande.re",
+			&["https://yande.re/post/show/4"],
+			95.0,
+			Some("https://www.pixiv.net/artworks/5"),
+		),
+		sauce("deviantArt", &["https://deviantart.com/view/6"], 80.0, None),
+		sauce("Konachan", &["https://konachan.com/post/show/7"], 98.0, Some("https://example.com/7")),
+	];
+	let policy = SourcePolicy::new();
+	assert_eq!(
+		sites(&results, &policy),
+		vec!["Yande.re", "deviantArt", "Twitter", "Gelbooru", "Konachan", "Danbooru"]
+	);
+
+	let ranked = results.rank(&policy);
+	assert_eq!(ranked[0].url.as_deref(), Some("https://www.pixiv.net/artworks/5"));
+	assert_eq!(
+		ranked[0].reason,
+		RankReason::OriginSource {
+			site: "Pixiv".to_string(),
+			preference: 0
+		}
+	);
+	assert_eq!(ranked[3].url.as_deref(), Some("https://twitter.com/i/web/status/3"));
+	assert_eq!(ranked[4].url.as_deref(), Some("https://konachan.com/post/show/7"));
+	assert_eq!(ranked[4].reason, RankReason::Similarity);
+}
+
+/// Tests that results from an origin site are found by their URLs as well as their site, and rank before posts sourced from the same site
+#[test]
+fn test_rank_by_url() {
+	let results = vec![
+		sauce(
+			"Danbooru",
+			&["https://danbooru.donmai.us/posts/1"],
+			95.0,
+			Some("https://www.pixiv.net/artworks/2"),
+		),
+		sauce("Other", &["https://www.pixiv.net/en/artworks/2"], 90.0, None),
+	];
+	let ranked = results.rank(&SourcePolicy::new());
+	assert_eq!(ranked[0].sauce.site, "Other");
+	assert_eq!(ranked[0].url.as_deref(), Some("https://www.pixiv.net/artworks/2"));
+	assert_eq!(
+		ranked[0].reason,
+		RankReason::Origin {
+			site: "Pixiv".to_string(),
+			preference: 0
+		}
+	);
+}
+
+/// Tests that the origin sites and minimum similarity of a policy can be configured
+#[test]
+fn test_policy() {
+	let results = vec![
+		sauce("Pixiv", &["https://www.pixiv.net/artworks/1"], 50.0, None),
+		sauce("Danbooru", &["https://danbooru.donmai.us/posts/2"], 92.0, None),
+		sauce("Twitter", &["https://twitter.com/user/status/3"], 85.0, None),
+	];
+	assert_eq!(sites(&results, &SourcePolicy::new()), vec!["Pixiv", "Twitter", "Danbooru"]);
+
+	let mut policy = SourcePolicy::new();
+	policy.min_similarity(60);
+	assert_eq!(sites(&results, &policy), vec!["Twitter", "Danbooru", "Pixiv"]);
+	assert_eq!(results.best(&policy).unwrap().reason.to_string(), "it is from Twitter, origin site #4");
+
+	policy.origins(vec!["danbooru", "Pixiv"]);
+	assert_eq!(sites(&results, &policy), vec!["Danbooru", "Twitter", "Pixiv"]);
+
+	policy.origins(vec![]);
+	assert_eq!(sites(&results, &policy), vec!["Danbooru", "Twitter", "Pixiv"]);
+	assert_eq!(results.best(&policy).unwrap().reason, RankReason::Similarity);
+}
@@ -0,0 +1,68 @@
+use rustnao::{Handler, Sauce, SauceBuilder};
+
+/// Creates a Sauce with every field filled in for testing purposes
+fn create_sauce() -> Sauce {
+	SauceBuilder::default()
+		.ext_urls(["https://www.pixiv.net/member_illust.php?mode=medium&illust_id=61477678".to_string()].to_vec())
+		.title("Title")
+		.site("Pixiv")
+		.index(Handler::PIXIV)
+		.similarity(92.5)
+		.thumbnail("https://img1.saucenao.com/res/pixiv/6147/61477678_s.jpg")
+		.additional_fields(serde_json::json!({ "member_id": 4754550, "member_name": "リン☆ユウ" }))
+		.build()
+}
+
+/// Tests the defaults of the builder
+#[test]
+fn test_builder_defaults() {
+	let sauce = SauceBuilder::default().index(Handler::SANKAKU_CHANNEL).build();
+	assert!(sauce.ext_urls.is_empty());
+	assert_eq!(sauce.title, None);
+	assert_eq!(sauce.site, "");
+	assert_eq!(sauce.index_id, Handler::SANKAKU_CHANNEL);
+	assert_eq!(sauce.schema_version(), Sauce::SCHEMA_VERSION);
+}
+
+/// Tests that a Sauce survives being serialized and deserialized
+#[test]
+fn test_round_trip() {
+	let sauce = create_sauce();
+	let json = serde_json::to_string(&sauce).unwrap();
+	let reloaded: Sauce = serde_json::from_str(json.as_str()).unwrap();
+	assert_eq!(sauce, reloaded);
+	assert_eq!(sauce, sauce.clone());
+}
+
+/// Tests that the serialized form contains the schema version
+#[test]
+fn test_schema_version_serialized() {
+	let value = serde_json::to_value(create_sauce()).unwrap();
+	assert_eq!(value["schema_version"], serde_json::json!(Sauce::SCHEMA_VERSION));
+}
+
+/// Tests that JSON written before the schema version existed can still be loaded
+#[test]
+fn test_legacy_json() {
+	let json = r#"{
+		"ext_urls": ["https://danbooru.donmai.us/post/show/3441187"],
+		"title": null,
+		"site": "Danbooru",
+		"index": 9,
+		"index_id": 9,
+		"similarity": 61.31,
+		"thumbnail": "https://img3.saucenao.com/booru/a/b/c.jpg",
+		"additional_fields": null
+	}"#;
+	let sauce: Sauce = serde_json::from_str(json).unwrap();
+	assert_eq!(sauce.schema_version(), 1);
+	assert_eq!(sauce.index, Handler::DANBOORU);
+}
+
+/// Tests that JSON from an unknown schema version is rejected
+#[test]
+fn test_unknown_schema_version() {
+	let mut value = serde_json::to_value(create_sauce()).unwrap();
+	value["schema_version"] = serde_json::json!(Sauce::SCHEMA_VERSION + 1);
+	assert!(serde_json::from_value::<Sauce>(value).is_err());
+}
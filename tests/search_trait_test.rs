@@ -0,0 +1,81 @@
+mod common;
+
+use common::{MockResponse, MockServer};
+use rustnao::{ErrType, FakeSearch, HandlerBuilder, ReverseImageSearch, Sauce, SauceBuilder};
+
+/// Returns the sites of the results, as application code depending on the trait would
+fn sites(search: &dyn ReverseImageSearch, image_path: &str) -> rustnao::Result<Vec<String>> {
+	let results = search.search_blocking(image_path, None, Some(50.0))?;
+	Ok(results.into_iter().map(|sauce| sauce.site).collect())
+}
+
+/// Creates a result from the given site with the given similarity
+fn sauce(site: &str, similarity: f32) -> Sauce {
+	SauceBuilder::default().site(site).similarity(similarity).build()
+}
+
+/// Tests that the fake returns the configured results for each input
+#[test]
+fn test_fake_results() {
+	let mut fake = FakeSearch::new();
+	fake.results("cat.png", vec![sauce("Pixiv", 92.5), sauce("Danbooru", 40.0)])
+		.results("dog.png", vec![sauce("Twitter", 80.0)]);
+
+	assert_eq!(sites(&fake, "cat.png").unwrap(), vec!["Pixiv"]);
+	assert_eq!(sites(&fake, "dog.png").unwrap(), vec!["Twitter"]);
+	assert!(sites(&fake, "bird.png").unwrap().is_empty());
+	assert_eq!(fake.get_searches(), vec!["cat.png", "dog.png", "bird.png"]);
+}
+
+/// Tests that the fake limits the number of results and rejects invalid parameters like a Handler
+#[test]
+fn test_fake_parameters() {
+	let mut fake = FakeSearch::new();
+	fake.results("cat.png", vec![sauce("Pixiv", 92.5), sauce("Danbooru", 91.0), sauce("Gelbooru", 90.0)]);
+
+	assert_eq!(fake.search_blocking("cat.png", Some(2), None).unwrap().len(), 2);
+	assert_eq!(fake.search_blocking("cat.png", None, Some(91.0)).unwrap().len(), 2);
+	let err = fake.search_blocking("cat.png", Some(1000), None).unwrap_err();
+	assert!(matches!(err.kind(), ErrType::InvalidParameters(_)), "{:?}", err);
+	let err = fake.search_blocking("cat.png", None, Some(100.1)).unwrap_err();
+	assert!(matches!(err.kind(), ErrType::InvalidParameters(_)), "{:?}", err);
+	assert_eq!(fake.get_searches().len(), 2);
+}
+
+/// Tests that the fake returns configured errors, and that clones share the recorded searches
+#[test]
+fn test_fake_errors() {
+	let mut fake = FakeSearch::new();
+	fake.error("dog.png", ErrType::Timeout("timed out".to_string()));
+	let clone = fake.clone();
+
+	let err = sites(&clone, "dog.png").unwrap_err();
+	assert_eq!(err.kind(), &ErrType::Timeout("timed out".to_string()));
+	assert_eq!(fake.get_searches(), vec!["dog.png"]);
+}
+
+/// Tests the asynchronous methods of the trait, through a trait object
+#[test]
+fn test_fake_async() {
+	let mut fake = FakeSearch::new();
+	fake.results("cat.png", vec![sauce("Pixiv", 92.5)]);
+	let search: Box<dyn ReverseImageSearch> = Box::new(fake);
+
+	async_std::task::block_on(async {
+		assert_eq!(search.search("cat.png", None, None).await.unwrap()[0].site, "Pixiv");
+		let json = search.search_as_json("cat.png", None, None).await.unwrap();
+		assert!(json.contains("\"site\":\"Pixiv\""), "{}", json);
+	});
+}
+
+/// Tests that a Handler searches through the trait like it does directly
+#[test]
+fn test_handler() {
+	let server = MockServer::start();
+	server.push(MockResponse::fixture("pixiv_danbooru.json"));
+	let handle = HandlerBuilder::default().api_url(server.url().as_str()).build();
+
+	assert_eq!(sites(&handle, "https://i.imgur.com/W42kkKS.jpg").unwrap(), vec!["Pixiv", "Danbooru"]);
+	let request = &server.requests()[0];
+	assert_eq!(request.query("url"), Some("https://i.imgur.com/W42kkKS.jpg"));
+}
@@ -0,0 +1,192 @@
+use rustnao::{SauceBuilder, SourceRef};
+
+/// Returns a MangaDex chapter
+fn mangadex(id: &str) -> Option<SourceRef> {
+	Some(SourceRef::MangaDex(id.to_string()))
+}
+
+/// Returns an IMDb title
+fn imdb(id: &str) -> Option<SourceRef> {
+	Some(SourceRef::Imdb(id.to_string()))
+}
+
+/// Returns URLs and the SourceRef they should be classified as, covering the shapes each site uses
+fn cases() -> Vec<(&'static str, Option<SourceRef>)> {
+	vec![
+		// Pixiv
+		(
+			"https://www.pixiv.net/member_illust.php?mode=medium&illust_id=61477678",
+			Some(SourceRef::Pixiv(61477678)),
+		),
+		(
+			"http://www.pixiv.net/member_illust.php?illust_id=61477678&mode=big",
+			Some(SourceRef::Pixiv(61477678)),
+		),
+		("https://www.pixiv.net/artworks/73095123", Some(SourceRef::Pixiv(73095123))),
+		("https://www.pixiv.net/en/artworks/73095123", Some(SourceRef::Pixiv(73095123))),
+		("https://pixiv.net/i/73095123", Some(SourceRef::Pixiv(73095123))),
+		(
+			"https://i.pximg.net/img-original/img/2019/02/10/03/11/39/73095123_p0.png",
+			Some(SourceRef::Pixiv(73095123)),
+		),
+		(
+			"https://i.pximg.net/img-master/img/2019/02/10/03/11/39/73095123_p0_master1200.jpg",
+			Some(SourceRef::Pixiv(73095123)),
+		),
+		(
+			"https://i.pximg.net/c/250x250_80_a2/img-master/img/2019/02/10/03/11/39/73095123_p0_square1200.jpg",
+			Some(SourceRef::Pixiv(73095123)),
+		),
+		(
+			"https://i.pximg.net/user-profile/img/2019/01/02/03/04/05/15228312_d4f2a1b3c8e9f0a1b2c3d4e5f6a7b8c9_170.jpg",
+			None,
+		),
+		("https://i.pximg.net/4754550_p0.png", None),
+		("https://www.pixiv.net/member.php?id=4754550", None),
+		("https://www.pixiv.net/users/4754550", None),
+		("https://www.pixiv.net/member_illust.php?mode=medium", None),
+		// Danbooru
+		("https://danbooru.donmai.us/post/show/3441187", Some(SourceRef::Danbooru(3441187))),
+		("https://danbooru.donmai.us/posts/3441187", Some(SourceRef::Danbooru(3441187))),
+		("https://danbooru.donmai.us/posts/3441187?q=1girl", Some(SourceRef::Danbooru(3441187))),
+		("https://danbooru.donmai.us/posts?tags=1girl", None),
+		// Gelbooru
+		(
+			"https://gelbooru.com/index.php?page=post&s=view&id=4425381",
+			Some(SourceRef::Gelbooru(4425381)),
+		),
+		(
+			"https://www.gelbooru.com/index.php?id=4425381&page=post&s=view",
+			Some(SourceRef::Gelbooru(4425381)),
+		),
+		("https://gelbooru.com/index.php?page=post&s=list&tags=1girl", None),
+		("https://gelbooru.com/index.php?page=wiki&s=view&id=1", None),
+		// yande.re and Konachan
+		("https://yande.re/post/show/461215", Some(SourceRef::YandeRe(461215))),
+		("https://yande.re/post/show/461215/dress-tagme", Some(SourceRef::YandeRe(461215))),
+		("https://yande.re/pool/show/123", None),
+		("https://konachan.com/post/show/286914", Some(SourceRef::Konachan(286914))),
+		("http://konachan.net/post/show/286914/long_hair", Some(SourceRef::Konachan(286914))),
+		// DeviantArt
+		("https://deviantart.com/view/515473394", Some(SourceRef::DeviantArt(515473394))),
+		(
+			"https://www.deviantart.com/artist-name/art/Some-Title-Words-515473394",
+			Some(SourceRef::DeviantArt(515473394)),
+		),
+		(
+			"https://artist-name.deviantart.com/art/Some-Title-515473394",
+			Some(SourceRef::DeviantArt(515473394)),
+		),
+		("https://www.deviantart.com/deviation/515473394", Some(SourceRef::DeviantArt(515473394))),
+		("https://fav.me/d8iwdte", Some(SourceRef::DeviantArt(515473394))),
+		("https://www.deviantart.com/artist-name", None),
+		// Nijie
+		("https://nijie.info/view.php?id=233339", Some(SourceRef::Nijie(233339))),
+		("https://sp.nijie.info/view_popup.php?id=233339", Some(SourceRef::Nijie(233339))),
+		("https://nijie.info/members.php?id=728", None),
+		// Twitter
+		(
+			"https://twitter.com/user/status/1160000000000000000",
+			Some(SourceRef::Twitter(1160000000000000000)),
+		),
+		(
+			"https://twitter.com/user/status/1160000000000000000/photo/1",
+			Some(SourceRef::Twitter(1160000000000000000)),
+		),
+		(
+			"https://mobile.twitter.com/user/status/1160000000000000000",
+			Some(SourceRef::Twitter(1160000000000000000)),
+		),
+		(
+			"https://x.com/user/status/1160000000000000000?s=20",
+			Some(SourceRef::Twitter(1160000000000000000)),
+		),
+		(
+			"https://twitter.com/i/web/status/1160000000000000000",
+			Some(SourceRef::Twitter(1160000000000000000)),
+		),
+		(
+			"http://twitter.com/user/statuses/1160000000000000000",
+			Some(SourceRef::Twitter(1160000000000000000)),
+		),
+		("https://twitter.com/user", None),
+		// MangaDex
+		(
+			"https://mangadex.org/chapter/4f0d8bd8-0b1c-4b2e-9d6b-2f2d1f6e3a7c",
+			mangadex("4f0d8bd8-0b1c-4b2e-9d6b-2f2d1f6e3a7c"),
+		),
+		(
+			"https://mangadex.org/chapter/4F0D8BD8-0B1C-4B2E-9D6B-2F2D1F6E3A7C/2",
+			mangadex("4f0d8bd8-0b1c-4b2e-9d6b-2f2d1f6e3a7c"),
+		),
+		("https://mangadex.org/chapter/517244", mangadex("517244")),
+		("https://mangadex.org/title/4f0d8bd8-0b1c-4b2e-9d6b-2f2d1f6e3a7c", None),
+		("https://mangadex.org/chapter/not-an-id", None),
+		// AniDB
+		("https://anidb.net/perl-bin/animedb.pl?show=anime&aid=4037", Some(SourceRef::AniDb(4037))),
+		("https://anidb.net/anime/4037", Some(SourceRef::AniDb(4037))),
+		("https://anidb.net/a4037", Some(SourceRef::AniDb(4037))),
+		("https://anidb.net/perl-bin/animedb.pl?show=character&charid=4037", None),
+		// IMDb
+		("https://www.imdb.com/title/tt0111161/", imdb("tt0111161")),
+		("https://m.imdb.com/title/TT0111161/reviews", imdb("tt0111161")),
+		("https://www.imdb.com/name/nm0000151/", None),
+		("https://www.imdb.com/title/tt/", None),
+		// Unknown sites and malformed URLs
+		("https://example.com/post/show/1", None),
+		("https://www.pixiv.net/artworks/not-a-number", None),
+		("not a url", None),
+		("", None),
+	]
+}
+
+/// Tests that every URL shape is classified as expected
+#[test]
+fn test_parse() {
+	for (url, source_ref) in cases() {
+		assert_eq!(SourceRef::parse(url), source_ref, "{}", url);
+	}
+}
+
+/// Tests the sites and IDs of the SourceRefs
+#[test]
+fn test_site_and_id() {
+	let cases = [
+		(SourceRef::Pixiv(61477678), "Pixiv", "61477678"),
+		(SourceRef::Danbooru(3441187), "Danbooru", "3441187"),
+		(SourceRef::Gelbooru(4425381), "Gelbooru", "4425381"),
+		(SourceRef::YandeRe(461215), "Yande.re", "461215"),
+		(SourceRef::Konachan(286914), "Konachan", "286914"),
+		(SourceRef::DeviantArt(515473394), "deviantArt", "515473394"),
+		(SourceRef::Nijie(233339), "Nijie Images", "233339"),
+		(SourceRef::Twitter(1160000000000000000), "Twitter", "1160000000000000000"),
+		(SourceRef::MangaDex("517244".to_string()), "MangaDex", "517244"),
+		(SourceRef::AniDb(4037), "AniDB", "4037"),
+		(SourceRef::Imdb("tt0111161".to_string()), "IMDb", "tt0111161"),
+	];
+	for (source_ref, site, id) in cases.iter() {
+		assert_eq!(source_ref.site(), *site);
+		assert_eq!(source_ref.id(), *id);
+		assert_eq!(source_ref.to_string(), format!("{} {}", site, id));
+	}
+}
+
+/// Tests that the SourceRefs of a Sauce are in order, without duplicates or unknown URLs
+#[test]
+fn test_sauce_source_refs() {
+	let sauce = SauceBuilder::default()
+		.ext_urls(
+			[
+				"https://www.pixiv.net/member_illust.php?mode=medium&illust_id=61477678",
+				"https://example.com/61477678",
+				"https://danbooru.donmai.us/post/show/3441187",
+				"https://www.pixiv.net/artworks/61477678",
+			]
+			.iter()
+			.map(|url| url.to_string())
+			.collect(),
+		)
+		.build();
+	assert_eq!(sauce.source_refs(), vec![SourceRef::Pixiv(61477678), SourceRef::Danbooru(3441187)]);
+	assert!(SauceBuilder::default().build().source_refs().is_empty());
+}
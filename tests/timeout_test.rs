@@ -0,0 +1,92 @@
+mod common;
+
+use common::{MockResponse, MockServer};
+use rustnao::{ErrType, HandlerBuilder};
+use std::net::TcpListener;
+use std::time::{Duration, Instant};
+
+/// Tests that a search which cannot complete in time fails with a timeout error
+#[test]
+fn test_timeout() {
+	let handle = HandlerBuilder::default().api_key("").timeout(Duration::from_secs(0)).build();
+	let err = handle.get_sauce("https://i.imgur.com/W42kkKS.jpg", None, None).unwrap_err();
+	assert!(matches!(err.kind(), ErrType::Timeout(_)), "{:?}", err);
+	assert!(err.is_retryable());
+}
+
+/// Tests that a per-search timeout overrides the timeout of the Handler
+#[test]
+fn test_timeout_override() {
+	let handle = HandlerBuilder::default().api_key("").timeout(Duration::from_secs(3600)).build();
+	let err = handle
+		.get_sauce_with_timeout("https://i.imgur.com/W42kkKS.jpg", None, None, Duration::from_secs(0))
+		.unwrap_err();
+	assert!(matches!(err.kind(), ErrType::Timeout(_)), "{:?}", err);
+}
+
+/// Tests that invalid parameters are still reported before the timeout applies
+#[test]
+fn test_timeout_invalid_parameters() {
+	let handle = HandlerBuilder::default().timeout(Duration::from_secs(0)).build();
+	let err = handle.get_sauce("https://i.imgur.com/W42kkKS.jpg", Some(1000), None).unwrap_err();
+	assert!(matches!(err.kind(), ErrType::InvalidParameters(_)), "{:?}", err);
+}
+
+/// Tests that a search which is dropped while waiting for its response leaves the state of the Handler untouched, even once the response arrives
+#[test]
+fn test_cancelled_search_keeps_state() {
+	let server = MockServer::start();
+	server.push(MockResponse::empty(3, 99).delay(Duration::from_millis(500)));
+	let handle = HandlerBuilder::default().api_key("test_api_key").api_url(server.url().as_str()).build();
+
+	// The search is polled until the request has been sent, and dropped before the response comes back
+	let search = handle.async_get_sauce("https://i.imgur.com/W42kkKS.jpg", None, None);
+	assert!(async_std::task::block_on(async_std::future::timeout(Duration::from_millis(200), search)).is_err());
+	assert_eq!(server.requests().len(), 1);
+
+	std::thread::sleep(Duration::from_millis(500));
+	assert_eq!(handle.get_short_limit(), 12);
+	assert_eq!(handle.get_current_short_limit(), 12);
+	assert_eq!(handle.get_long_limit(), 200);
+	assert_eq!(handle.get_current_long_limit(), 200);
+
+	server.push(MockResponse::empty(3, 99));
+	assert!(handle.get_sauce("https://i.imgur.com/W42kkKS.jpg", None, None).unwrap().is_empty());
+	assert_eq!(handle.get_current_short_limit(), 3);
+}
+
+/// Tests that the connect timeout applies when the server accepts the connection but never completes the handshake
+#[test]
+fn test_connect_timeout() {
+	let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+	let api_url = format!("https://{}/search.php", listener.local_addr().unwrap());
+	let handle = HandlerBuilder::default()
+		.api_key("test_api_key")
+		.api_url(api_url.as_str())
+		.connect_timeout(Duration::from_millis(300))
+		.build();
+
+	let started = Instant::now();
+	let err = handle.get_sauce("https://i.imgur.com/W42kkKS.jpg", None, None).unwrap_err();
+	assert!(matches!(err.kind(), ErrType::Timeout(_)), "{:?}", err);
+	assert!(started.elapsed() < Duration::from_secs(5), "{:?}", started.elapsed());
+	assert_eq!(handle.get_current_long_limit(), 200);
+	drop(listener);
+}
+
+/// Tests that the Handler can still be used for other searches after a timeout
+#[test]
+fn test_search_after_timeout() {
+	let server = MockServer::start();
+	server.push(MockResponse::empty(0, 0).delay(Duration::from_millis(500)));
+	server.push(MockResponse::empty(3, 99));
+	let handle = HandlerBuilder::default()
+		.api_url(server.url().as_str())
+		.timeout(Duration::from_millis(100))
+		.build();
+	let err = handle.get_sauce("https://i.imgur.com/W42kkKS.jpg", None, None).unwrap_err();
+	assert!(matches!(err.kind(), ErrType::Timeout(_)), "{:?}", err);
+	let result = handle.get_sauce_with_timeout("https://i.imgur.com/W42kkKS.jpg", None, None, Duration::from_secs(5));
+	assert!(result.unwrap().is_empty());
+	assert_eq!(handle.get_current_short_limit(), 3);
+}
@@ -0,0 +1,94 @@
+#![cfg(feature = "tracemoe")]
+
+mod common;
+
+use common::{MockResponse, MockServer};
+use rustnao::{ErrType, ReverseImageSearch, TraceMoe};
+use std::time::Duration;
+
+/// Returns a trace.moe backend that searches the mock server
+fn trace_moe(server: &MockServer) -> TraceMoe {
+	let mut trace_moe = TraceMoe::new();
+	trace_moe.api_url(server.base_url().as_str());
+	trace_moe
+}
+
+/// Tests that the scenes are parsed, whether or not the AniList information is included
+#[test]
+fn test_parse() {
+	let server = MockServer::start();
+	server.push(MockResponse::fixture("tracemoe.json"));
+	let result = trace_moe(&server).search_blocking("https://i.imgur.com/W42kkKS.jpg", None, None).unwrap();
+
+	let request = &server.requests()[0];
+	assert_eq!(request.method, "GET");
+	assert_eq!(request.path, "/search");
+	assert_eq!(request.query("url"), Some("https://i.imgur.com/W42kkKS.jpg"));
+	assert_eq!(request.query("anilistInfo"), Some(""));
+
+	assert_eq!(result.len(), 2);
+	assert_eq!(result[0].site, "Anime");
+	assert_eq!(result[0].index, 21);
+	assert!((result[0].similarity - 94.404).abs() < 0.01);
+	assert_eq!(result[0].title.as_deref(), Some("Nekopara OVA"));
+	assert_eq!(result[0].ext_urls, vec!["https://anilist.co/anime/99939"]);
+	assert!(result[0].thumbnail.starts_with("https://media.trace.moe/image/99939/"));
+	let fields = result[0].additional_fields.as_ref().unwrap();
+	assert_eq!(fields["engine"], "trace.moe");
+	assert_eq!(fields["anilist_id"], 99939);
+	assert_eq!(fields["from"], 97.75);
+	assert_eq!(fields["to"], 98.92);
+	assert!(fields["episode"].is_null());
+
+	assert_eq!(
+		result[1].title.as_deref(),
+		Some("[Ohys-Raws] Gochuumon wa Usagi Desu ka - 03 (BS11 1280x720 x264 AAC).mp4")
+	);
+	assert_eq!(result[1].additional_fields.as_ref().unwrap()["episode"], 3);
+}
+
+/// Tests the episode and time of the scenes
+#[test]
+fn test_timing() {
+	let server = MockServer::start();
+	server.push(MockResponse::fixture("tracemoe.json"));
+	let result = trace_moe(&server).search_blocking("https://i.imgur.com/W42kkKS.jpg", None, None).unwrap();
+
+	assert_eq!(result[0].episode(), None);
+	assert_eq!(result[0].timestamp(), Some(Duration::from_secs_f64(97.75)));
+	assert_eq!(result[1].episode(), Some(3));
+	assert_eq!(result[1].timestamp(), Some(Duration::from_secs_f64(1190.5)));
+	assert_eq!(result[1].video_length(), None);
+}
+
+/// Tests that a local file is uploaded as the body, and the results are limited
+#[test]
+fn test_file_search() {
+	let server = MockServer::start();
+	server.push(MockResponse::fixture("tracemoe.json"));
+	let result = trace_moe(&server).search_blocking("./tests/test.jpg", None, Some(90.0)).unwrap();
+	assert_eq!(result.len(), 1);
+
+	let request = &server.requests()[0];
+	assert_eq!(request.method, "POST");
+	assert_eq!(request.header("content-type"), Some("image/jpeg"));
+	assert_eq!(request.body, std::fs::read("./tests/test.jpg").unwrap());
+}
+
+/// Tests that errors reported by trace.moe are returned
+#[test]
+fn test_errors() {
+	let server = MockServer::start();
+	server.push(MockResponse::json(r#"{"error": "Failed to fetch image"}"#));
+	server.push(MockResponse::status(402, r#"{"error": "Search quota depleted"}"#));
+	let trace_moe = trace_moe(&server);
+
+	let err = trace_moe.search_blocking("https://i.imgur.com/W42kkKS.jpg", None, None).unwrap_err();
+	assert_eq!(err.kind(), &ErrType::InvalidRequest("Failed to fetch image".to_string()));
+	let err = trace_moe.search_blocking("https://i.imgur.com/W42kkKS.jpg", None, None).unwrap_err();
+	assert!(
+		matches!(err.kind(), ErrType::InvalidRequest(message) if message.contains("402")),
+		"{:?}",
+		err
+	);
+}
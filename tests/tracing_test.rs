@@ -0,0 +1,127 @@
+#![cfg(feature = "tracing")]
+
+mod common;
+
+use common::{MockResponse, MockServer};
+use rustnao::HandlerBuilder;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+
+const PIXIV_DANBOORU: &str = include_str!("fixtures/pixiv_danbooru.json");
+
+/// A subscriber that records every span and event as a line of text
+#[derive(Default, Clone)]
+struct Recorder {
+	lines: Arc<Mutex<Vec<String>>>,
+	next_id: Arc<AtomicU64>,
+}
+
+/// Tests that the HTTP client does not record the request, whose URL contains the API key, in its own spans and events
+#[test]
+fn test_client_does_not_trace_api_key() {
+	let server = MockServer::start();
+	server.push(MockResponse::fixture("pixiv_danbooru.json"));
+	let recorder = Recorder::default();
+	tracing::subscriber::with_default(recorder.clone(), || {
+		let handle = HandlerBuilder::default().api_key("secret_api_key").api_url(server.url().as_str()).build();
+		assert_eq!(handle.get_sauce("https://i.imgur.com/W42kkKS.jpg", None, None).unwrap().len(), 3);
+	});
+
+	assert_eq!(server.requests()[0].query("api_key"), Some("secret_api_key"));
+	let lines = recorder.lines();
+	assert!(lines.iter().any(|line| line.starts_with("span search")), "{:?}", lines);
+	assert!(lines.iter().all(|line| !line.contains("secret_api_key")), "{:?}", lines);
+}
+
+/// Collects the fields of a span or event
+struct Fields(String);
+
+impl Visit for Fields {
+	fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+		self.0.push_str(format!(" {}={:?}", field.name(), value).as_str());
+	}
+}
+
+impl Recorder {
+	fn lines(&self) -> Vec<String> {
+		self.lines.lock().unwrap().clone()
+	}
+}
+
+impl Subscriber for Recorder {
+	fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+		true
+	}
+
+	fn new_span(&self, span: &Attributes<'_>) -> Id {
+		let mut fields = Fields(format!("span {}", span.metadata().name()));
+		span.record(&mut fields);
+		self.lines.lock().unwrap().push(fields.0);
+		Id::from_u64(self.next_id.fetch_add(1, Ordering::SeqCst) + 1)
+	}
+
+	fn record(&self, _span: &Id, values: &Record<'_>) {
+		let mut fields = Fields("record".to_string());
+		values.record(&mut fields);
+		self.lines.lock().unwrap().push(fields.0);
+	}
+
+	fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+	fn event(&self, event: &Event<'_>) {
+		let mut fields = Fields(format!("event {}", event.metadata().level()));
+		event.record(&mut fields);
+		self.lines.lock().unwrap().push(fields.0);
+	}
+
+	fn enter(&self, _span: &Id) {}
+
+	fn exit(&self, _span: &Id) {}
+}
+
+/// Tests that the results dropped by each filter are recorded
+#[test]
+fn test_filtered_results() {
+	let recorder = Recorder::default();
+	tracing::subscriber::with_default(recorder.clone(), || {
+		let server = MockServer::start();
+		server.push(MockResponse::json(PIXIV_DANBOORU));
+		let handle = HandlerBuilder::default().api_url(server.url().as_str()).build();
+		let result = handle.get_sauce("https://i.imgur.com/W42kkKS.jpg", None, Some(90.0)).unwrap();
+		assert_eq!(result.len(), 2);
+	});
+
+	let lines = recorder.lines();
+	let filtered = lines.iter().find(|line| line.contains("filtered results")).unwrap();
+	assert!(filtered.contains("received=3"), "{}", filtered);
+	assert!(filtered.contains("returned=2"), "{}", filtered);
+	assert!(filtered.contains("below_similarity=1"), "{}", filtered);
+	assert!(filtered.contains("empty_urls=0"), "{}", filtered);
+}
+
+/// Tests that searches are recorded in spans without the API key
+#[test]
+fn test_search_span_redacts_api_key() {
+	let recorder = Recorder::default();
+	tracing::subscriber::with_default(recorder.clone(), || {
+		let handle = HandlerBuilder::default()
+			.api_key("secret_api_key")
+			.timeout(Duration::from_secs(0))
+			.build();
+		assert!(handle.get_sauce("https://i.imgur.com/W42kkKS.jpg", Some(5), None).is_err());
+	});
+
+	let lines = recorder.lines();
+	assert!(lines.iter().any(|line| line.starts_with("span search")), "{:?}", lines);
+	assert!(lines.iter().any(|line| line.starts_with("span attempt")), "{:?}", lines);
+	let parameters = lines.iter().find(|line| line.contains("generated request URL")).unwrap();
+	assert!(parameters.contains("api_key=<redacted>"), "{}", parameters);
+	assert!(parameters.contains("numres=5"), "{}", parameters);
+	assert!(lines.iter().any(|line| line.contains("search failed")), "{:?}", lines);
+	assert!(lines.iter().all(|line| !line.contains("secret_api_key")), "{:?}", lines);
+}
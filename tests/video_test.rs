@@ -0,0 +1,78 @@
+mod common;
+
+use common::{MockResponse, MockServer};
+use rustnao::{Handler, HandlerBuilder, Sauce, SauceBuilder};
+use std::time::Duration;
+
+const ANIME: &str = include_str!("fixtures/anime.json");
+
+/// Creates a result from the given index with the given additional fields
+fn sauce(index: u32, additional_fields: serde_json::Value) -> Sauce {
+	SauceBuilder::default().index(index).additional_fields(additional_fields).build()
+}
+
+/// Tests the episode, year, time and AniDB ID of an anime result from a SauceNAO response
+#[test]
+fn test_anime_result() {
+	let server = MockServer::start();
+	server.push(MockResponse::json(ANIME));
+	let handle = HandlerBuilder::default().api_url(server.url().as_str()).build();
+	let result = handle.get_sauce("https://i.imgur.com/W42kkKS.jpg", None, None).unwrap();
+	let sauce = &result[0];
+	assert_eq!(sauce.episode(), Some(3));
+	assert_eq!(sauce.year(), Some(2017));
+	assert_eq!(sauce.timestamp(), Some(Duration::from_secs(12 * 60 + 34)));
+	assert_eq!(sauce.video_length(), Some(Duration::from_secs(24 * 60)));
+	assert_eq!(sauce.anidb_id(), Some(12345));
+	assert_eq!(sauce.imdb_id(), None);
+}
+
+/// Tests the shapes of time, episode and year that the video indexes give
+#[test]
+fn test_video_fields() {
+	let movie = sauce(
+		Handler::MOVIES,
+		serde_json::json!({ "part": "", "year": "1994-1995", "est_time": "1:02:03 - 1:02:05 / 2:22:00", "imdb_id": "tt0111161" }),
+	);
+	assert_eq!(movie.episode(), None);
+	assert_eq!(movie.year(), Some(1994));
+	assert_eq!(movie.timestamp(), Some(Duration::from_secs(3723)));
+	assert_eq!(movie.video_length(), Some(Duration::from_secs(8520)));
+	assert_eq!(movie.imdb_id().as_deref(), Some("tt0111161"));
+
+	let show = SauceBuilder::default()
+		.index(Handler::SHOWS)
+		.ext_urls(vec!["https://www.imdb.com/title/tt0903747/".to_string()])
+		.additional_fields(serde_json::json!({ "part": "Episode 12", "est_time": "12:34" }))
+		.build();
+	assert_eq!(show.episode(), Some(12));
+	assert_eq!(show.timestamp(), Some(Duration::from_secs(754)));
+	assert_eq!(show.video_length(), None);
+	assert_eq!(show.imdb_id().as_deref(), Some("tt0903747"));
+
+	let h_anime = sauce(Handler::H_ANIME, serde_json::json!({ "anidb_aid": "4037", "est_time": "not a time" }));
+	assert_eq!(h_anime.anidb_id(), Some(4037));
+	assert_eq!(h_anime.timestamp(), None);
+
+	// trace.moe gives the time in seconds, which may not fit in a Duration
+	assert_eq!(
+		sauce(Handler::ANIME, serde_json::json!({ "from": 97.75 })).timestamp(),
+		Some(Duration::from_millis(97750))
+	);
+	assert_eq!(sauce(Handler::ANIME, serde_json::json!({ "from": 1e300 })).timestamp(), None);
+	assert_eq!(sauce(Handler::ANIME, serde_json::json!({ "from": -1.0 })).timestamp(), None);
+}
+
+/// Tests that the timing fields are ignored outside of the video indexes, where they mean something else
+#[test]
+fn test_other_indexes() {
+	let magazine = sauce(
+		Handler::H_MAGAZINES,
+		serde_json::json!({ "part": "Vol. 3", "year": "2017", "est_time": "00:12:34 / 00:24:00" }),
+	);
+	assert_eq!(magazine.episode(), None);
+	assert_eq!(magazine.year(), None);
+	assert_eq!(magazine.timestamp(), None);
+	assert_eq!(magazine.video_length(), None);
+	assert_eq!(SauceBuilder::default().build().anidb_id(), None);
+}